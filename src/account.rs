@@ -0,0 +1,635 @@
+//! Account level resources: resource policies, subscription destinations,
+//! saved query definitions and account policies.
+//!
+//! Unlike groups these are not kept in the storage backend. They live
+//! beside it in the `Context`, and are part of its snapshots.
+
+use crate::actions::ServiceError;
+use crate::filter::Pattern;
+use crate::protection::Masker;
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The region and account ID used in the ARNs the mock makes up.
+pub const REGION: &str = "us-east-1";
+pub const ACCOUNT_ID: &str = "123456789012";
+
+/// The name of the group `identifier` refers to, given either a name or
+/// an ARN.
+pub(crate) fn group_name(identifier: &str) -> &str {
+    match identifier.find(":log-group:") {
+        Some(i) => identifier[i + ":log-group:".len()..].trim_end_matches(":*"),
+        None => identifier,
+    }
+}
+
+/// The largest policy document, in bytes.
+const MAX_POLICY_SIZE: usize = 5120;
+
+/// The largest account policy document, in bytes.
+const MAX_ACCOUNT_POLICY_SIZE: usize = 30720;
+
+/// How many resource policies an account may have.
+const MAX_RESOURCE_POLICIES: usize = 10;
+
+/// The default and largest page size of most describe actions.
+const MAX_RESULTS: usize = 50;
+
+/// The default and largest page size of `DescribeQueryDefinitions`.
+const MAX_QUERY_DEFINITIONS: usize = 1000;
+
+/// The account policy types the mock supports.
+pub(crate) const DATA_PROTECTION_POLICY: &str = "DATA_PROTECTION_POLICY";
+pub(crate) const SUBSCRIPTION_FILTER_POLICY: &str = "SUBSCRIPTION_FILTER_POLICY";
+
+/// How many log groups or prefixes selection criteria may list.
+const MAX_SELECTED: usize = 50;
+
+const MAX_QUERY_NAME: usize = 255;
+const MAX_QUERY_STRING: usize = 10000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Account {
+    #[serde(default)]
+    pub resource_policies: BTreeMap<String, ResourcePolicy>,
+    #[serde(default)]
+    pub destinations: BTreeMap<String, Destination>,
+    /// Query definitions by ID. IDs are handed out in order, so this is
+    /// also the order they were created in.
+    #[serde(default)]
+    pub query_definitions: BTreeMap<String, QueryDefinition>,
+    #[serde(default)]
+    pub last_query_definition: u64,
+    /// Account policies by type. An account has at most one of each.
+    #[serde(default)]
+    pub account_policies: BTreeMap<String, StoredPolicy>,
+}
+
+/// An account policy, with its selection criteria and, for a data
+/// protection policy, its document compiled when it was put or loaded.
+#[derive(Debug, Clone)]
+pub struct StoredPolicy {
+    pub policy: AccountPolicy,
+    selection: Selection,
+    pub(crate) masker: Option<Arc<Masker>>,
+}
+
+impl Serialize for StoredPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.policy.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StoredPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let policy = AccountPolicy::deserialize(deserializer)?;
+
+        let criteria = policy.selection_criteria.as_ref().map(String::as_str);
+        let selection = Selection::parse(criteria)
+            .map_err(|_| <D::Error as de::Error>::custom("invalid selectionCriteria"))?;
+
+        let masker = match policy.policy_type.as_ref().map(String::as_str) {
+            Some(DATA_PROTECTION_POLICY) => {
+                let document = policy.policy_document.as_ref().map_or("", String::as_str);
+                let masker = Masker::parse(document).map_err(<D::Error as de::Error>::custom)?;
+                Some(Arc::new(masker))
+            }
+            _ => None,
+        };
+
+        Ok(StoredPolicy {
+            policy,
+            selection,
+            masker,
+        })
+    }
+}
+
+/// Check that `document` is a JSON object no larger than the service
+/// allows.
+fn check_policy(name: &str, document: &str) -> Result<(), ServiceError> {
+    if document.len() > MAX_POLICY_SIZE {
+        return Err(ServiceError::LimitExceeded(format!(
+            "{} must be at most {} bytes",
+            name, MAX_POLICY_SIZE
+        )));
+    }
+
+    match serde_json::from_str::<serde_json::Value>(document) {
+        Ok(serde_json::Value::Object(_)) => Ok(()),
+        _ => Err(ServiceError::InvalidParameter(format!(
+            "{} is not a valid JSON policy document",
+            name
+        ))),
+    }
+}
+
+/// The groups an account policy applies to, from its `selectionCriteria`:
+/// `LogGroupName NOT IN ["a", "b"]` or `LogGroupNamePrefix IN ["c"]`.
+#[derive(Debug, Clone)]
+enum Selection {
+    All,
+    Except(Vec<String>),
+    Prefixed(Vec<String>),
+}
+
+impl Selection {
+    fn parse(criteria: Option<&str>) -> Result<Selection, ServiceError> {
+        let criteria = match criteria {
+            Some(criteria) if !criteria.trim().is_empty() => criteria,
+            _ => return Ok(Selection::All),
+        };
+
+        let invalid =
+            || ServiceError::InvalidParameter(format!("Invalid selectionCriteria: {}", criteria));
+
+        let open = criteria.find('[').ok_or_else(invalid)?;
+        let names = serde_json::from_str::<Vec<String>>(&criteria[open..])
+            .ok()
+            .filter(|names| !names.is_empty() && names.len() <= MAX_SELECTED)
+            .ok_or_else(invalid)?;
+
+        let condition = criteria[..open].split_whitespace().collect::<Vec<_>>();
+        match condition.as_slice() {
+            ["LogGroupName", "NOT", "IN"] => Ok(Selection::Except(names)),
+            ["LogGroupNamePrefix", "IN"] => Ok(Selection::Prefixed(names)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn selects(&self, group: &str) -> bool {
+        match self {
+            Selection::All => true,
+            Selection::Except(names) => !names.iter().any(|name| name == group),
+            Selection::Prefixed(prefixes) => prefixes.iter().any(|p| group.starts_with(p.as_str())),
+        }
+    }
+}
+
+/// The account policy of type `policy_type` if it applies to `group`.
+pub(crate) fn account_policy(
+    context: &Context,
+    policy_type: &str,
+    group: &str,
+) -> Option<StoredPolicy> {
+    let account = lock(&context.account);
+    account
+        .account_policies
+        .get(policy_type)
+        .filter(|stored| stored.selection.selects(group))
+        .cloned()
+}
+
+/// Check a `SUBSCRIPTION_FILTER_POLICY` document, which holds the same
+/// settings as a `PutSubscriptionFilter` request.
+fn check_subscription_policy(document: &str) -> Result<(), String> {
+    let policy = match serde_json::from_str::<serde_json::Value>(document) {
+        Ok(serde_json::Value::Object(policy)) => policy,
+        _ => return Err("The policy document is not a JSON object".into()),
+    };
+
+    if !policy
+        .get("DestinationArn")
+        .map_or(false, |arn| arn.is_string())
+    {
+        return Err("The policy must have a DestinationArn".into());
+    }
+
+    if let Some(pattern) = policy.get("FilterPattern") {
+        let pattern = pattern.as_str().ok_or("FilterPattern must be a string")?;
+        pattern
+            .parse::<Pattern>()
+            .map_err(|e| format!("Invalid filter pattern: {}", e))?;
+    }
+
+    match policy.get("Distribution").map(|d| d.as_str()) {
+        None | Some(Some("ByLogStream")) | Some(Some("Random")) => Ok(()),
+        Some(_) => Err("Distribution must be ByLogStream or Random".into()),
+    }
+}
+
+/// One page of at most `limit`, or `max`, `items`, which are ordered by
+/// key, and the token for the next. Tokens are the key of the last item
+/// returned.
+pub(crate) fn page<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = (&'a String, &'a T)>,
+    limit: Option<i64>,
+    max: usize,
+    next_token: Option<&String>,
+) -> Result<(Vec<T>, Option<String>), ServiceError> {
+    let limit = match limit {
+        None => max,
+        Some(limit) if limit >= 1 && limit as usize <= max => limit as usize,
+        Some(_) => {
+            return Err(ServiceError::InvalidParameter(format!(
+                "limit must be between 1 and {}",
+                max
+            )));
+        }
+    };
+
+    let mut items = items
+        .filter(|(name, _)| next_token.map_or(true, |token| *name > token))
+        .take(limit + 1)
+        .collect::<Vec<_>>();
+
+    let next_token = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|(name, _)| (*name).clone())
+    } else {
+        None
+    };
+
+    Ok((
+        items.into_iter().map(|(_, item)| item.clone()).collect(),
+        next_token,
+    ))
+}
+
+pub(crate) fn json<T: Serialize>(value: &T) -> Response {
+    Response::new(Body::from(serde_json::to_vec(value).unwrap()))
+}
+
+pub fn put_resource_policy(
+    context: &Context,
+    request: PutResourcePolicyRequest,
+) -> Result<Response, ServiceError> {
+    let name = request
+        .policy_name
+        .ok_or_else(|| ServiceError::InvalidParameter("policyName is required".into()))?;
+    let document = request
+        .policy_document
+        .ok_or_else(|| ServiceError::InvalidParameter("policyDocument is required".into()))?;
+    check_policy("policyDocument", &document)?;
+
+    let mut account = lock(&context.account);
+    let policies = &mut account.resource_policies;
+
+    if !policies.contains_key(&name) && policies.len() >= MAX_RESOURCE_POLICIES {
+        return Err(ServiceError::LimitExceeded(format!(
+            "An account may have at most {} resource policies",
+            MAX_RESOURCE_POLICIES
+        )));
+    }
+
+    let policy = ResourcePolicy {
+        last_updated_time: Some(context.clock().now()),
+        policy_document: Some(document),
+        policy_name: Some(name.clone()),
+    };
+    policies.insert(name, policy.clone());
+
+    Ok(json(&PutResourcePolicyResponse {
+        resource_policy: Some(policy),
+    }))
+}
+
+pub fn describe_resource_policies(
+    context: &Context,
+    request: DescribeResourcePoliciesRequest,
+) -> Result<Response, ServiceError> {
+    let account = lock(&context.account);
+    let (policies, next_token) = page(
+        account.resource_policies.iter(),
+        request.limit,
+        MAX_RESULTS,
+        request.next_token.as_ref(),
+    )?;
+
+    Ok(json(&DescribeResourcePoliciesResponse {
+        next_token,
+        resource_policies: Some(policies),
+    }))
+}
+
+pub fn delete_resource_policy(
+    context: &Context,
+    request: DeleteResourcePolicyRequest,
+) -> Result<Response, ServiceError> {
+    let name = request.policy_name.unwrap_or_default();
+
+    match lock(&context.account).resource_policies.remove(&name) {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(ServiceError::NotFound(format!(
+            "Policy with name [{}] does not exist",
+            name
+        ))),
+    }
+}
+
+pub fn put_destination(
+    context: &Context,
+    request: PutDestinationRequest,
+) -> Result<Response, ServiceError> {
+    let name = request.destination_name;
+    if name.is_empty() || name.len() > 512 || name.contains(':') || name.contains('*') {
+        return Err(ServiceError::InvalidParameter(format!(
+            "Invalid destination name: {}",
+            name
+        )));
+    }
+
+    let mut account = lock(&context.account);
+    let now = context.clock().now();
+
+    // Putting an existing destination updates its target, keeping its
+    // policy and creation time.
+    let destination = account
+        .destinations
+        .entry(name.clone())
+        .or_insert_with(|| Destination {
+            arn: Some(format!(
+                "arn:aws:logs:{}:{}:destination:{}",
+                REGION, ACCOUNT_ID, name
+            )),
+            creation_time: Some(now),
+            destination_name: Some(name.clone()),
+            ..Default::default()
+        });
+    destination.role_arn = Some(request.role_arn);
+    destination.target_arn = Some(request.target_arn);
+
+    Ok(json(&PutDestinationResponse {
+        destination: Some(destination.clone()),
+    }))
+}
+
+pub fn put_destination_policy(
+    context: &Context,
+    request: PutDestinationPolicyRequest,
+) -> Result<Response, ServiceError> {
+    check_policy("accessPolicy", &request.access_policy)?;
+
+    let mut account = lock(&context.account);
+    match account.destinations.get_mut(&request.destination_name) {
+        Some(destination) => {
+            destination.access_policy = Some(request.access_policy);
+            Ok(Response::new(Body::empty()))
+        }
+        None => Err(ServiceError::NotFound(format!(
+            "The specified destination does not exist: {}",
+            request.destination_name
+        ))),
+    }
+}
+
+pub fn describe_destinations(
+    context: &Context,
+    request: DescribeDestinationsRequest,
+) -> Result<Response, ServiceError> {
+    let prefix = request.destination_name_prefix.unwrap_or_default();
+
+    let account = lock(&context.account);
+    let matching = account
+        .destinations
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix.as_str()));
+    let (destinations, next_token) = page(
+        matching,
+        request.limit,
+        MAX_RESULTS,
+        request.next_token.as_ref(),
+    )?;
+
+    Ok(json(&DescribeDestinationsResponse {
+        destinations: Some(destinations),
+        next_token,
+    }))
+}
+
+pub fn delete_destination(
+    context: &Context,
+    request: DeleteDestinationRequest,
+) -> Result<Response, ServiceError> {
+    match lock(&context.account)
+        .destinations
+        .remove(&request.destination_name)
+    {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(ServiceError::NotFound(format!(
+            "The specified destination does not exist: {}",
+            request.destination_name
+        ))),
+    }
+}
+
+pub fn put_query_definition(
+    context: &Context,
+    request: PutQueryDefinitionRequest,
+) -> Result<Response, ServiceError> {
+    if request.name.is_empty() || request.name.len() > MAX_QUERY_NAME {
+        return Err(ServiceError::InvalidParameter(format!(
+            "name must be between 1 and {} characters",
+            MAX_QUERY_NAME
+        )));
+    }
+
+    if request.query_string.is_empty() || request.query_string.len() > MAX_QUERY_STRING {
+        return Err(ServiceError::InvalidParameter(format!(
+            "queryString must be between 1 and {} characters",
+            MAX_QUERY_STRING
+        )));
+    }
+
+    let mut account = lock(&context.account);
+
+    let id = match request.query_definition_id {
+        Some(id) => {
+            if !account.query_definitions.contains_key(&id) {
+                return Err(ServiceError::NotFound(format!(
+                    "The specified query definition does not exist: {}",
+                    id
+                )));
+            }
+            id
+        }
+        None => {
+            account.last_query_definition += 1;
+            format!(
+                "00000000-0000-4000-8000-{:012x}",
+                account.last_query_definition
+            )
+        }
+    };
+
+    let definition = QueryDefinition {
+        last_modified: Some(context.clock().now()),
+        log_group_names: request.log_group_names,
+        name: Some(request.name),
+        query_definition_id: Some(id.clone()),
+        query_string: Some(request.query_string),
+    };
+    account.query_definitions.insert(id.clone(), definition);
+
+    Ok(json(&PutQueryDefinitionResponse {
+        query_definition_id: Some(id),
+    }))
+}
+
+pub fn describe_query_definitions(
+    context: &Context,
+    request: DescribeQueryDefinitionsRequest,
+) -> Result<Response, ServiceError> {
+    let prefix = request.query_definition_name_prefix.unwrap_or_default();
+
+    let account = lock(&context.account);
+    let matching = account.query_definitions.iter().filter(|(_, definition)| {
+        let name = definition.name.as_ref().map_or("", String::as_str);
+        name.starts_with(prefix.as_str())
+    });
+    let (definitions, next_token) = page(
+        matching,
+        request.max_results,
+        MAX_QUERY_DEFINITIONS,
+        request.next_token.as_ref(),
+    )?;
+
+    Ok(json(&DescribeQueryDefinitionsResponse {
+        next_token,
+        query_definitions: Some(definitions),
+    }))
+}
+
+pub fn delete_query_definition(
+    context: &Context,
+    request: DeleteQueryDefinitionRequest,
+) -> Result<Response, ServiceError> {
+    let id = request.query_definition_id;
+
+    match lock(&context.account).query_definitions.remove(&id) {
+        Some(_) => Ok(json(&DeleteQueryDefinitionResponse {
+            success: Some(true),
+        })),
+        None => Err(ServiceError::NotFound(format!(
+            "The specified query definition does not exist: {}",
+            id
+        ))),
+    }
+}
+
+pub fn put_account_policy(
+    context: &Context,
+    request: PutAccountPolicyRequest,
+) -> Result<Response, ServiceError> {
+    if request.policy_document.len() > MAX_ACCOUNT_POLICY_SIZE {
+        return Err(ServiceError::LimitExceeded(format!(
+            "policyDocument must be at most {} bytes",
+            MAX_ACCOUNT_POLICY_SIZE
+        )));
+    }
+
+    let masker = match request.policy_type.as_str() {
+        DATA_PROTECTION_POLICY => Masker::parse(&request.policy_document).map(Some),
+        SUBSCRIPTION_FILTER_POLICY => {
+            check_subscription_policy(&request.policy_document).map(|_| None)
+        }
+        policy_type => {
+            return Err(ServiceError::InvalidParameter(format!(
+                "Unsupported policyType: {}",
+                policy_type
+            )));
+        }
+    };
+    let masker = masker.map_err(ServiceError::InvalidParameter)?;
+
+    let scope = request.scope.unwrap_or_else(|| "ALL".to_string());
+    if scope != "ALL" {
+        return Err(ServiceError::InvalidParameter(format!(
+            "Invalid scope: {}",
+            scope
+        )));
+    }
+
+    let criteria = request.selection_criteria.as_ref().map(String::as_str);
+    let selection = Selection::parse(criteria)?;
+
+    let mut account = lock(&context.account);
+    let policies = &mut account.account_policies;
+
+    if let Some(existing) = policies.get(&request.policy_type) {
+        if existing.policy.policy_name.as_ref() != Some(&request.policy_name) {
+            return Err(ServiceError::LimitExceeded(format!(
+                "An account may have only one {}",
+                request.policy_type
+            )));
+        }
+    }
+
+    let policy = AccountPolicy {
+        account_id: Some(ACCOUNT_ID.to_string()),
+        last_updated_time: Some(context.clock().now()),
+        policy_document: Some(request.policy_document),
+        policy_name: Some(request.policy_name),
+        policy_type: Some(request.policy_type.clone()),
+        scope: Some(scope),
+        selection_criteria: request.selection_criteria,
+    };
+    let stored = StoredPolicy {
+        policy: policy.clone(),
+        selection,
+        masker: masker.map(Arc::new),
+    };
+    policies.insert(request.policy_type, stored);
+
+    Ok(json(&PutAccountPolicyResponse {
+        account_policy: Some(policy),
+    }))
+}
+
+pub fn describe_account_policies(
+    context: &Context,
+    request: DescribeAccountPoliciesRequest,
+) -> Result<Response, ServiceError> {
+    let ours = request
+        .account_identifiers
+        .as_ref()
+        .map_or(true, |ids| ids.iter().any(|id| id == ACCOUNT_ID));
+
+    let account = lock(&context.account);
+    let policies = account
+        .account_policies
+        .get(&request.policy_type)
+        .map(|stored| &stored.policy)
+        .filter(|policy| {
+            let name = policy.policy_name.as_ref();
+            ours && request
+                .policy_name
+                .as_ref()
+                .map_or(true, |n| Some(n) == name)
+        })
+        .cloned()
+        .into_iter()
+        .collect();
+
+    Ok(json(&DescribeAccountPoliciesResponse {
+        account_policies: Some(policies),
+        next_token: None,
+    }))
+}
+
+pub fn delete_account_policy(
+    context: &Context,
+    request: DeleteAccountPolicyRequest,
+) -> Result<Response, ServiceError> {
+    let mut account = lock(&context.account);
+    let policies = &mut account.account_policies;
+
+    let named = policies.get(&request.policy_type).map_or(false, |stored| {
+        stored.policy.policy_name.as_ref() == Some(&request.policy_name)
+    });
+
+    if named {
+        policies.remove(&request.policy_type);
+        Ok(Response::new(Body::empty()))
+    } else {
+        Err(ServiceError::NotFound(format!(
+            "Policy with name [{}] does not exist",
+            request.policy_name
+        )))
+    }
+}
@@ -1,28 +1,35 @@
-use crate::streams::{Group, Stream};
+use crate::account::page;
+use crate::filter::Pattern;
+use crate::limits::event_size;
+use crate::metrics::{ErrorType, Ingested};
+use crate::protection::masker;
+use crate::streams::{
+    lock, read, EventKey, Group, LogEvent, LogGroupClass, StorageError, Stream, DAY,
+};
+use crate::transform::transformer;
 use crate::types::*;
 use crate::{Body, Context, Response};
 use serde_json::json;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::ops::Bound;
 
 pub fn describe_streams(
-    context: &mut Context,
+    context: &Context,
     request: DescribeLogStreamsRequest,
 ) -> Result<Response, ServiceError> {
-    let group = context.groups.get(&request.log_group_name);
-
-    let streams = if let Some(group) = group {
-        group.streams.clone()
-    } else {
-        return Err(ServiceError::NotFound("Group not found".into()));
-    };
+    let streams = context.streams(&request.log_group_name)?;
+    let streams = streams.iter().map(|stream| lock(stream));
 
     let streams = if let Some(prefix) = request.log_stream_name_prefix {
         streams
-            .into_iter()
             .filter(|stream| stream.name.starts_with(prefix.as_str()))
-            .map(|e| e.into())
+            .map(|e| (&*e).into())
             .collect()
     } else {
-        streams.into_iter().map(|e| e.into()).collect()
+        streams.map(|e| (&*e).into()).collect()
     };
 
     let res = DescribeLogStreamsResponse {
@@ -35,175 +42,612 @@ pub fn describe_streams(
 }
 
 pub fn describe_groups(
-    context: &mut Context,
+    context: &Context,
     request: DescribeLogGroupsRequest,
 ) -> Result<Response, ServiceError> {
-    if let Some(name) = &request.log_group_name_prefix {
-        if let Some(group) = context.groups.get(name) {
-            let group = LogGroup {
-                log_group_name: Some(group.name.clone()),
-                ..Default::default()
-            };
+    let class = match &request.log_group_class {
+        Some(class) => Some(
+            class
+                .parse::<LogGroupClass>()
+                .map_err(ServiceError::InvalidParameter)?,
+        ),
+        None => None,
+    };
+    let prefix = request.log_group_name_prefix.unwrap_or_default();
+
+    let groups = context
+        .groups()
+        .iter()
+        .filter_map(|group| {
+            let mut summary = {
+                let group = lock(group);
+
+                if !group.name.starts_with(prefix.as_str())
+                    || class.map_or(false, |class| class != group.log_group_class)
+                {
+                    return None;
+                }
 
-            let response = DescribeLogGroupsResponse {
-                log_groups: vec![group].into(),
-                next_token: Some("token".into()),
+                LogGroup {
+                    creation_time: group.creation_time,
+                    data_protection_status: group
+                        .data_protection_policy
+                        .as_ref()
+                        .map(|_| "ACTIVATED".to_string()),
+                    log_group_class: Some(group.log_group_class.as_str().to_string()),
+                    log_group_name: Some(group.name.clone()),
+                    metric_filter_count: Some(group.metric_filters.len() as i64),
+                    retention_in_days: group.retention_in_days,
+                    ..Default::default()
+                }
             };
 
-            let body = serde_json::to_vec(&response).unwrap();
-            Ok(Response::new(Body::from(body)))
-        } else {
-            Err(ServiceError::NotFound(name.clone()))
-        }
-    } else {
-        unreachable!()
-    }
+            // A group deleted since it was listed is skipped.
+            let name = summary.log_group_name.clone()?;
+            let usage = context.group_usage(&name).ok()?;
+            summary.stored_bytes = Some(usage.stored_bytes as i64);
+
+            Some((name, summary))
+        })
+        .collect::<Vec<_>>();
+
+    let (groups, next_token) = page(
+        groups.iter().map(|(name, group)| (name, group)),
+        request.limit,
+        MAX_GROUPS,
+        request.next_token.as_ref(),
+    )?;
+
+    let response = DescribeLogGroupsResponse {
+        log_groups: Some(groups),
+        next_token,
+    };
+
+    let body = serde_json::to_vec(&response).unwrap();
+    Ok(Response::new(Body::from(body)))
 }
 
 pub fn create_group(
-    context: &mut Context,
+    context: &Context,
     request: CreateLogGroupRequest,
 ) -> Result<Response, ServiceError> {
-    if let None = context.groups.get(&request.log_group_name) {
-        context.groups.insert(
-            request.log_group_name.clone(),
-            Group {
-                name: request.log_group_name,
-                ..Default::default()
-            },
-        );
-        Ok(Response::new(Body::empty()))
-    } else {
-        Err(ServiceError::ResourceAlreadyExistsException)
+    let class = match &request.log_group_class {
+        Some(class) => class
+            .parse::<LogGroupClass>()
+            .map_err(ServiceError::InvalidParameter)?,
+        None => LogGroupClass::Standard,
+    };
+
+    let group = Group {
+        name: request.log_group_name,
+        creation_time: Some(context.clock().now()),
+        tags: request.tags.unwrap_or_default(),
+        log_group_class: class,
+        ..Default::default()
+    };
+    context.create_group(group, context.limits().max_groups)?;
+
+    Ok(Response::new(Body::empty()))
+}
+
+/// Fail unless `group`'s class allows `feature`, which INFREQUENT_ACCESS
+/// groups do not.
+pub(crate) fn supports(group: &Group, feature: &str) -> Result<(), ServiceError> {
+    match group.log_group_class {
+        LogGroupClass::Standard => Ok(()),
+        LogGroupClass::InfrequentAccess => Err(ServiceError::InvalidParameter(format!(
+            "{} is not supported for log groups in the INFREQUENT_ACCESS class: {}",
+            feature, group.name
+        ))),
     }
 }
 
 pub fn create_stream(
-    context: &mut Context,
+    context: &Context,
     request: CreateLogStreamRequest,
 ) -> Result<Response, ServiceError> {
-    if let Some(ref mut group) = context.groups.get_mut(&request.log_group_name) {
-        if let None = group
-            .streams
-            .iter()
-            .find(|e| e.name == request.log_stream_name)
-        {
-            group.streams.push(Stream {
-                name: request.log_stream_name.clone(),
-                logs: Vec::new(),
-            });
-            Ok(Response::new(Body::empty()))
-        } else {
-            Err(ServiceError::ResourceAlreadyExistsException)
-        }
+    context.create_stream(
+        &request.log_group_name,
+        Stream {
+            name: request.log_stream_name,
+            ..Default::default()
+        },
+    )?;
+
+    Ok(Response::new(Body::empty()))
+}
+
+pub fn put_logs(context: &Context, request: PutLogEventsRequest) -> Result<Response, ServiceError> {
+    let now = context.clock().now();
+    let retention = context
+        .group(&request.log_group_name)
+        .and_then(|group| lock(&group).retention_in_days);
+
+    let too_old = |e: &InputLogEvent| e.timestamp < now - MAX_EVENT_AGE;
+    let too_new = |e: &InputLogEvent| e.timestamp > now + MAX_EVENT_LEAD;
+    let expired = |e: &InputLogEvent| match retention {
+        Some(days) => e.timestamp < now - days * DAY,
+        None => false,
+    };
+
+    let events = &request.log_events;
+    let too_old_end = events.iter().rposition(too_old).map(|i| i + 1);
+    let expired_end = events.iter().rposition(expired).map(|i| i + 1);
+    let too_new_start = events.iter().position(too_new);
+
+    let accepted: Vec<_> = request
+        .log_events
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            *i >= too_old_end.unwrap_or(0).max(expired_end.unwrap_or(0))
+                && *i < too_new_start.unwrap_or(events.len())
+        })
+        .map(|(_, e)| e.clone())
+        .collect();
+
+    // Events whose messages the transformer cannot parse are kept as sent.
+    let accepted: Vec<_> = match transformer(context, &request.log_group_name) {
+        Some(transformer) => accepted
+            .into_iter()
+            .map(|mut event| {
+                if let Some(message) = transformer.transform(&event.message) {
+                    event.message = message;
+                }
+                event
+            })
+            .collect(),
+        None => accepted,
+    };
+
+    // Counted as stored, after transforming.
+    let ingested = Ingested {
+        group: request.log_group_name.clone(),
+        events: accepted.len(),
+        bytes: accepted.iter().map(|e| event_size(&e.message)).sum(),
+    };
+
+    let (group, stream) = (&request.log_group_name, &request.log_stream_name);
+    let mirror = read(&context.mirror);
+    let mirrored = mirror.as_ref().map_or(false, |m| m.is_mirrored(group));
+
+    let published = if context.live.is_watched(now) || mirrored {
+        accepted.clone()
     } else {
-        Err(ServiceError::NotFound("Group not found".into()))
+        Vec::new()
+    };
+
+    context.store_events(group, stream, accepted)?;
+
+    if !published.is_empty() {
+        // Tails and the mirror show events the way readers see them.
+        let published = match masker(context, group, None) {
+            Some(masker) => published
+                .into_iter()
+                .map(|mut event| {
+                    if let Cow::Owned(masked) = masker.mask(&event.message) {
+                        event.message = masked;
+                    }
+                    event
+                })
+                .collect(),
+            None => published,
+        };
+
+        context.live.publish(group, stream, &published, now);
+
+        if let Some(mirror) = mirror.as_ref() {
+            mirror.write(group, stream, &published);
+        }
     }
+
+    let rejected = if too_old_end.is_some() || expired_end.is_some() || too_new_start.is_some() {
+        Some(RejectedLogEventsInfo {
+            expired_log_event_end_index: expired_end.map(|i| i as i64),
+            too_new_log_event_start_index: too_new_start.map(|i| i as i64),
+            too_old_log_event_end_index: too_old_end.map(|i| i as i64),
+        })
+    } else {
+        None
+    };
+
+    let res = PutLogEventsResponse {
+        rejected_log_events_info: rejected,
+        ..Default::default()
+    };
+
+    let body = serde_json::to_vec(&res).unwrap();
+    let mut res = Response::new(Body::from(body));
+    res.extensions_mut().insert(ingested);
+    Ok(res)
 }
 
-pub fn put_logs(
-    context: &mut Context,
-    request: PutLogEventsRequest,
+pub fn get_logs(context: &Context, request: GetLogEventsRequest) -> Result<Response, ServiceError> {
+    let stream = context.stream(&request.log_group_name, &request.log_stream_name)?;
+    let masker = masker(context, &request.log_group_name, request.unmask);
+    let stream = lock(&stream);
+
+    let limit = match request.limit {
+        None => MAX_EVENTS,
+        Some(limit) if limit >= 1 && limit as usize <= MAX_EVENTS => limit as usize,
+        Some(_) => {
+            return Err(ServiceError::InvalidParameter(format!(
+                "limit must be between 1 and {}",
+                MAX_EVENTS
+            )))
+        }
+    };
+
+    let token = match &request.next_token {
+        Some(token) => Some(Token::parse(token).ok_or_else(|| {
+            ServiceError::InvalidParameter("The specified nextToken is invalid.".into())
+        })?),
+        None => None,
+    };
+
+    let (start, end) = (request.start_time, request.end_time);
+    let from_head = request.start_from_head.unwrap_or(false);
+
+    let page = match token {
+        Some(Token::Forward(key)) => stream.logs.after(key, end).take(limit).collect(),
+        Some(Token::Backward(key)) => latest(stream.logs.before(key, start), limit),
+        None if from_head => stream.logs.range(start, end).take(limit).collect(),
+        None => latest(stream.logs.range(start, end), limit),
+    };
+
+    let (forward, backward) = match (page.first(), page.last()) {
+        (Some((first, _)), Some((last, _))) => (Token::Forward(**last), Token::Backward(**first)),
+        // Nothing was returned, so hand back tokens that resume from the
+        // same place.
+        _ => match token {
+            Some(Token::Forward(key)) | Some(Token::Backward(key)) => {
+                (Token::Forward(key), Token::Backward(key))
+            }
+            None => (
+                Token::Forward(EventKey::at(start.unwrap_or(i64::min_value()))),
+                Token::Backward(EventKey::at(end.unwrap_or(i64::max_value()))),
+            ),
+        },
+    };
+
+    let logs = page
+        .into_iter()
+        .map(|(_, l)| OutputLogEvent {
+            ingestion_time: Some(l.ingestion_time),
+            message: Some(match &masker {
+                Some(masker) => masker.mask(&l.message).into_owned(),
+                None => l.message.clone(),
+            }),
+            timestamp: Some(l.timestamp),
+        })
+        .collect();
+
+    let res = GetLogEventsResponse {
+        events: Some(logs),
+        next_forward_token: Some(forward.to_string()),
+        next_backward_token: Some(backward.to_string()),
+    };
+
+    let body = serde_json::to_vec(&res).unwrap();
+    Ok(Response::new(Body::from(body)))
+}
+
+pub fn filter_logs(
+    context: &Context,
+    request: FilterLogEventsRequest,
 ) -> Result<Response, ServiceError> {
-    if let Some(ref mut group) = context.groups.get_mut(&request.log_group_name) {
-        if let Some(stream) = group
-            .streams
-            .iter_mut()
-            .find(|e| e.name == request.log_stream_name)
-        {
-            // TODO: validate logs?
-            stream.logs.extend(request.log_events.clone());
-
-            let res = PutLogEventsResponse {
-                ..Default::default()
+    if request.log_stream_names.is_some() && request.log_stream_name_prefix.is_some() {
+        return Err(ServiceError::InvalidParameter(
+            "logStreamNames and logStreamNamePrefix are mutually exclusive".into(),
+        ));
+    }
+
+    let pattern = match &request.filter_pattern {
+        Some(pattern) => pattern
+            .parse::<Pattern>()
+            .map_err(|e| ServiceError::InvalidParameter(e.to_string()))?,
+        None => Pattern::All,
+    };
+
+    let limit = match request.limit {
+        None => MAX_EVENTS,
+        Some(limit) if limit >= 1 && limit as usize <= MAX_EVENTS => limit as usize,
+        Some(_) => {
+            return Err(ServiceError::InvalidParameter(format!(
+                "limit must be between 1 and {}",
+                MAX_EVENTS
+            )))
+        }
+    };
+
+    let resume = match &request.next_token {
+        Some(token) => Some(EventId::parse(token).ok_or_else(|| {
+            ServiceError::InvalidParameter("The specified nextToken is invalid.".into())
+        })?),
+        None => None,
+    };
+
+    // Unlike GetLogEvents, the end time is inclusive.
+    let end = request.end_time.map(|t| t.saturating_add(1));
+
+    // Patterns match the messages as returned, so masked data cannot be
+    // searched for.
+    let masker = masker(context, &request.log_group_name, request.unmask);
+
+    let handles = context.streams(&request.log_group_name)?;
+    let streams = handles
+        .iter()
+        .map(|stream| lock(stream))
+        .filter(
+            |stream| match (&request.log_stream_names, &request.log_stream_name_prefix) {
+                (Some(names), _) => names.contains(&stream.name),
+                (_, Some(prefix)) => stream.name.starts_with(prefix.as_str()),
+                _ => true,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let searched = streams
+        .iter()
+        .map(|stream| SearchedLogStream {
+            log_stream_name: Some(stream.name.clone()),
+            searched_completely: Some(true),
+        })
+        .collect();
+
+    // Each stream is read from where the page starts, and the streams are
+    // merged in `EventId` order, so a page only looks at the events before
+    // its last match no matter how far into the group it is.
+    let upper = end.map_or(Bound::Unbounded, |end| Bound::Excluded(EventKey::at(end)));
+    let mut pending = streams
+        .iter()
+        .map(|stream| {
+            let lower = match &resume {
+                // Events at the resume key come after it in later streams.
+                Some(resume) if stream.name > resume.stream => Bound::Included(resume.key),
+                Some(resume) => Bound::Excluded(resume.key),
+                None => Bound::Unbounded,
             };
 
-            let body = serde_json::to_vec(&res).unwrap();
-            Ok(Response::new(Body::from(body)))
-        } else {
-            Err(ServiceError::NotFound("Stream not found".into()))
+            let lower = match (request.start_time.map(EventKey::at), lower) {
+                (Some(start), Bound::Unbounded) => Bound::Included(start),
+                (Some(start), Bound::Included(key)) | (Some(start), Bound::Excluded(key))
+                    if start > key =>
+                {
+                    Bound::Included(start)
+                }
+                (_, lower) => lower,
+            };
+
+            stream.logs.bounded(lower, upper).peekable()
+        })
+        .collect::<Vec<_>>();
+
+    let mut next = BinaryHeap::new();
+    for (i, events) in pending.iter_mut().enumerate() {
+        if let Some((key, _)) = events.peek() {
+            next.push(Reverse((**key, i)));
         }
-    } else {
-        Err(ServiceError::NotFound("Group not found".into()))
     }
-}
 
-pub fn get_logs(
-    context: &mut Context,
-    request: GetLogEventsRequest,
-) -> Result<Response, ServiceError> {
-    if let Some(ref mut group) = context.groups.get_mut(&request.log_group_name) {
-        if let Some(stream) = group
-            .streams
-            .iter()
-            .find(|e| e.name == request.log_stream_name)
-        {
-            let logs = stream
-                .logs
-                .iter()
-                .filter(|log| {
-                    if let Some(start_time) = request.start_time {
-                        log.timestamp >= start_time
-                    } else {
-                        true
-                    }
-                })
-                .map(|l| OutputLogEvent {
-                    message: Some(l.message.clone()),
-                    timestamp: Some(l.timestamp),
-                    ..Default::default()
-                })
-                .collect();
+    let mut matched = Vec::new();
 
-            let res = GetLogEventsResponse {
-                events: Some(logs),
-                ..Default::default()
+    while matched.len() <= limit {
+        let (key, i) = match next.pop() {
+            Some(Reverse(next)) => next,
+            None => break,
+        };
+
+        let event = match pending[i].next() {
+            Some((_, event)) => event,
+            None => continue,
+        };
+
+        if let Some((key, _)) = pending[i].peek() {
+            next.push(Reverse((**key, i)));
+        }
+
+        let message = match &masker {
+            Some(masker) => masker.mask(&event.message),
+            None => Cow::Borrowed(event.message.as_str()),
+        };
+
+        if pattern.matches(&message) {
+            let id = EventId {
+                key,
+                stream: streams[i].name.clone(),
             };
 
-            let body = serde_json::to_vec(&res).unwrap();
-            Ok(Response::new(Body::from(body)))
-        } else {
-            Err(ServiceError::NotFound("Stream not found".into()))
+            let mut event = event.clone();
+            event.message = message.into_owned();
+            matched.push((id, event));
         }
+    }
+
+    let next_token = if matched.len() > limit {
+        matched.truncate(limit);
+        matched.last().map(|(id, _)| id.to_string())
     } else {
-        Err(ServiceError::NotFound("Group not found".into()))
+        None
+    };
+
+    let events = matched
+        .into_iter()
+        .map(|(id, event)| FilteredLogEvent {
+            event_id: Some(id.to_string()),
+            ingestion_time: Some(event.ingestion_time),
+            log_stream_name: Some(id.stream),
+            message: Some(event.message),
+            timestamp: Some(event.timestamp),
+        })
+        .collect();
+
+    let res = FilterLogEventsResponse {
+        events: Some(events),
+        next_token,
+        searched_log_streams: Some(searched),
+    };
+
+    let body = serde_json::to_vec(&res).unwrap();
+    Ok(Response::new(Body::from(body)))
+}
+
+/// Where an event sits in the interleaved order `FilterLogEvents` returns
+/// events in. This doubles as the event's `eventId` and as the token to
+/// resume after it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct EventId {
+    pub key: EventKey,
+    pub stream: String,
+}
+
+impl EventId {
+    pub fn parse(id: &str) -> Option<EventId> {
+        let mut parts = id.splitn(3, '/');
+
+        let timestamp = parts.next()?.parse().ok()?;
+        let seq = parts.next()?.parse().ok()?;
+        let stream = parts.next()?.to_string();
+
+        Some(EventId {
+            key: EventKey { timestamp, seq },
+            stream,
+        })
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.key.timestamp, self.key.seq, self.stream)
+    }
+}
+
+/// The default and largest page size of `DescribeLogGroups`.
+const MAX_GROUPS: usize = 50;
+
+/// How far in the past `PutLogEvents` accepts events.
+const MAX_EVENT_AGE: i64 = 14 * DAY;
+
+/// How far in the future `PutLogEvents` accepts events.
+const MAX_EVENT_LEAD: i64 = 2 * 60 * 60 * 1000;
+
+/// The most events a single `GetLogEvents` call returns.
+const MAX_EVENTS: usize = 10_000;
+
+/// The last `limit` events of `events`, in ascending order.
+fn latest<'a, I>(events: I, limit: usize) -> Vec<(&'a EventKey, &'a LogEvent)>
+where
+    I: DoubleEndedIterator<Item = (&'a EventKey, &'a LogEvent)>,
+{
+    let mut page = events.rev().take(limit).collect::<Vec<_>>();
+    page.reverse();
+    page
+}
+
+/// A `GetLogEvents` pagination token.
+///
+/// Tokens hold the key of the event they continue from, so resuming is a
+/// single seek no matter how many events were added in the meantime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Forward(EventKey),
+    Backward(EventKey),
+}
+
+impl Token {
+    fn parse(token: &str) -> Option<Token> {
+        let mut parts = token.splitn(3, '/');
+
+        let direction = parts.next()?;
+        let timestamp = parts.next()?.parse().ok()?;
+        let seq = parts.next()?.parse().ok()?;
+        let key = EventKey { timestamp, seq };
+
+        match direction {
+            "f" => Some(Token::Forward(key)),
+            "b" => Some(Token::Backward(key)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Forward(key) => write!(f, "f/{}/{}", key.timestamp, key.seq),
+            Token::Backward(key) => write!(f, "b/{}/{}", key.timestamp, key.seq),
+        }
     }
 }
 
 pub enum ServiceError {
     NotFound(String),
     ResourceAlreadyExistsException,
+    InvalidParameter(String),
+    LimitExceeded(String),
+    Serialization(String),
+    ServiceUnavailable,
+    InternalFailure,
+}
+
+impl From<StorageError> for ServiceError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::GroupNotFound | StorageError::StreamNotFound => {
+                ServiceError::NotFound(e.to_string())
+            }
+            StorageError::GroupAlreadyExists | StorageError::StreamAlreadyExists => {
+                ServiceError::ResourceAlreadyExistsException
+            }
+            StorageError::LimitExceeded(message) => ServiceError::LimitExceeded(message),
+        }
+    }
 }
 
 impl From<ServiceError> for hyper::Response<hyper::Body> {
     fn from(e: ServiceError) -> Self {
         match e {
             ServiceError::NotFound(message) => {
-                let json = json!({
-                    "__type": "ResourceNotFoundException",
-                    "message": message
-                });
-
-                let body = serde_json::to_vec(&json).unwrap();
-
-                hyper::Response::builder()
-                    .status(404)
-                    .body(hyper::Body::from(body))
-                    .unwrap()
+                error_response(404, "ResourceNotFoundException", &message)
             }
             ServiceError::ResourceAlreadyExistsException => {
-                let json = json!({
-                    "__type": "ResourceAlreadyExistsException",
-                    "message": "Resource not found"
-                });
-
-                let body = serde_json::to_vec(&json).unwrap();
-
-                hyper::Response::builder()
-                    .status(404)
-                    .body(hyper::Body::from(body))
-                    .unwrap()
+                error_response(404, "ResourceAlreadyExistsException", "Resource not found")
+            }
+            ServiceError::InvalidParameter(message) => {
+                error_response(400, "InvalidParameterException", &message)
+            }
+            ServiceError::LimitExceeded(message) => {
+                error_response(400, "LimitExceededException", &message)
+            }
+            ServiceError::Serialization(message) => {
+                error_response(400, "SerializationException", &message)
             }
+            ServiceError::ServiceUnavailable => error_response(
+                503,
+                "ServiceUnavailableException",
+                "The service cannot complete the request",
+            ),
+            ServiceError::InternalFailure => error_response(
+                500,
+                "InternalFailure",
+                "The request processing has failed because of an unknown error",
+            ),
         }
     }
 }
+
+pub(crate) fn error_response(status: u16, kind: &str, message: &str) -> Response {
+    let json = json!({
+        "__type": kind,
+        "message": message
+    });
+
+    let body = serde_json::to_vec(&json).unwrap();
+
+    let mut res = hyper::Response::builder()
+        .status(status)
+        .body(hyper::Body::from(body))
+        .unwrap();
+    res.extensions_mut().insert(ErrorType(kind.to_string()));
+    res
+}
@@ -0,0 +1,304 @@
+//! The `/_mock/...` control plane.
+//!
+//! This lives beside the `X-Amz-Target` dispatcher so that test suites in
+//! any language can reset and inspect the mock over plain HTTP.
+
+use crate::faults::Rule;
+use crate::fixture::Timestamp;
+use crate::limits::Limits;
+use crate::quotas::Quota;
+use crate::streams::{lock, Snapshot};
+use crate::ui;
+use crate::{Body, Response, Shared};
+use bytes::Bytes;
+use hyper::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::VecDeque;
+
+pub const PREFIX: &str = "/_mock/";
+
+/// How many requests `/_mock/requests` remembers unless told otherwise.
+pub const DEFAULT_MAX_REQUESTS: usize = 1000;
+
+/// Bookkeeping about the requests the mock has seen.
+#[derive(Debug)]
+pub struct Admin {
+    /// The most recent requests, oldest first.
+    pub requests: VecDeque<ReceivedRequest>,
+    /// How many requests to remember; older ones are forgotten. Zero turns
+    /// capture off.
+    pub max_requests: usize,
+    /// When set every AWS action fails with `ServiceUnavailableException`,
+    /// regardless of the fault injection rules.
+    pub faults: bool,
+    /// What the mock was configured with before serving, restored by reset.
+    pub baseline: Baseline,
+}
+
+impl Default for Admin {
+    fn default() -> Self {
+        Admin {
+            requests: VecDeque::new(),
+            max_requests: DEFAULT_MAX_REQUESTS,
+            faults: false,
+            baseline: Baseline::default(),
+        }
+    }
+}
+
+/// The fault rules, quotas and limits set through `MockServer`, which is
+/// how the command line flags and fixtures configure the mock.
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    pub faults: Vec<Rule>,
+    pub quotas: Vec<Quota>,
+    pub limits: Limits,
+}
+
+impl Admin {
+    pub fn record(&mut self, target: &str, body: &Bytes, now: i64) {
+        if self.max_requests == 0 {
+            return;
+        }
+
+        let body = serde_json::from_slice::<serde_json::Value>(&body[..])
+            .unwrap_or_else(|_| String::from_utf8_lossy(&body[..]).into_owned().into());
+
+        while self.requests.len() >= self.max_requests {
+            self.requests.pop_front();
+        }
+        self.requests.push_back(ReceivedRequest {
+            target: target.to_string(),
+            body,
+            received_at: now,
+        });
+    }
+
+    /// Remember at most `max` requests, forgetting the oldest beyond it.
+    pub fn set_max_requests(&mut self, max: usize) {
+        self.max_requests = max;
+        while self.requests.len() > max {
+            self.requests.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceivedRequest {
+    pub target: String,
+    pub body: serde_json::Value,
+    #[serde(rename = "receivedAt")]
+    pub received_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FaultsRequest {
+    #[serde(default)]
+    enabled: Option<bool>,
+    /// Replaces every fault injection rule.
+    #[serde(default)]
+    rules: Option<Vec<Rule>>,
+}
+
+/// Changes to the clock, applied in field order.
+#[derive(Debug, Deserialize)]
+struct ClockRequest {
+    #[serde(default)]
+    frozen: Option<bool>,
+    /// Milliseconds since the epoch, or an offset from the current time.
+    #[serde(default)]
+    now: Option<Timestamp>,
+    /// An offset such as `"+15d"`, or a number of milliseconds.
+    #[serde(default)]
+    advance: Option<Timestamp>,
+}
+
+pub fn handle(shared: &Shared, method: &Method, path: &str, body: Bytes) -> Response {
+    let route = &path[PREFIX.len()..];
+
+    debug!("Incoming admin request: {} {}", method, route);
+
+    match (method, route.trim_end_matches('/')) {
+        (&Method::POST, "reset") => {
+            shared.context.clear();
+            shared.context.clock().reset();
+            shared.context.live.close_all();
+            shared.snapshot();
+
+            let mut admin = lock(&shared.admin);
+            admin.requests.clear();
+            admin.faults = false;
+
+            let baseline = admin.baseline.clone();
+            drop(admin);
+
+            lock(&shared.faults).set(baseline.faults);
+            lock(&shared.quotas).set(baseline.quotas);
+            shared.context.set_limits(baseline.limits);
+            empty()
+        }
+
+        (&Method::GET, "state") => json_response(StatusCode::OK, &*shared.context),
+
+        (&Method::PUT, "state") | (&Method::POST, "state") => {
+            match serde_json::from_slice::<Snapshot>(&body[..]) {
+                Ok(snapshot) => {
+                    shared.context.restore(snapshot);
+                    shared.snapshot();
+                    empty()
+                }
+                Err(e) => bad_request(e.to_string()),
+            }
+        }
+
+        (&Method::GET, "requests") => {
+            let admin = lock(&shared.admin);
+            json_response(StatusCode::OK, &admin.requests)
+        }
+
+        (&Method::DELETE, "requests") => {
+            lock(&shared.admin).requests.clear();
+            empty()
+        }
+
+        (&Method::GET, "faults") => {
+            let faults = json!({
+                "enabled": lock(&shared.admin).faults,
+                "rules": lock(&shared.faults).rules(),
+            });
+            json_response(StatusCode::OK, &faults)
+        }
+
+        (&Method::PUT, "faults") | (&Method::POST, "faults") => {
+            match serde_json::from_slice::<FaultsRequest>(&body[..]) {
+                Ok(req) => {
+                    if let Some(enabled) = req.enabled {
+                        lock(&shared.admin).faults = enabled;
+                    }
+
+                    if let Some(rules) = req.rules {
+                        lock(&shared.faults).set(rules);
+                    }
+
+                    empty()
+                }
+                Err(e) => bad_request(e.to_string()),
+            }
+        }
+
+        (&Method::POST, "faults/rules") => match serde_json::from_slice::<Rule>(&body[..]) {
+            Ok(rule) => {
+                lock(&shared.faults).add(rule);
+                empty()
+            }
+            Err(e) => bad_request(e.to_string()),
+        },
+
+        (&Method::DELETE, "faults/rules") => {
+            lock(&shared.faults).clear();
+            empty()
+        }
+
+        (&Method::GET, "clock") => clock_response(shared),
+
+        (&Method::PUT, "clock") | (&Method::POST, "clock") => {
+            let req = match serde_json::from_slice::<ClockRequest>(&body[..]) {
+                Ok(req) => req,
+                Err(e) => return bad_request(e.to_string()),
+            };
+
+            let clock = shared.context.clock();
+
+            match req.frozen {
+                Some(true) => clock.freeze(),
+                Some(false) => clock.resume(),
+                None => {}
+            }
+
+            if let Some(now) = req.now {
+                match now.resolve(clock.now()) {
+                    Ok(now) => clock.set(now),
+                    Err(e) => return bad_request(e.to_string()),
+                }
+            }
+
+            if let Some(advance) = req.advance {
+                match advance.resolve(0) {
+                    Ok(millis) => clock.advance(millis),
+                    Err(e) => return bad_request(e.to_string()),
+                }
+            }
+
+            clock_response(shared)
+        }
+
+        (&Method::GET, "quotas") => {
+            let limiter = lock(&shared.quotas);
+            json_response(StatusCode::OK, &limiter.quotas())
+        }
+
+        (&Method::PUT, "quotas") | (&Method::POST, "quotas") => {
+            match serde_json::from_slice::<Vec<Quota>>(&body[..]) {
+                Ok(quotas) => {
+                    lock(&shared.quotas).set(quotas);
+                    empty()
+                }
+                Err(e) => bad_request(e.to_string()),
+            }
+        }
+
+        (&Method::GET, "usage") => {
+            let usage = json!({
+                "usage": shared.context.usage(),
+                "limits": shared.context.limits(),
+            });
+            json_response(StatusCode::OK, &usage)
+        }
+
+        (&Method::PUT, "limits") | (&Method::POST, "limits") => {
+            match serde_json::from_slice::<Limits>(&body[..]) {
+                Ok(limits) => {
+                    shared.context.set_limits(limits);
+                    empty()
+                }
+                Err(e) => bad_request(e.to_string()),
+            }
+        }
+
+        (&Method::GET, "ui") => ui::page(),
+
+        (&Method::POST, route) if route.starts_with("ui/") => {
+            ui::query(shared, &route["ui/".len()..], &body)
+        }
+
+        _ => json_response(
+            StatusCode::NOT_FOUND,
+            &json!({ "message": format!("no admin route for {} {}", method, path) }),
+        ),
+    }
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    let body = serde_json::to_vec(value).unwrap();
+
+    hyper::Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn clock_response(shared: &Shared) -> Response {
+    let clock = shared.context.clock();
+    let state = json!({ "now": clock.now(), "frozen": clock.is_frozen() });
+    json_response(StatusCode::OK, &state)
+}
+
+fn bad_request(message: String) -> Response {
+    json_response(StatusCode::BAD_REQUEST, &json!({ "message": message }))
+}
+
+fn empty() -> Response {
+    Response::new(Body::empty())
+}
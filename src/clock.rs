@@ -0,0 +1,120 @@
+//! The mock's notion of "now".
+//!
+//! Every time lookup goes through a `Clock`, which follows the system clock
+//! by default. Tests can freeze it, move it to a fixed time or advance it,
+//! so that retention expiry or the too-new and too-old checks on
+//! `PutLogEvents` can be exercised without waiting.
+
+use crate::streams::lock;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Clones share their time, so moving one moves them all.
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// The system time shifted by `offset` milliseconds.
+    Running {
+        offset: i64,
+    },
+    Frozen {
+        now: i64,
+    },
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::Running { offset: 0 }
+    }
+}
+
+impl Clock {
+    /// Milliseconds since the unix epoch.
+    pub fn now(&self) -> i64 {
+        match *lock(&self.state) {
+            State::Running { offset } => system_millis().saturating_add(offset),
+            State::Frozen { now } => now,
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        match *lock(&self.state) {
+            State::Frozen { .. } => true,
+            State::Running { .. } => false,
+        }
+    }
+
+    /// Stop the clock at the current time.
+    pub fn freeze(&self) {
+        let mut state = lock(&self.state);
+
+        if let State::Running { offset } = *state {
+            *state = State::Frozen {
+                now: system_millis().saturating_add(offset),
+            };
+        }
+    }
+
+    /// Let the clock run again from where it was frozen.
+    pub fn resume(&self) {
+        let mut state = lock(&self.state);
+
+        if let State::Frozen { now } = *state {
+            *state = State::Running {
+                offset: now.saturating_sub(system_millis()),
+            };
+        }
+    }
+
+    /// Move the clock to `now`, leaving it frozen or running as it was.
+    pub fn set(&self, now: i64) {
+        let mut state = lock(&self.state);
+
+        *state = match *state {
+            State::Running { .. } => State::Running {
+                offset: now.saturating_sub(system_millis()),
+            },
+            State::Frozen { .. } => State::Frozen { now },
+        };
+    }
+
+    /// Move the clock forward by `millis`, or back if it is negative.
+    pub fn advance(&self, millis: i64) {
+        let mut state = lock(&self.state);
+
+        *state = match *state {
+            State::Running { offset } => State::Running {
+                offset: offset.saturating_add(millis),
+            },
+            State::Frozen { now } => State::Frozen {
+                now: now.saturating_add(millis),
+            },
+        };
+    }
+
+    /// Follow the system clock again, dropping any offset or freeze.
+    pub fn reset(&self) {
+        *lock(&self.state) = State::default();
+    }
+
+    /// Run `f` with the clock frozen at `now`, then put it back.
+    ///
+    /// Used to replay the write-ahead log at the times the entries were
+    /// originally applied.
+    pub(crate) fn at<F: FnOnce() -> T, T>(&self, now: i64, f: F) -> T {
+        let saved = std::mem::replace(&mut *lock(&self.state), State::Frozen { now });
+        let result = f();
+        *lock(&self.state) = saved;
+        result
+    }
+}
+
+/// Milliseconds since the unix epoch according to the system clock.
+pub fn system_millis() -> i64 {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    since_epoch.as_secs() as i64 * 1000 + i64::from(since_epoch.subsec_millis())
+}
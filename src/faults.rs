@@ -0,0 +1,272 @@
+//! Rule based fault injection.
+//!
+//! Rules sit in front of the action dispatcher. Each one matches on the
+//! action and optionally the group and stream a request names, and fires
+//! either on the Nth matching call or with some probability. The first
+//! rule that fires decides what happens to the request:
+//!
+//! ```json
+//! { "action": "PutLogEvents", "nth": 3,
+//!   "fault": { "type": "error", "code": "ThrottlingException" } }
+//! ```
+//!
+//! Errors are returned without running the action. Latency delays it, and
+//! `drop` and `truncate` run it and then mangle the response, so clients
+//! see a failure for a request that actually succeeded.
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// The action name, such as `PutLogEvents`, or a full `X-Amz-Target`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(
+        rename = "logGroupName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub group: Option<String>,
+    #[serde(
+        rename = "logStreamName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stream: Option<String>,
+    /// Fire on this matching call only, counting from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nth: Option<u64>,
+    /// Fire on each matching call with this probability.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_probability",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub probability: Option<f64>,
+    pub fault: Fault,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Fault {
+    /// Fail with the AWS error `code`.
+    Error {
+        code: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+    },
+    /// Wait `millis` before running the action.
+    Latency { millis: u64 },
+    /// Close the connection part way through the response.
+    Drop,
+    /// Send only the first half of the response body.
+    Truncate,
+}
+
+impl Fault {
+    /// The HTTP status an injected error is returned with.
+    pub fn status(code: &str) -> u16 {
+        match code {
+            "ServiceUnavailableException" => 503,
+            "InternalFailure" => 500,
+            _ => 400,
+        }
+    }
+}
+
+/// The configured rules and how often each has matched.
+#[derive(Debug)]
+pub struct Injector {
+    rules: Vec<(Rule, u64)>,
+    rng: u64,
+}
+
+impl Default for Injector {
+    fn default() -> Self {
+        // Any nonzero seed will do for xorshift.
+        let seed = crate::clock::system_millis() as u64 | 1;
+        Injector {
+            rules: Vec::new(),
+            rng: seed,
+        }
+    }
+}
+
+impl Injector {
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules.iter().map(|(rule, _)| rule.clone()).collect()
+    }
+
+    pub fn add(&mut self, rule: Rule) {
+        self.rules.push((rule, 0));
+    }
+
+    pub fn set(&mut self, rules: Vec<Rule>) {
+        self.rules = rules.into_iter().map(|rule| (rule, 0)).collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// The fault to inject into a call to `action`, if any.
+    pub fn check(&mut self, action: &str, body: &[u8]) -> Option<Fault> {
+        if self.rules.is_empty() {
+            return None;
+        }
+
+        let name = action.rsplit('.').next().unwrap_or(action);
+        let names = serde_json::from_slice::<Names>(body).unwrap_or_default();
+
+        for i in 0..self.rules.len() {
+            let matches = {
+                let rule = &self.rules[i].0;
+
+                rule.action
+                    .as_ref()
+                    .map_or(true, |a| a == name || a == action)
+                    && rule
+                        .group
+                        .as_ref()
+                        .map_or(true, |g| Some(g) == names.group.as_ref())
+                    && rule
+                        .stream
+                        .as_ref()
+                        .map_or(true, |s| Some(s) == names.stream.as_ref())
+            };
+
+            if !matches {
+                continue;
+            }
+
+            self.rules[i].1 += 1;
+            let (nth, probability) = (self.rules[i].0.nth, self.rules[i].0.probability);
+
+            let fires = nth.map_or(true, |n| n == self.rules[i].1)
+                && probability.map_or(true, |p| self.random() < p);
+
+            if fires {
+                return Some(self.rules[i].0.fault.clone());
+            }
+        }
+
+        None
+    }
+
+    /// A number in `[0, 1)` from a xorshift64* generator.
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        bits as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// `p`, if it is a probability.
+fn probability(p: f64) -> Result<f64, String> {
+    if p.is_finite() && p >= 0.0 && p <= 1.0 {
+        Ok(p)
+    } else {
+        Err(format!("probability must be between 0 and 1, not {}", p))
+    }
+}
+
+fn deserialize_probability<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(p) => probability(p)
+            .map(Some)
+            .map_err(<D::Error as de::Error>::custom),
+        None => Ok(None),
+    }
+}
+
+/// The names a request targets, for matching rules against.
+#[derive(Default, Deserialize)]
+pub(crate) struct Names {
+    #[serde(rename = "logGroupName")]
+    pub group: Option<String>,
+    #[serde(rename = "logStreamName")]
+    pub stream: Option<String>,
+}
+
+/// Parse the `--fault` command line syntax, a comma separated list of
+/// `key=value` pairs and flags:
+///
+/// ```text
+/// action=PutLogEvents,probability=0.1,error=ThrottlingException
+/// action=GetLogEvents,nth=2,latency=500
+/// group=app,drop
+/// ```
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut rule = Rule {
+            action: None,
+            group: None,
+            stream: None,
+            nth: None,
+            probability: None,
+            fault: Fault::Drop,
+        };
+        let mut fault = None;
+
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut kv = part.splitn(2, '=');
+            let key = kv.next().unwrap_or_default();
+            let value = kv.next();
+            let invalid = || ParseRuleError(format!("invalid fault option {:?}", part));
+
+            match (key, value) {
+                ("action", Some(v)) => rule.action = Some(v.to_string()),
+                ("group", Some(v)) => rule.group = Some(v.to_string()),
+                ("stream", Some(v)) => rule.stream = Some(v.to_string()),
+                ("nth", Some(v)) => rule.nth = Some(v.parse().map_err(|_| invalid())?),
+                ("probability", Some(v)) => {
+                    let p = v.parse().map_err(|_| invalid())?;
+                    rule.probability = Some(probability(p).map_err(ParseRuleError)?)
+                }
+                ("error", Some(v)) => {
+                    fault = Some(Fault::Error {
+                        code: v.to_string(),
+                        message: None,
+                        status: None,
+                    })
+                }
+                ("latency", Some(v)) => {
+                    let millis = v.parse().map_err(|_| invalid())?;
+                    fault = Some(Fault::Latency { millis })
+                }
+                ("drop", None) => fault = Some(Fault::Drop),
+                ("truncate", None) => fault = Some(Fault::Truncate),
+                _ => return Err(invalid()),
+            }
+        }
+
+        rule.fault = fault.ok_or_else(|| {
+            ParseRuleError("a fault needs one of error=, latency=, drop or truncate".into())
+        })?;
+
+        Ok(rule)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseRuleError(String);
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
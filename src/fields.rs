@@ -0,0 +1,168 @@
+//! Logs Insights field discovery: `GetLogGroupFields` and `GetLogRecord`.
+//!
+//! Fields come from events whose message is a JSON object. Nested keys are
+//! joined with dots, as Insights does, so `{"http": {"status": 200}}` has
+//! the field `http.status`, and array elements are numbered,
+//! `{"tags": ["a"]}` having `tags.0`.
+//!
+//! There is no `StartQuery` to hand out log record pointers, so a pointer
+//! is the event's group name and its `FilterLogEvents` `eventId`, joined
+//! by a colon, which group names cannot contain.
+
+use crate::account::ACCOUNT_ID;
+use crate::actions::{EventId, ServiceError};
+use crate::protection::masker;
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// How far either side of `time` `GetLogGroupFields` looks.
+const AROUND: i64 = 8 * 60 * 1000;
+
+/// How far back `GetLogGroupFields` looks when no `time` is given.
+const RECENT: i64 = 15 * 60 * 1000;
+
+/// Fields every event has.
+const SYSTEM_FIELDS: &[&str] = &["@timestamp", "@message", "@logStream"];
+
+/// The fields of `message`, if it is a JSON object, with their values as
+/// strings.
+fn fields(message: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(message) {
+        for (key, value) in object {
+            flatten(key, value, &mut fields);
+        }
+    }
+
+    fields
+}
+
+fn flatten(name: String, value: Value, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten(format!("{}.{}", name, key), value, fields);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.into_iter().enumerate() {
+                flatten(format!("{}.{}", name, i), value, fields);
+            }
+        }
+        Value::String(s) => fields.push((name, s)),
+        value => fields.push((name, value.to_string())),
+    }
+}
+
+pub fn get_log_group_fields(
+    context: &Context,
+    request: GetLogGroupFieldsRequest,
+) -> Result<Response, ServiceError> {
+    let (start, end) = match request.time {
+        Some(secs) => {
+            let millis = secs.saturating_mul(1000);
+            (millis.saturating_sub(AROUND), millis.saturating_add(AROUND))
+        }
+        None => {
+            let now = context.clock().now();
+            (now.saturating_sub(RECENT), now)
+        }
+    };
+
+    let mut events = 0;
+    let mut counts = HashMap::<String, i64>::new();
+
+    for stream in context.streams(&request.log_group_name)? {
+        let stream = lock(&stream);
+
+        for (_, event) in stream.logs.range(Some(start), Some(end.saturating_add(1))) {
+            events += 1;
+
+            let mut names = fields(&event.message)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                *counts.entry(name).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    if events > 0 {
+        found.extend(SYSTEM_FIELDS.iter().map(|name| (name.to_string(), 100)));
+
+        let mut discovered = counts
+            .into_iter()
+            .map(|(name, count)| (name, (count * 100 + events / 2) / events))
+            .collect::<Vec<_>>();
+        discovered.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        found.extend(discovered);
+    }
+
+    let res = GetLogGroupFieldsResponse {
+        log_group_fields: Some(
+            found
+                .into_iter()
+                .map(|(name, percent)| LogGroupField {
+                    name: Some(name),
+                    percent: Some(percent),
+                })
+                .collect(),
+        ),
+    };
+
+    let body = serde_json::to_vec(&res).unwrap();
+    Ok(Response::new(Body::from(body)))
+}
+
+pub fn get_log_record(
+    context: &Context,
+    request: GetLogRecordRequest,
+) -> Result<Response, ServiceError> {
+    let pointer = &request.log_record_pointer;
+    let invalid =
+        || ServiceError::InvalidParameter(format!("Invalid logRecordPointer: {}", pointer));
+
+    let colon = pointer.find(':').ok_or_else(invalid)?;
+    let group = &pointer[..colon];
+    let id = EventId::parse(&pointer[colon + 1..]).ok_or_else(invalid)?;
+
+    let stream = context.stream(group, &id.stream)?;
+    let masker = masker(context, group, request.unmask);
+    let stream = lock(&stream);
+    let event = stream
+        .logs
+        .get(&id.key)
+        .ok_or_else(|| ServiceError::NotFound(format!("Log record not found: {}", pointer)))?;
+
+    let message = match &masker {
+        Some(masker) => masker.mask(&event.message).into_owned(),
+        None => event.message.clone(),
+    };
+
+    let mut record = BTreeMap::new();
+    record.extend(fields(&message));
+    record.insert("@timestamp".to_string(), event.timestamp.to_string());
+    record.insert(
+        "@ingestionTime".to_string(),
+        event.ingestion_time.to_string(),
+    );
+    record.insert("@message".to_string(), message);
+    record.insert("@logStream".to_string(), id.stream.clone());
+    record.insert("@log".to_string(), format!("{}:{}", ACCOUNT_ID, group));
+
+    let res = GetLogRecordResponse {
+        log_record: Some(record),
+    };
+
+    let body = serde_json::to_vec(&res).unwrap();
+    Ok(Response::new(Body::from(body)))
+}
@@ -0,0 +1,677 @@
+//! CloudWatch Logs filter patterns.
+//!
+//! Three kinds of pattern are understood:
+//!
+//! ```text
+//! ERROR "disk full" -retrying      terms, all of which must appear
+//! ?ERROR ?WARN                     terms, at least one of which must appear
+//! { $.level = "error" && $.latency > 200 }
+//! [ip, user, ..., status = 5*]     space delimited fields
+//! ```
+//!
+//! Matching is case sensitive, and `*` is a wildcard in string values. An
+//! empty pattern matches every event.
+//!
+//! Patterns come straight from clients, so like the service the parser
+//! refuses ones longer than `MAX_LENGTH`, and it refuses JSON conditions
+//! nested deeper than `MAX_DEPTH` rather than recursing without bound.
+
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// The longest pattern the service accepts, in characters.
+pub const MAX_LENGTH: usize = 1024;
+
+/// How deeply parentheses may nest in a JSON pattern.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    All,
+    Terms(Terms),
+    Json(Expr),
+    Delimited(Vec<Field>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Terms {
+    all: Vec<String>,
+    any: Vec<String>,
+    none: Vec<String>,
+}
+
+/// A condition on the fields of a JSON message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Selector, Op, Literal),
+    Is(Selector, Literal),
+    Exists(Selector, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector(Vec<Step>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+/// One field of a space delimited pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Any,
+    /// `...`, any number of fields.
+    Rest,
+    Compare(Op, Literal),
+}
+
+impl Pattern {
+    pub fn matches(&self, message: &str) -> bool {
+        match self {
+            Pattern::All => true,
+            Pattern::Terms(terms) => terms.matches(message),
+            Pattern::Json(expr) => match serde_json::from_str::<Value>(message) {
+                Ok(value) => expr.matches(&value),
+                Err(_) => false,
+            },
+            Pattern::Delimited(fields) => match_fields(fields, &split_fields(message)),
+        }
+    }
+}
+
+impl Terms {
+    fn matches(&self, message: &str) -> bool {
+        self.all.iter().all(|t| message.contains(t.as_str()))
+            && (self.any.is_empty() || self.any.iter().any(|t| message.contains(t.as_str())))
+            && !self.none.iter().any(|t| message.contains(t.as_str()))
+    }
+}
+
+impl Expr {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(value) && b.matches(value),
+            Expr::Or(a, b) => a.matches(value) || b.matches(value),
+            Expr::Compare(selector, op, literal) => match selector.select(value) {
+                Some(found) => compare_json(found, *op, literal),
+                None => false,
+            },
+            Expr::Is(selector, literal) => match (selector.select(value), literal) {
+                (Some(Value::Null), Literal::Null) => true,
+                (Some(Value::Bool(b)), Literal::Bool(expected)) => b == expected,
+                _ => false,
+            },
+            Expr::Exists(selector, exists) => selector.select(value).is_some() == *exists,
+        }
+    }
+}
+
+impl Selector {
+    fn select<'a>(&self, mut value: &'a Value) -> Option<&'a Value> {
+        for step in &self.0 {
+            value = match step {
+                Step::Key(key) => value.get(key.as_str())?,
+                Step::Index(i) => value.get(*i)?,
+            };
+        }
+
+        Some(value)
+    }
+}
+
+fn compare_json(found: &Value, op: Op, literal: &Literal) -> bool {
+    match (found, literal) {
+        (Value::Number(n), Literal::Num(expected)) => match n.as_f64() {
+            Some(n) => compare_num(n, op, *expected),
+            None => false,
+        },
+        (Value::String(s), Literal::Str(expected)) => compare_str(s, op, expected),
+        (Value::Bool(b), Literal::Bool(expected)) => match op {
+            Op::Eq => b == expected,
+            Op::Ne => b != expected,
+            _ => false,
+        },
+        (Value::Null, Literal::Null) => op == Op::Eq,
+        _ => op == Op::Ne,
+    }
+}
+
+fn compare_num(found: f64, op: Op, expected: f64) -> bool {
+    match op {
+        Op::Eq => (found - expected).abs() < std::f64::EPSILON,
+        Op::Ne => (found - expected).abs() >= std::f64::EPSILON,
+        Op::Lt => found < expected,
+        Op::Le => found <= expected,
+        Op::Gt => found > expected,
+        Op::Ge => found >= expected,
+    }
+}
+
+fn compare_str(found: &str, op: Op, expected: &str) -> bool {
+    match op {
+        Op::Eq => wildcard(expected, found),
+        Op::Ne => !wildcard(expected, found),
+        _ => false,
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of
+/// characters.
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// Split a message into fields on whitespace, keeping quoted and bracketed
+/// runs together.
+fn split_fields(message: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = message.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let close = match c {
+            '"' => Some('"'),
+            '[' => Some(']'),
+            _ => None,
+        };
+
+        let mut field = String::new();
+        if let Some(close) = close {
+            chars.next();
+            while let Some(c) = chars.next() {
+                if c == close {
+                    break;
+                }
+                field.push(c);
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+        }
+
+        fields.push(field);
+    }
+
+    fields
+}
+
+/// Match `values` against `fields` the way a glob is matched, with `...`
+/// as the `*`. On a mismatch the most recent `...` takes one more value and
+/// matching resumes after it, which is enough since earlier `...` fields
+/// can never need to take more. At worst this takes time proportional to
+/// the number of fields times the number of values, where trying every
+/// split for every `...` would be exponential in how many there are.
+fn match_fields(fields: &[Field], values: &[String]) -> bool {
+    let (mut f, mut v) = (0, 0);
+    let mut rest = None;
+
+    while v < values.len() {
+        match fields.get(f) {
+            Some(Field::Rest) => {
+                rest = Some((f, v));
+                f += 1;
+            }
+            Some(field) if field.matches(&values[v]) => {
+                f += 1;
+                v += 1;
+            }
+            _ => match rest {
+                Some((rest_f, rest_v)) => {
+                    rest = Some((rest_f, rest_v + 1));
+                    f = rest_f + 1;
+                    v = rest_v + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    fields[f..].iter().all(|field| *field == Field::Rest)
+}
+
+impl Field {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Field::Any | Field::Rest => true,
+            Field::Compare(op, Literal::Num(expected)) => match value.parse::<f64>() {
+                Ok(found) => compare_num(found, *op, *expected),
+                Err(_) => false,
+            },
+            Field::Compare(op, Literal::Str(expected)) => compare_str(value, *op, expected),
+            Field::Compare(op, literal) => compare_str(value, *op, &literal.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Str(s) => write!(f, "{:?}", s),
+            Literal::Num(n) => write!(f, "{}", n),
+            Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Null => f.write_str("null"),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParsePatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern.chars().count() > MAX_LENGTH {
+            return Err(ParsePatternError(format!(
+                "must be at most {} characters",
+                MAX_LENGTH
+            )));
+        }
+
+        let pattern = pattern.trim();
+
+        if pattern.is_empty() {
+            Ok(Pattern::All)
+        } else if pattern.starts_with('{') {
+            if !pattern.ends_with('}') {
+                return Err(ParsePatternError::new("unclosed {"));
+            }
+
+            let tokens = tokenize(&pattern[1..pattern.len() - 1])?;
+            let mut parser = Parser {
+                tokens,
+                pos: 0,
+                depth: 0,
+            };
+            let expr = parser.or()?;
+
+            if parser.pos < parser.tokens.len() {
+                return Err(ParsePatternError::new("unexpected input after expression"));
+            }
+
+            Ok(Pattern::Json(expr))
+        } else if pattern.starts_with('[') {
+            if !pattern.ends_with(']') {
+                return Err(ParsePatternError::new("unclosed ["));
+            }
+
+            pattern[1..pattern.len() - 1]
+                .split(',')
+                .map(parse_field)
+                .collect::<Result<_, _>>()
+                .map(Pattern::Delimited)
+        } else {
+            parse_terms(pattern).map(Pattern::Terms)
+        }
+    }
+}
+
+fn parse_terms(pattern: &str) -> Result<Terms, ParsePatternError> {
+    let mut terms = Terms::default();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let list = match c {
+            '?' => {
+                chars.next();
+                &mut terms.any
+            }
+            '-' => {
+                chars.next();
+                &mut terms.none
+            }
+            _ => &mut terms.all,
+        };
+
+        let mut term = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => term.extend(chars.next()),
+                    Some(c) => term.push(c),
+                    None => return Err(ParsePatternError::new("unclosed quote")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                term.push(c);
+                chars.next();
+            }
+        }
+
+        if !term.is_empty() {
+            list.push(term);
+        }
+    }
+
+    Ok(terms)
+}
+
+fn parse_field(field: &str) -> Result<Field, ParsePatternError> {
+    let field = field.trim();
+
+    if field == "..." {
+        return Ok(Field::Rest);
+    }
+
+    let ops = ["!=", "<=", ">=", "=", "<", ">"];
+    let found = ops
+        .iter()
+        .filter_map(|op| field.find(op).map(|i| (i, *op)))
+        .min_by_key(|(i, _)| *i);
+
+    match found {
+        None if is_identifier(field) => Ok(Field::Any),
+        None => Err(ParsePatternError(format!("invalid field {:?}", field))),
+        Some((i, op)) => {
+            let name = field[..i].trim();
+            if !is_identifier(name) {
+                return Err(ParsePatternError(format!("invalid field {:?}", field)));
+            }
+
+            let value = field[i + op.len()..].trim();
+            Ok(Field::Compare(parse_op(op), parse_literal(value)))
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_op(op: &str) -> Op {
+    match op {
+        "=" => Op::Eq,
+        "!=" => Op::Ne,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        ">" => Op::Gt,
+        _ => Op::Ge,
+    }
+}
+
+fn parse_literal(value: &str) -> Literal {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        return Literal::Str(value[1..value.len() - 1].to_string());
+    }
+
+    match value {
+        "true" => Literal::Bool(true),
+        "false" => Literal::Bool(false),
+        "null" => Literal::Null,
+        _ => value
+            .parse()
+            .map(Literal::Num)
+            .unwrap_or_else(|_| Literal::Str(value.to_string())),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Op(Op),
+    Quoted(String),
+    Word(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParsePatternError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                Token::And
+            }
+            '|' if chars.peek() == Some(&'|') => {
+                chars.next();
+                Token::Or
+            }
+            '=' => Token::Op(Op::Eq),
+            '!' | '<' | '>' => {
+                let eq = chars.peek() == Some(&'=');
+                if eq {
+                    chars.next();
+                }
+
+                match (c, eq) {
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('<', true) => Token::Op(Op::Le),
+                    ('>', false) => Token::Op(Op::Gt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    _ => return Err(ParsePatternError::new("expected != after !")),
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => s.extend(chars.next()),
+                        Some(c) => s.push(c),
+                        None => return Err(ParsePatternError::new("unclosed quote")),
+                    }
+                }
+                Token::Quoted(s)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()=!<>&|\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// How many parentheses are open.
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParsePatternError> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParsePatternError> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParsePatternError> {
+        match self.next() {
+            Some(Token::Open) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(ParsePatternError(format!(
+                        "parentheses nest more than {} deep",
+                        MAX_DEPTH
+                    )));
+                }
+
+                self.depth += 1;
+                let expr = self.or()?;
+                self.depth -= 1;
+
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(ParsePatternError::new("expected )")),
+                }
+            }
+            Some(Token::Word(word)) => self.condition(parse_selector(&word)?),
+            _ => Err(ParsePatternError::new("expected a $ selector")),
+        }
+    }
+
+    fn condition(&mut self, selector: Selector) -> Result<Expr, ParsePatternError> {
+        match self.next() {
+            Some(Token::Op(op)) => match self.next() {
+                Some(Token::Quoted(s)) => Ok(Expr::Compare(selector, op, Literal::Str(s))),
+                Some(Token::Word(w)) => Ok(Expr::Compare(selector, op, parse_literal(&w))),
+                _ => Err(ParsePatternError::new("expected a value")),
+            },
+            Some(Token::Word(ref w)) if w == "IS" => match self.next() {
+                Some(Token::Word(ref w)) if w == "NULL" => Ok(Expr::Is(selector, Literal::Null)),
+                Some(Token::Word(ref w)) if w == "TRUE" => {
+                    Ok(Expr::Is(selector, Literal::Bool(true)))
+                }
+                Some(Token::Word(ref w)) if w == "FALSE" => {
+                    Ok(Expr::Is(selector, Literal::Bool(false)))
+                }
+                _ => Err(ParsePatternError::new("expected NULL, TRUE or FALSE")),
+            },
+            Some(Token::Word(ref w)) if w == "EXISTS" => Ok(Expr::Exists(selector, true)),
+            Some(Token::Word(ref w)) if w == "NOT" => match self.next() {
+                Some(Token::Word(ref w)) if w == "EXISTS" => Ok(Expr::Exists(selector, false)),
+                _ => Err(ParsePatternError::new("expected EXISTS")),
+            },
+            _ => Err(ParsePatternError::new("expected a comparison")),
+        }
+    }
+}
+
+fn parse_selector(word: &str) -> Result<Selector, ParsePatternError> {
+    let invalid = || ParsePatternError(format!("invalid selector {:?}", word));
+
+    if !word.starts_with('$') {
+        return Err(invalid());
+    }
+
+    let mut steps = Vec::new();
+    let mut rest = &word[1..];
+
+    while !rest.is_empty() {
+        if rest.starts_with('.') {
+            let end = rest[1..]
+                .find(|c: char| c == '.' || c == '[')
+                .map_or(rest.len(), |i| i + 1);
+            let key = &rest[1..end];
+            if key.is_empty() {
+                return Err(invalid());
+            }
+            steps.push(Step::Key(key.to_string()));
+            rest = &rest[end..];
+        } else if rest.starts_with('[') {
+            let end = rest.find(']').ok_or_else(invalid)?;
+            let index = rest[1..end].parse().map_err(|_| invalid())?;
+            steps.push(Step::Index(index));
+            rest = &rest[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(Selector(steps))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsePatternError(String);
+
+impl ParsePatternError {
+    fn new(message: &str) -> Self {
+        ParsePatternError(message.to_string())
+    }
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid filter pattern: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
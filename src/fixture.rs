@@ -0,0 +1,285 @@
+//! Declarative state loaded before the mock starts serving.
+//!
+//! A fixture is a JSON or YAML document describing groups, their streams
+//! and any events that should already exist:
+//!
+//! ```json
+//! {
+//!   "groups": [{
+//!     "name": "app",
+//!     "retentionInDays": 14,
+//!     "tags": { "team": "core" },
+//!     "streams": [{
+//!       "name": "web-1",
+//!       "events": [
+//!         { "timestamp": "-5m", "message": "starting" },
+//!         { "timestamp": "-4m30s", "message": "started" }
+//!       ]
+//!     }]
+//!   }]
+//! }
+//! ```
+//!
+//! Timestamps are either milliseconds since the epoch or a duration relative
+//! to the time the fixture is loaded, such as `"now"`, `"-5m"` or `"+1h"`.
+//!
+//! Files ending in `.yaml` or `.yml` are read as YAML, anything else as
+//! JSON.
+//!
+//! A fixture can also carry fault injection rules under `"faults"`; see the
+//! `faults` module for their format.
+
+use crate::faults::Rule;
+use crate::streams::{lock, Context, Group, LogGroupClass, StorageError, Stream};
+use crate::types::InputLogEvent;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Fixture {
+    #[serde(default)]
+    pub groups: Vec<GroupFixture>,
+    /// Fault injection rules, see `faults`.
+    #[serde(default)]
+    pub faults: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GroupFixture {
+    pub name: String,
+    #[serde(rename = "retentionInDays")]
+    #[serde(default)]
+    pub retention_in_days: Option<i64>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    #[serde(rename = "logGroupClass")]
+    #[serde(default)]
+    pub log_group_class: Option<LogGroupClass>,
+    #[serde(default)]
+    pub streams: Vec<StreamFixture>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StreamFixture {
+    pub name: String,
+    #[serde(default)]
+    pub events: Vec<EventFixture>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventFixture {
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Timestamp {
+    Absolute(i64),
+    Relative(String),
+}
+
+#[derive(Debug)]
+pub enum FixtureError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    ParseYaml(serde_yaml::Error),
+    InvalidTimestamp(String),
+    Storage(StorageError),
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FixtureError::Io(e) => write!(f, "unable to read fixture: {}", e),
+            FixtureError::Parse(e) => write!(f, "invalid fixture: {}", e),
+            FixtureError::ParseYaml(e) => write!(f, "invalid fixture: {}", e),
+            FixtureError::InvalidTimestamp(t) => write!(f, "invalid fixture timestamp {:?}", t),
+            FixtureError::Storage(e) => write!(f, "unable to load fixture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FixtureError {}
+
+impl From<io::Error> for FixtureError {
+    fn from(e: io::Error) -> Self {
+        FixtureError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for FixtureError {
+    fn from(e: serde_json::Error) -> Self {
+        FixtureError::Parse(e)
+    }
+}
+
+impl From<serde_yaml::Error> for FixtureError {
+    fn from(e: serde_yaml::Error) -> Self {
+        FixtureError::ParseYaml(e)
+    }
+}
+
+impl From<StorageError> for FixtureError {
+    fn from(e: StorageError) -> Self {
+        FixtureError::Storage(e)
+    }
+}
+
+impl Fixture {
+    /// Read a fixture, as YAML if `path` ends in `.yaml` or `.yml` and as
+    /// JSON otherwise.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, FixtureError> {
+        let path = path.as_ref();
+        let contents = fs::read(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => Fixture::from_yaml(&contents),
+            _ => Fixture::from_slice(&contents),
+        }
+    }
+
+    pub fn from_slice(contents: &[u8]) -> Result<Self, FixtureError> {
+        Ok(serde_json::from_slice(contents)?)
+    }
+
+    pub fn from_yaml(contents: &[u8]) -> Result<Self, FixtureError> {
+        Ok(serde_yaml::from_slice(contents)?)
+    }
+
+    /// Add the fixture's groups, streams and events to `context`.
+    ///
+    /// Groups and streams that already exist are extended rather than
+    /// replaced. Relative timestamps are resolved against `now`.
+    pub fn load(&self, context: &Context, now: i64) -> Result<(), FixtureError> {
+        for group_fixture in &self.groups {
+            let group = Group {
+                name: group_fixture.name.clone(),
+                creation_time: Some(now),
+                ..Default::default()
+            };
+
+            match context.create_group(group, context.limits().max_groups) {
+                Ok(()) | Err(StorageError::GroupAlreadyExists) => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            if let Some(group) = context.group(&group_fixture.name) {
+                let mut group = lock(&group);
+
+                if group_fixture.retention_in_days.is_some() {
+                    group.retention_in_days = group_fixture.retention_in_days;
+                }
+
+                group.tags.extend(group_fixture.tags.clone());
+
+                if let Some(class) = group_fixture.log_group_class {
+                    group.log_group_class = class;
+                }
+            }
+
+            for stream_fixture in &group_fixture.streams {
+                let events = stream_fixture
+                    .events
+                    .iter()
+                    .map(|event| {
+                        let timestamp = match &event.timestamp {
+                            Some(timestamp) => timestamp.resolve(now)?,
+                            None => now,
+                        };
+
+                        Ok(InputLogEvent {
+                            message: event.message.clone(),
+                            timestamp,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, FixtureError>>()?;
+
+                let stream = Stream {
+                    name: stream_fixture.name.clone(),
+                    ..Default::default()
+                };
+
+                // Existing streams are extended rather than replaced.
+                match context.create_stream(&group_fixture.name, stream) {
+                    Ok(()) | Err(StorageError::StreamAlreadyExists) => {}
+                    Err(e) => return Err(e.into()),
+                }
+
+                context.store_events(&group_fixture.name, &stream_fixture.name, events)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Timestamp {
+    pub fn resolve(&self, now: i64) -> Result<i64, FixtureError> {
+        match self {
+            Timestamp::Absolute(millis) => Ok(*millis),
+            Timestamp::Relative(offset) => parse_offset(offset)
+                .and_then(|offset| now.checked_add(offset))
+                .ok_or_else(|| FixtureError::InvalidTimestamp(offset.clone())),
+        }
+    }
+}
+
+/// Parse `now` or a signed duration such as `-5m` or `+1h30m` into
+/// milliseconds, or `None` if it is invalid or too large.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let offset = offset.trim();
+
+    if offset == "now" {
+        return Some(0);
+    }
+
+    let (sign, rest) = match offset.chars().next()? {
+        '-' => (-1, &offset[1..]),
+        '+' => (1, &offset[1..]),
+        _ => return None,
+    };
+
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total = 0i64;
+    let mut chars = rest.chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut amount = String::new();
+        while let Some(c) = chars.peek().cloned().filter(|c| c.is_ascii_digit()) {
+            amount.push(c);
+            chars.next();
+        }
+
+        let mut unit = String::new();
+        while let Some(c) = chars.peek().cloned().filter(|c| c.is_ascii_alphabetic()) {
+            unit.push(c);
+            chars.next();
+        }
+
+        let amount: i64 = amount.parse().ok()?;
+        let millis = match unit.as_str() {
+            "ms" => 1,
+            "s" => 1000,
+            "m" => 60 * 1000,
+            "h" => 60 * 60 * 1000,
+            "d" => 24 * 60 * 60 * 1000,
+            "w" => 7 * 24 * 60 * 60 * 1000,
+            _ => return None,
+        };
+
+        total = amount
+            .checked_mul(millis)
+            .and_then(|millis| total.checked_add(millis))?;
+    }
+
+    Some(sign * total)
+}
@@ -0,0 +1,250 @@
+//! Metric filters and subscription filters.
+//!
+//! Both are stored on their log group and never act on events: the mock
+//! publishes no metrics and delivers nothing to subscription destinations.
+//! They exist so that provisioning tools can create, list and delete them,
+//! and see the same rejections as from the service.
+
+use crate::account::{account_policy, json, page, SUBSCRIPTION_FILTER_POLICY};
+use crate::actions::{supports, ServiceError};
+use crate::filter::Pattern;
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+
+/// How many metric filters a group may have.
+const MAX_METRIC_FILTERS: usize = 100;
+
+/// How many subscription filters a group may have.
+const MAX_SUBSCRIPTION_FILTERS: usize = 2;
+
+/// The default and largest page size of the describe actions.
+const MAX_RESULTS: usize = 50;
+
+fn check_pattern(pattern: &str) -> Result<(), ServiceError> {
+    pattern
+        .parse::<Pattern>()
+        .map(|_| ())
+        .map_err(|e| ServiceError::InvalidParameter(format!("Invalid filter pattern: {}", e)))
+}
+
+fn not_found(name: &str) -> ServiceError {
+    ServiceError::NotFound(format!("The specified filter does not exist: {}", name))
+}
+
+pub fn put_metric_filter(
+    context: &Context,
+    request: PutMetricFilterRequest,
+) -> Result<Response, ServiceError> {
+    check_pattern(&request.filter_pattern)?;
+
+    if request.metric_transformations.len() != 1 {
+        return Err(ServiceError::InvalidParameter(
+            "metricTransformations must hold exactly one transformation".into(),
+        ));
+    }
+
+    let group = context
+        .group(&request.log_group_name)
+        .ok_or_else(|| ServiceError::NotFound("The specified log group does not exist.".into()))?;
+    let mut group = lock(&group);
+    supports(&group, "Metric filters")?;
+
+    let filters = &group.metric_filters;
+    if !filters.contains_key(&request.filter_name) && filters.len() >= MAX_METRIC_FILTERS {
+        return Err(ServiceError::LimitExceeded(format!(
+            "A log group may have at most {} metric filters",
+            MAX_METRIC_FILTERS
+        )));
+    }
+
+    let filter = MetricFilter {
+        creation_time: Some(context.clock().now()),
+        filter_name: Some(request.filter_name.clone()),
+        filter_pattern: Some(request.filter_pattern),
+        log_group_name: Some(request.log_group_name),
+        metric_transformations: Some(request.metric_transformations),
+    };
+    group.metric_filters.insert(request.filter_name, filter);
+
+    Ok(Response::new(Body::empty()))
+}
+
+pub fn describe_metric_filters(
+    context: &Context,
+    request: DescribeMetricFiltersRequest,
+) -> Result<Response, ServiceError> {
+    let groups = match &request.log_group_name {
+        Some(name) => vec![context.group(name).ok_or_else(|| {
+            ServiceError::NotFound("The specified log group does not exist.".into())
+        })?],
+        None => context.groups(),
+    };
+
+    let transforms = |filter: &MetricFilter| {
+        let transformations = filter.metric_transformations.as_ref();
+        transformations.map_or(false, |transformations| {
+            transformations.iter().any(|t| {
+                request
+                    .metric_name
+                    .as_ref()
+                    .map_or(true, |n| *n == t.metric_name)
+                    && request
+                        .metric_namespace
+                        .as_ref()
+                        .map_or(true, |n| *n == t.metric_namespace)
+            })
+        })
+    };
+
+    // The prefix only applies along with a group, as in the service.
+    let prefix = match &request.log_group_name {
+        Some(_) => request.filter_name_prefix.clone().unwrap_or_default(),
+        None => String::new(),
+    };
+
+    let mut filters = Vec::new();
+    for group in groups {
+        let group = lock(&group);
+
+        for (name, filter) in &group.metric_filters {
+            if name.starts_with(prefix.as_str()) && transforms(filter) {
+                filters.push((format!("{}:{}", group.name, name), filter.clone()));
+            }
+        }
+    }
+    filters.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (filters, next_token) = page(
+        filters.iter().map(|(key, filter)| (key, filter)),
+        request.limit,
+        MAX_RESULTS,
+        request.next_token.as_ref(),
+    )?;
+
+    Ok(json(&DescribeMetricFiltersResponse {
+        metric_filters: Some(filters),
+        next_token,
+    }))
+}
+
+pub fn delete_metric_filter(
+    context: &Context,
+    request: DeleteMetricFilterRequest,
+) -> Result<Response, ServiceError> {
+    let removed = match context.group(&request.log_group_name) {
+        Some(group) => {
+            let mut group = lock(&group);
+            group.metric_filters.remove(&request.filter_name)
+        }
+        None => None,
+    };
+
+    match removed {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(not_found(&request.filter_name)),
+    }
+}
+
+pub fn put_subscription_filter(
+    context: &Context,
+    request: PutSubscriptionFilterRequest,
+) -> Result<Response, ServiceError> {
+    check_pattern(&request.filter_pattern)?;
+
+    match request.distribution.as_ref().map(String::as_str) {
+        None | Some("ByLogStream") | Some("Random") => {}
+        Some(distribution) => {
+            return Err(ServiceError::InvalidParameter(format!(
+                "distribution must be ByLogStream or Random, not {:?}",
+                distribution
+            )));
+        }
+    }
+
+    // An account level subscription filter policy selecting the group
+    // takes up one of its places.
+    let limit = match account_policy(context, SUBSCRIPTION_FILTER_POLICY, &request.log_group_name) {
+        Some(_) => MAX_SUBSCRIPTION_FILTERS - 1,
+        None => MAX_SUBSCRIPTION_FILTERS,
+    };
+
+    let group = context
+        .group(&request.log_group_name)
+        .ok_or_else(|| ServiceError::NotFound("The specified log group does not exist.".into()))?;
+    let mut group = lock(&group);
+    supports(&group, "Subscription filters")?;
+
+    let filters = &group.subscription_filters;
+    if !filters.contains_key(&request.filter_name) && filters.len() >= limit {
+        return Err(ServiceError::LimitExceeded(format!(
+            "A log group may have at most {} subscription filters",
+            MAX_SUBSCRIPTION_FILTERS
+        )));
+    }
+
+    let filter = SubscriptionFilter {
+        creation_time: Some(context.clock().now()),
+        destination_arn: Some(request.destination_arn),
+        distribution: Some(
+            request
+                .distribution
+                .unwrap_or_else(|| "ByLogStream".to_string()),
+        ),
+        filter_name: Some(request.filter_name.clone()),
+        filter_pattern: Some(request.filter_pattern),
+        log_group_name: Some(request.log_group_name),
+        role_arn: request.role_arn,
+    };
+    group
+        .subscription_filters
+        .insert(request.filter_name, filter);
+
+    Ok(Response::new(Body::empty()))
+}
+
+pub fn describe_subscription_filters(
+    context: &Context,
+    request: DescribeSubscriptionFiltersRequest,
+) -> Result<Response, ServiceError> {
+    let prefix = request.filter_name_prefix.unwrap_or_default();
+
+    let group = context
+        .group(&request.log_group_name)
+        .ok_or_else(|| ServiceError::NotFound("The specified log group does not exist.".into()))?;
+    let group = lock(&group);
+
+    let matching = group
+        .subscription_filters
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix.as_str()));
+    let (filters, next_token) = page(
+        matching,
+        request.limit,
+        MAX_RESULTS,
+        request.next_token.as_ref(),
+    )?;
+
+    Ok(json(&DescribeSubscriptionFiltersResponse {
+        next_token,
+        subscription_filters: Some(filters),
+    }))
+}
+
+pub fn delete_subscription_filter(
+    context: &Context,
+    request: DeleteSubscriptionFilterRequest,
+) -> Result<Response, ServiceError> {
+    let removed = match context.group(&request.log_group_name) {
+        Some(group) => {
+            let mut group = lock(&group);
+            group.subscription_filters.remove(&request.filter_name)
+        }
+        None => None,
+    };
+
+    match removed {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(not_found(&request.filter_name)),
+    }
+}
@@ -1,25 +1,305 @@
 #[macro_use]
 extern crate tokio_trace;
 
+mod account;
 mod actions;
-mod streams;
-mod types;
+mod admin;
+pub mod clock;
+pub mod faults;
+mod fields;
+pub mod filter;
+pub mod fixture;
+mod group_filters;
+pub mod limits;
+mod live;
+pub mod metrics;
+pub mod mirror;
+mod persist;
+mod protection;
+pub mod quotas;
+pub mod recording;
+pub mod streams;
+pub mod testing;
+mod transform;
+pub mod types;
+mod ui;
 
 use bytes::Bytes;
-use futures::{future, Future, Poll, Stream as _};
+use futures::{future, stream, Future, Poll, Stream as _};
+use hyper::http::request::Parts;
+use hyper::Method;
 use serde::Deserialize;
+use std::io;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use tokio_tcp::TcpListener;
+use tokio_timer::Delay;
 use tokio_trace::field;
 use tokio_trace_futures::Instrument;
 use tokio_trace_tower_http::InstrumentedMakeService;
 use tower_hyper::server::Server;
 use tower_service::Service;
 
-use streams::Context;
-
+use admin::Admin;
+use clock::Clock;
+use faults::{Fault, Injector, Rule};
+use fixture::{Fixture, FixtureError};
+use limits::Limits;
+use metrics::Metrics;
+use mirror::Mirror;
+use persist::Persistence;
+use quotas::{Limiter, Quota};
+use recording::{Exchange, Mismatch, Recorder};
+use streams::{lock, read, write, Context, Storage};
+
+/// Serve a fresh mock on `addr`.
 pub fn serve(addr: SocketAddr) -> impl Future<Item = (), Error = ()> {
+    MockServer::new().serve(addr)
+}
+
+/// A handle to a mock's state.
+///
+/// Clones share the same `Context`, so a handle kept by a test can inspect
+/// everything the served copy receives.
+#[derive(Clone, Default)]
+pub struct MockServer {
+    shared: Shared,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        MockServer::default()
+    }
+
+    /// A mock that keeps its groups, streams and events in `storage`.
+    pub fn with_storage(storage: Box<dyn Storage>) -> Self {
+        let mut server = MockServer::new();
+        server.shared.context = Arc::new(Context::new(storage));
+        server
+    }
+
+    /// The shared state backing this mock.
+    pub fn context(&self) -> Arc<Context> {
+        self.shared.context.clone()
+    }
+
+    /// Cap how much the mock stores. See `limits` for the policies.
+    ///
+    /// Set this before `persist` so that replaying the log evicts the same
+    /// events as the original run did. `/_mock/reset` puts these limits
+    /// back.
+    pub fn set_limits(&self, limits: Limits) {
+        self.shared.context.set_limits(limits.clone());
+        lock(&self.shared.admin).baseline.limits = limits;
+    }
+
+    /// Add a fault injection rule. See `faults` for how rules match. The
+    /// rule survives `/_mock/reset`.
+    pub fn add_fault(&self, rule: Rule) {
+        lock(&self.shared.faults).add(rule.clone());
+        lock(&self.shared.admin).baseline.faults.push(rule);
+    }
+
+    /// Throttle actions that go over `quotas`. An empty list, the default,
+    /// disables throttling. `/_mock/reset` puts these quotas back.
+    pub fn set_quotas(&self, quotas: Vec<Quota>) {
+        lock(&self.shared.quotas).set(quotas.clone());
+        lock(&self.shared.admin).baseline.quotas = quotas;
+    }
+
+    /// Inspect the group named `name`, whether or not it exists yet.
+    pub fn group(&self, name: &str) -> testing::GroupRef {
+        testing::GroupRef::new(self.shared.context.clone(), name)
+    }
+
+    /// The clock every time lookup goes through.
+    pub fn clock(&self) -> &Clock {
+        self.shared.context.clock()
+    }
+
+    /// Populate the mock from `fixture`, resolving relative timestamps
+    /// against the current time, and add its fault injection rules.
+    pub fn load_fixture(&self, fixture: &Fixture) -> Result<(), FixtureError> {
+        let context = &self.shared.context;
+        fixture.load(context, context.clock().now())?;
+
+        for rule in &fixture.faults {
+            self.add_fault(rule.clone());
+        }
+
+        self.shared.snapshot();
+
+        Ok(())
+    }
+
+    /// Persist state to the data directory `dir`, recovering whatever a
+    /// previous run left there.
+    ///
+    /// Returns `true` if existing state was recovered. Recovery can run
+    /// while the mock is already serving, as long as it is marked not ready
+    /// until this returns.
+    pub fn persist<P: AsRef<Path>>(&self, dir: P) -> io::Result<bool> {
+        let (persistence, recovered) =
+            Persistence::open(dir, &self.shared.context, |context, action, body| {
+                let _ = Router::apply(action, context, body);
+            })?;
+
+        *write(&self.shared.persistence) = Some(Arc::new(persistence));
+
+        Ok(recovered)
+    }
+
+    /// End `StartLiveTail` sessions after `timeout`, rather than the
+    /// default three hours.
+    pub fn set_live_tail_timeout(&self, timeout: Duration) {
+        self.shared.context.live.set_timeout(timeout);
+    }
+
+    /// Remember at most the last `max` requests for `/_mock/requests`,
+    /// rather than the default 1000. Zero stops recording them.
+    pub fn set_max_requests(&self, max: usize) {
+        lock(&self.shared.admin).set_max_requests(max);
+    }
+
+    /// Echo every accepted event to `mirror`, or stop echoing with `None`.
+    pub fn set_mirror(&self, mirror: Option<Mirror>) {
+        *write(&self.shared.context.mirror) = mirror;
+    }
+
+    /// Mark the mock ready, or not. Until it is ready `/ready` fails and
+    /// every other request is refused with `ServiceUnavailableException`.
+    ///
+    /// A new mock is ready straight away.
+    pub fn set_ready(&self, ready: bool) {
+        self.shared.starting.store(!ready, Ordering::Release);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        !self.shared.starting.load(Ordering::Acquire)
+    }
+
+    /// Append every request the mock serves, and its response, to the
+    /// recording at `path`. See `recording` for the format.
+    ///
+    /// This must be called before the mock is cloned or served.
+    pub fn record<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.shared.recorder = Some(Arc::new(Recorder::create(path)?));
+        Ok(())
+    }
+
+    /// Feed the recording at `path` back into the mock, returning the
+    /// requests whose responses differ from the recorded ones.
+    pub fn replay<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<Mismatch>> {
+        let mut mismatches = Vec::new();
+
+        for (i, exchange) in recording::read(path)?.iter().enumerate() {
+            if exchange.streaming {
+                continue;
+            }
+
+            let body = Bytes::from(exchange.body.as_bytes());
+
+            let res = match &exchange.target {
+                Some(target) => {
+                    let clock = self.shared.context.clock();
+                    clock.at(exchange.received_at, || self.shared.apply(target, &body))
+                }
+                None if exchange.path.starts_with(admin::PREFIX) => {
+                    let method = Method::from_bytes(exchange.method.as_bytes()).unwrap_or_default();
+                    Ok(admin::handle(&self.shared, &method, &exchange.path, body))
+                }
+                None => continue,
+            };
+
+            if let Ok(res) = res {
+                mismatches.extend(Mismatch::check(i + 1, exchange, res));
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    pub fn serve(&self, addr: SocketAddr) -> impl Future<Item = (), Error = ()> {
+        serve_shared(addr, self.shared.clone())
+    }
+}
+
+/// State shared by every connection.
+///
+/// There is no lock around the whole `Context`; storage locks each group
+/// and stream on its own, so actions against different streams run in
+/// parallel.
+#[derive(Clone, Default)]
+struct Shared {
+    context: Arc<Context>,
+    admin: Arc<Mutex<Admin>>,
+    faults: Arc<Mutex<Injector>>,
+    quotas: Arc<Mutex<Limiter>>,
+    persistence: Arc<RwLock<Option<Arc<Persistence>>>>,
+    recorder: Option<Arc<Recorder>>,
+    metrics: Arc<Metrics>,
+    /// Set while state is still being recovered or loaded.
+    starting: Arc<AtomicBool>,
+}
+
+impl Shared {
+    /// Write a full snapshot of the context if persistence is enabled.
+    ///
+    /// Used after changes that bypass the action dispatcher.
+    fn snapshot(&self) {
+        if let Some(persistence) = read(&self.persistence).clone() {
+            if let Err(e) = persistence.snapshot(&self.context) {
+                error!({ error = field::display(e) }, "unable to write snapshot");
+            }
+        }
+    }
+
+    /// Apply `action`, logging it if it changed anything.
+    fn apply(&self, action: &str, body: &Bytes) -> Result<Response, Error> {
+        let persistence = match read(&self.persistence).clone() {
+            Some(persistence) if persist::is_mutating(action) => persistence,
+            _ => return Router::apply_guarded(action, &self.context, body),
+        };
+
+        let (res, snapshot_due) = {
+            let _writer = persistence.writer();
+            let res = Router::apply_guarded(action, &self.context, body);
+
+            if !res
+                .as_ref()
+                .map(|r| r.status().is_success())
+                .unwrap_or(false)
+            {
+                return res;
+            }
+
+            let now = self.context.clock().now();
+            let snapshot_due = match persistence.append(action, body, now) {
+                Ok(due) => due,
+                Err(e) => {
+                    error!({ error = field::display(e) }, "unable to persist action");
+                    false
+                }
+            };
+
+            (res, snapshot_due)
+        };
+
+        // Snapshots wait for in-flight writers, so this must happen after
+        // the writer guard is released.
+        if snapshot_due {
+            self.snapshot();
+        }
+
+        res
+    }
+}
+
+fn serve_shared(addr: SocketAddr, shared: Shared) -> impl Future<Item = (), Error = ()> {
     let bind = TcpListener::bind(&addr).expect("bind");
 
     let mut serve_span = span!(
@@ -28,7 +308,8 @@ pub fn serve(addr: SocketAddr) -> impl Future<Item = (), Error = ()> {
         local_port = addr.port() as u64
     );
 
-    let service = MockCloudwatchLogs::default();
+    let metrics = shared.metrics.clone();
+    let service = MockCloudwatchLogs { shared };
     let service = InstrumentedMakeService::new(service, serve_span.clone());
 
     let serve_span2 = serve_span.clone();
@@ -50,13 +331,23 @@ pub fn serve(addr: SocketAddr) -> impl Future<Item = (), Error = ()> {
                         return Err(e);
                     }
 
+                    let connection = Metrics::connection(&metrics);
+
                     let serve = server
                         .serve(stream)
-                        .map_err(|e| panic!("Server error {:?}", e))
+                        .map_err(|e| {
+                            // Clients going away, and injected drops, end up
+                            // here; neither should take the server down.
+                            warn!({ error = field::debug(e) }, "connection error");
+                        })
                         .and_then(|_| {
                             debug!("response finished");
                             future::ok(())
                         })
+                        .then(move |res| {
+                            drop(connection);
+                            res
+                        })
                         .instrument(conn_span2);
 
                     hyper::rt::spawn(serve);
@@ -77,9 +368,19 @@ pub type Request = hyper::Request<Body>;
 pub type Response = hyper::Response<Body>;
 pub type Error = hyper::Error;
 
-#[derive(Default)]
+/// Paths polled by orchestrators and scrapers rather than sent by clients.
+const PROBES: &[&str] = &["/health", "/ready", "/metrics"];
+
+/// Whether requests to `path` go in the recording. Probes, the web UI's
+/// polling and tail feeds are left out.
+fn is_recorded(path: &str) -> bool {
+    !PROBES.contains(&path) && !path.starts_with("/_mock/ui") && path != live::TAIL_PATH
+}
+
+type ResponseFuture = Box<dyn Future<Item = Response, Error = Error> + Send + 'static>;
+
 struct MockCloudwatchLogs {
-    context: Arc<Mutex<Context>>,
+    shared: Shared,
 }
 
 impl Service<()> for MockCloudwatchLogs {
@@ -92,74 +393,385 @@ impl Service<()> for MockCloudwatchLogs {
     }
 
     fn call(&mut self, _: ()) -> Self::Future {
-        let context = self.context.clone();
-        future::ok(Router::new(context))
+        let shared = self.shared.clone();
+        future::ok(Router::new(shared))
     }
 }
 
 struct Router {
-    context: Arc<Mutex<Context>>,
+    shared: Shared,
 }
 
 impl Router {
-    pub fn new(context: Arc<Mutex<Context>>) -> Self {
-        Router { context }
+    pub fn new(shared: Shared) -> Self {
+        Router { shared }
     }
 
-    fn dispatch(
-        action: &str,
-        context: Arc<Mutex<Context>>,
-        body: Bytes,
-    ) -> Result<Response, Error> {
-        use actions::*;
+    /// Send a request to the admin API or the action dispatcher.
+    fn route(shared: Shared, parts: &Parts, body: Bytes) -> ResponseFuture {
+        match (&parts.method, parts.uri.path()) {
+            (&Method::GET, "/health") => return Box::new(future::ok(probe(200, "ok"))),
+
+            (&Method::GET, "/ready") => {
+                let res = if shared.starting.load(Ordering::Acquire) {
+                    probe(503, "starting")
+                } else {
+                    probe(200, "ready")
+                };
+                return Box::new(future::ok(res));
+            }
+
+            (&Method::GET, "/metrics") => {
+                let metrics = shared.metrics.render(&shared.context);
+                let res = hyper::Response::builder()
+                    .header("Content-Type", "text/plain; version=0.0.4")
+                    .body(Body::from(metrics))
+                    .unwrap();
+                return Box::new(future::ok(res));
+            }
+
+            _ => {}
+        }
+
+        if shared.starting.load(Ordering::Acquire) {
+            debug!("still starting, refusing request");
+            shared.metrics.refuse();
+            let res = actions::ServiceError::ServiceUnavailable.into();
+            return Box::new(future::ok(res));
+        }
+
+        if (&parts.method, parts.uri.path()) == (&Method::GET, live::TAIL_PATH) {
+            let res = live::tail(&shared.context, parts.uri.query());
+            return Box::new(future::ok(res));
+        }
+
+        if parts.uri.path().starts_with(admin::PREFIX) {
+            return Box::new(future::ok(admin::handle(
+                &shared,
+                &parts.method,
+                parts.uri.path(),
+                body,
+            )));
+        }
+
+        // TODO: check that it is a post request
+        let amz_target_header = parts.headers.get("X-Amz-Target");
+
+        if let Some(Ok(action)) = amz_target_header.map(|a| a.to_str()) {
+            Router::dispatch(action.to_string(), shared, body)
+        } else {
+            let res = actions::error_response(
+                400,
+                "UnknownOperationException",
+                "Missing or invalid X-Amz-Target header",
+            );
+            Box::new(future::ok(res))
+        }
+    }
 
+    fn dispatch(action: String, shared: Shared, body: Bytes) -> ResponseFuture {
         info!("Incoming action: {}", action);
 
-        let mut action_span = span!("action", action = &field::debug(action));
+        let mut action_span = span!("action", action = &field::debug(&action));
+        let started = Instant::now();
+        let metrics = shared.metrics.clone();
+        let name = action.clone();
+
+        let fut = action_span.enter(|| {
+            let faults = {
+                let mut admin = lock(&shared.admin);
+                admin.record(&action, &body, shared.context.clock().now());
+                admin.faults
+            };
+
+            if faults {
+                debug!("fault injection enabled, failing action");
+                let res = actions::ServiceError::ServiceUnavailable.into();
+                return Box::new(future::ok(res)) as ResponseFuture;
+            }
 
-        action_span.enter(|| {
-            match action {
-                "Logs_20140328.DescribeLogStreams" => {
-                    // TODO: make this a 500
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    describe_streams(&mut context, req).or_else(|e| Ok(e.into()))
-                }
+            let now = shared.context.clock().now();
+            if !lock(&shared.quotas).check(&action, &body, now) {
+                debug!("over quota, throttling action");
+                let res = actions::error_response(400, "ThrottlingException", "Rate exceeded");
+                return Box::new(future::ok(res));
+            }
 
-                "Logs_20140328.DescribeLogGroups" => {
-                    // TODO: make this a 500
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    describe_groups(&mut context, req).or_else(|e| Ok(e.into()))
-                }
+            let fault = lock(&shared.faults).check(&action, &body);
 
-                "Logs_20140328.CreateLogGroup" => {
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    create_group(&mut context, req).or_else(|e| Ok(e.into()))
-                }
+            if let Some(fault) = &fault {
+                debug!("injecting fault {:?}", fault);
+            }
 
-                "Logs_20140328.CreateLogStream" => {
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    create_stream(&mut context, req).or_else(|e| Ok(e.into()))
+            match fault {
+                None => Box::new(future::result(shared.apply(&action, &body))),
+
+                Some(Fault::Error {
+                    code,
+                    message,
+                    status,
+                }) => {
+                    let status = status.unwrap_or_else(|| Fault::status(&code));
+                    let message = message.unwrap_or_else(|| "Injected fault".to_string());
+                    let res = actions::error_response(status, &code, &message);
+                    Box::new(future::ok(res))
                 }
 
-                "Logs_20140328.PutLogEvents" => {
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    put_logs(&mut context, req).or_else(|e| Ok(e.into()))
+                Some(Fault::Latency { millis }) => {
+                    let delay = Delay::new(Instant::now() + Duration::from_millis(millis));
+                    Box::new(delay.then(move |_| shared.apply(&action, &body)))
                 }
 
-                "Logs_20140328.GetLogEvents" => {
-                    let mut context = context.lock().unwrap();
-                    let req = extract(&body).unwrap();
-                    get_logs(&mut context, req).or_else(|e| Ok(e.into()))
-                }
+                Some(Fault::Drop) => Box::new(
+                    future::result(shared.apply(&action, &body)).and_then(|res| {
+                        let (parts, body) = res.into_parts();
+
+                        body.concat2().map(|body| {
+                            let head = body[..body.len() / 2].to_vec();
+                            let abort =
+                                io::Error::new(io::ErrorKind::ConnectionAborted, "injected fault");
+                            let chunks = stream::iter_result(vec![Ok(head), Err(abort)]);
+
+                            Response::from_parts(parts, Body::wrap_stream(chunks))
+                        })
+                    }),
+                ),
+
+                Some(Fault::Truncate) => Box::new(
+                    future::result(shared.apply(&action, &body)).and_then(|res| {
+                        let (parts, body) = res.into_parts();
 
-                _ => unimplemented!("404"),
+                        body.concat2().map(|body| {
+                            let head = body[..body.len() / 2].to_vec();
+                            Response::from_parts(parts, Body::from(head))
+                        })
+                    }),
+                ),
             }
-        })
+        });
+
+        Box::new(fut.map(move |res| {
+            metrics.observe(&name, &res, started.elapsed());
+            res
+        }))
+    }
+
+    /// Run `action`, turning a panic into an `InternalFailure` response.
+    ///
+    /// Locks are poison tolerant, so one failed request leaves the rest of
+    /// the mock usable.
+    fn apply_guarded(action: &str, context: &Context, body: &Bytes) -> Result<Response, Error> {
+        panic::catch_unwind(AssertUnwindSafe(|| Router::apply(action, context, body)))
+            .unwrap_or_else(|_| {
+                error!("action {} panicked", action);
+                Ok(actions::ServiceError::InternalFailure.into())
+            })
+    }
+
+    /// Run `action` against `context`.
+    ///
+    /// This is also used to replay the write-ahead log, so it must not
+    /// depend on anything besides its arguments.
+    fn apply(action: &str, context: &Context, body: &Bytes) -> Result<Response, Error> {
+        context.expire();
+
+        Router::dispatch(action, context, body).or_else(|e| Ok(e.into()))
+    }
+
+    fn dispatch(
+        action: &str,
+        context: &Context,
+        body: &Bytes,
+    ) -> Result<Response, actions::ServiceError> {
+        use actions::*;
+
+        match action {
+            "Logs_20140328.DescribeLogStreams" => {
+                let req = extract(body)?;
+                describe_streams(context, req)
+            }
+
+            "Logs_20140328.DescribeLogGroups" => {
+                let req = extract(body)?;
+                describe_groups(context, req)
+            }
+
+            "Logs_20140328.CreateLogGroup" => {
+                let req = extract(body)?;
+                create_group(context, req)
+            }
+
+            "Logs_20140328.CreateLogStream" => {
+                let req = extract(body)?;
+                create_stream(context, req)
+            }
+
+            "Logs_20140328.PutLogEvents" => {
+                let req = extract(body)?;
+                put_logs(context, req)
+            }
+
+            "Logs_20140328.FilterLogEvents" => {
+                let req = extract(body)?;
+                filter_logs(context, req)
+            }
+
+            "Logs_20140328.StartLiveTail" => {
+                let req = extract(body)?;
+                live::start_live_tail(context, req)
+            }
+
+            "Logs_20140328.GetLogEvents" => {
+                let req = extract(body)?;
+                get_logs(context, req)
+            }
+
+            "Logs_20140328.PutMetricFilter" => {
+                let req = extract(body)?;
+                group_filters::put_metric_filter(context, req)
+            }
+
+            "Logs_20140328.DescribeMetricFilters" => {
+                let req = extract(body)?;
+                group_filters::describe_metric_filters(context, req)
+            }
+
+            "Logs_20140328.DeleteMetricFilter" => {
+                let req = extract(body)?;
+                group_filters::delete_metric_filter(context, req)
+            }
+
+            "Logs_20140328.PutSubscriptionFilter" => {
+                let req = extract(body)?;
+                group_filters::put_subscription_filter(context, req)
+            }
+
+            "Logs_20140328.DescribeSubscriptionFilters" => {
+                let req = extract(body)?;
+                group_filters::describe_subscription_filters(context, req)
+            }
+
+            "Logs_20140328.DeleteSubscriptionFilter" => {
+                let req = extract(body)?;
+                group_filters::delete_subscription_filter(context, req)
+            }
+
+            "Logs_20140328.GetLogGroupFields" => {
+                let req = extract(body)?;
+                fields::get_log_group_fields(context, req)
+            }
+
+            "Logs_20140328.GetLogRecord" => {
+                let req = extract(body)?;
+                fields::get_log_record(context, req)
+            }
+
+            "Logs_20140328.PutDataProtectionPolicy" => {
+                let req = extract(body)?;
+                protection::put_data_protection_policy(context, req)
+            }
+
+            "Logs_20140328.GetDataProtectionPolicy" => {
+                let req = extract(body)?;
+                protection::get_data_protection_policy(context, req)
+            }
+
+            "Logs_20140328.DeleteDataProtectionPolicy" => {
+                let req = extract(body)?;
+                protection::delete_data_protection_policy(context, req)
+            }
+
+            "Logs_20140328.PutTransformer" => {
+                let req = extract(body)?;
+                transform::put_transformer(context, req)
+            }
+
+            "Logs_20140328.GetTransformer" => {
+                let req = extract(body)?;
+                transform::get_transformer(context, req)
+            }
+
+            "Logs_20140328.DeleteTransformer" => {
+                let req = extract(body)?;
+                transform::delete_transformer(context, req)
+            }
+
+            "Logs_20140328.TestTransformer" => {
+                let req = extract(body)?;
+                transform::test_transformer(context, req)
+            }
+
+            "Logs_20140328.PutAccountPolicy" => {
+                let req = extract(body)?;
+                account::put_account_policy(context, req)
+            }
+
+            "Logs_20140328.DescribeAccountPolicies" => {
+                let req = extract(body)?;
+                account::describe_account_policies(context, req)
+            }
+
+            "Logs_20140328.DeleteAccountPolicy" => {
+                let req = extract(body)?;
+                account::delete_account_policy(context, req)
+            }
+
+            "Logs_20140328.PutResourcePolicy" => {
+                let req = extract(body)?;
+                account::put_resource_policy(context, req)
+            }
+
+            "Logs_20140328.DescribeResourcePolicies" => {
+                let req = extract(body)?;
+                account::describe_resource_policies(context, req)
+            }
+
+            "Logs_20140328.DeleteResourcePolicy" => {
+                let req = extract(body)?;
+                account::delete_resource_policy(context, req)
+            }
+
+            "Logs_20140328.PutDestination" => {
+                let req = extract(body)?;
+                account::put_destination(context, req)
+            }
+
+            "Logs_20140328.PutDestinationPolicy" => {
+                let req = extract(body)?;
+                account::put_destination_policy(context, req)
+            }
+
+            "Logs_20140328.DescribeDestinations" => {
+                let req = extract(body)?;
+                account::describe_destinations(context, req)
+            }
+
+            "Logs_20140328.DeleteDestination" => {
+                let req = extract(body)?;
+                account::delete_destination(context, req)
+            }
+
+            "Logs_20140328.PutQueryDefinition" => {
+                let req = extract(body)?;
+                account::put_query_definition(context, req)
+            }
+
+            "Logs_20140328.DescribeQueryDefinitions" => {
+                let req = extract(body)?;
+                account::describe_query_definitions(context, req)
+            }
+
+            "Logs_20140328.DeleteQueryDefinition" => {
+                let req = extract(body)?;
+                account::delete_query_definition(context, req)
+            }
+
+            _ => Ok(error_response(
+                400,
+                "UnknownOperationException",
+                &format!("Unknown action {}", action),
+            )),
+        }
     }
 }
 
@@ -185,17 +797,52 @@ impl Service<Request> for Router {
             trace!("HTTP Request: {:?}", req);
 
             let (parts, body) = req.into_parts();
-            let context = self.context.clone();
+            let shared = self.shared.clone();
 
-            let fut = body.concat2().and_then(move |body| {
-                // TODO: check that it is a post request
-                let amz_target_header = parts.headers.get("X-Amz-Target");
+            let fut = body.concat2().and_then(move |body| -> ResponseFuture {
+                let body = body.into_bytes();
 
-                if let Some(Ok(action)) = amz_target_header.map(|a| a.to_str()) {
-                    Router::dispatch(action, context, body.into_bytes())
-                } else {
-                    unimplemented!("Bad request")
-                }
+                let recorder = match &shared.recorder {
+                    Some(recorder) if is_recorded(parts.uri.path()) => recorder.clone(),
+                    _ => return Router::route(shared, &parts, body),
+                };
+
+                let started = Instant::now();
+                let mut exchange = Exchange::new(&parts, &body, shared.context.clock().now());
+
+                let record = move |exchange: &Exchange| {
+                    if let Err(e) = recorder.write(exchange) {
+                        error!({ error = field::display(e) }, "unable to record request");
+                    }
+                };
+
+                Box::new(Router::route(shared, &parts, body).and_then(
+                    move |res| -> ResponseFuture {
+                        let status = res.status().as_u16();
+                        let streaming = res
+                            .headers()
+                            .get("Content-Type")
+                            .map_or(false, |t| t == live::CONTENT_TYPE);
+
+                        // An event stream lasts as long as its session, so
+                        // only the head of the response is recorded.
+                        if streaming {
+                            exchange.streaming = true;
+                            exchange.finish(status, b"", started.elapsed());
+                            record(&exchange);
+                            return Box::new(future::ok(res));
+                        }
+
+                        let (res_parts, res_body) = res.into_parts();
+
+                        Box::new(res_body.concat2().map(move |res_body| {
+                            exchange.finish(status, &res_body[..], started.elapsed());
+                            record(&exchange);
+
+                            Response::from_parts(res_parts, Body::from(res_body))
+                        }))
+                    },
+                ))
             });
 
             Box::new(fut)
@@ -203,9 +850,21 @@ impl Service<Request> for Router {
     }
 }
 
-fn extract<'a, T>(body: &'a Bytes) -> Result<T, ()>
+fn extract<'a, T>(body: &'a Bytes) -> Result<T, actions::ServiceError>
 where
     T: Deserialize<'a>,
 {
-    serde_json::from_slice(&body[..]).map_err(|_| ())
+    serde_json::from_slice(&body[..])
+        .map_err(|e| actions::ServiceError::Serialization(e.to_string()))
+}
+
+/// A health check response, `{"status": status}`.
+fn probe(code: u16, status: &str) -> Response {
+    let body = serde_json::json!({ "status": status }).to_string();
+
+    hyper::Response::builder()
+        .status(code)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
 }
@@ -0,0 +1,248 @@
+//! Caps on how much the mock keeps in memory.
+//!
+//! Every cap is optional and unset by default. When a write would go over a
+//! cap the configured `Policy` decides what happens: the oldest events make
+//! room for it, it is rejected with `LimitExceededException`, or the oldest
+//! events are dropped and archived to a file on disk. Creating a group past
+//! `max_groups` is always rejected, since there is nothing sensible to evict.
+//!
+//! `max_groups` is checked by the storage backend under the lock that adds
+//! the group. The event and byte caps are checked per request rather than
+//! under a global lock, so concurrent writers can briefly overshoot them.
+//! The storage backend keeps running totals and an index of each stream's
+//! oldest event, so neither check nor eviction walks every stream.
+
+use crate::streams::{lock, read, write, Context, Evicted, Group, LogEvent, StorageError};
+use crate::types::InputLogEvent;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use tokio_trace::field;
+
+/// The bytes CloudWatch Logs counts for each event on top of its message.
+pub const EVENT_OVERHEAD: usize = 26;
+
+const SPILL: &str = "spill.jsonl";
+
+/// The size an event with `message` counts for against
+/// `Limits::max_bytes`.
+pub fn event_size(message: &str) -> usize {
+    message.len() + EVENT_OVERHEAD
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    /// The most event bytes stored across every stream.
+    #[serde(rename = "maxBytes", default)]
+    pub max_bytes: Option<usize>,
+    #[serde(rename = "maxEventsPerStream", default)]
+    pub max_events_per_stream: Option<usize>,
+    #[serde(rename = "maxGroups", default)]
+    pub max_groups: Option<usize>,
+    #[serde(default)]
+    pub policy: Policy,
+}
+
+/// What to do with a write that would exceed a cap.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Evict the oldest events until the write fits.
+    DropOldest,
+    /// Fail the write with `LimitExceededException`.
+    Reject,
+    /// Like `DropOldest`, but also append evicted events to `spill.jsonl`
+    /// in the given directory.
+    ///
+    /// The file is an archive for the user to inspect. Spilled events are
+    /// gone from the mock and are never read back, and replaying the
+    /// write-ahead log on startup does not spill them a second time.
+    Spill(PathBuf),
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::Reject
+    }
+}
+
+/// How much the mock currently stores.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Usage {
+    #[serde(rename = "storedBytes")]
+    pub stored_bytes: usize,
+    pub groups: usize,
+    pub streams: usize,
+    pub events: usize,
+}
+
+impl Usage {
+    /// What `group` and its streams store.
+    pub fn of(group: &Group) -> Self {
+        let mut usage = Usage {
+            groups: 1,
+            ..Default::default()
+        };
+
+        for stream in group.streams.values() {
+            let stream = lock(stream);
+            usage.streams += 1;
+            usage.events += stream.logs.len();
+            usage.stored_bytes += stream.logs.stored_bytes();
+        }
+
+        usage
+    }
+
+    pub fn add(&mut self, other: &Usage) {
+        self.stored_bytes += other.stored_bytes;
+        self.groups += other.groups;
+        self.streams += other.streams;
+        self.events += other.events;
+    }
+}
+
+/// An evicted event, as written to the spill file.
+#[derive(Serialize)]
+struct Spilled<'a> {
+    #[serde(rename = "logGroupName")]
+    group: &'a str,
+    #[serde(rename = "logStreamName")]
+    stream: &'a str,
+    timestamp: i64,
+    message: &'a str,
+}
+
+impl Context {
+    pub fn limits(&self) -> Limits {
+        read(&self.limits).clone()
+    }
+
+    pub fn set_limits(&self, limits: Limits) {
+        *write(&self.limits) = limits;
+    }
+
+    /// Add `events` to a stream, enforcing the configured limits.
+    ///
+    /// The events are stamped with the clock's current time as their
+    /// ingestion time.
+    pub fn store_events(
+        &self,
+        group: &str,
+        stream: &str,
+        events: Vec<InputLogEvent>,
+    ) -> Result<(), StorageError> {
+        let limits = self.limits();
+        let handle = self.stream(group, stream)?;
+
+        let now = self.clock().now();
+        let events = events
+            .into_iter()
+            .map(|event| LogEvent::new(event, now))
+            .collect::<Vec<_>>();
+
+        if limits.policy == Policy::Reject {
+            if let Some(max) = limits.max_events_per_stream {
+                if lock(&handle).logs.len() + events.len() > max {
+                    return Err(StorageError::LimitExceeded(format!(
+                        "Resource limit exceeded: at most {} events can be stored per log stream",
+                        max
+                    )));
+                }
+            }
+
+            if let Some(max) = limits.max_bytes {
+                let incoming = events.iter().map(|e| event_size(&e.message)).sum::<usize>();
+
+                if self.usage().stored_bytes + incoming > max {
+                    return Err(StorageError::LimitExceeded(format!(
+                        "Resource limit exceeded: at most {} bytes of events can be stored",
+                        max
+                    )));
+                }
+            }
+
+            return self.put_events(group, stream, events);
+        }
+
+        self.put_events(group, stream, events)?;
+
+        if let Some(max) = limits.max_events_per_stream {
+            let evicted = self.evict_stream(group, stream, max)?;
+
+            if !evicted.is_empty() {
+                self.evicted(&limits.policy, group, stream, &evicted);
+            }
+        }
+
+        if let Some(max) = limits.max_bytes {
+            self.evict_bytes(&limits.policy, max);
+        }
+
+        Ok(())
+    }
+
+    /// Evict the oldest events across every stream until at most `max`
+    /// bytes are stored.
+    fn evict_bytes(&self, policy: &Policy, max: usize) {
+        let mut stored = self.usage().stored_bytes;
+
+        while stored > max {
+            let Evicted {
+                group,
+                stream,
+                event,
+            } = match self.evict_oldest() {
+                Some(evicted) => evicted,
+                None => break,
+            };
+
+            stored = stored.saturating_sub(event_size(&event.message));
+            self.evicted(policy, &group, &stream, &[event]);
+        }
+    }
+
+    fn evicted(&self, policy: &Policy, group: &str, stream: &str, events: &[LogEvent]) {
+        debug!(
+            "Evicted {} events from {} in {}",
+            events.len(),
+            stream,
+            group
+        );
+
+        if let Policy::Spill(dir) = policy {
+            if self.replaying.load(Ordering::Acquire) {
+                return;
+            }
+
+            if let Err(e) = spill(dir, group, stream, events) {
+                error!({ error = field::display(e) }, "unable to spill events");
+            }
+        }
+    }
+}
+
+fn spill(dir: &Path, group: &str, stream: &str, events: &[LogEvent]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut lines = Vec::new();
+    for event in events {
+        let spilled = Spilled {
+            group,
+            stream,
+            timestamp: event.timestamp,
+            message: &event.message,
+        };
+
+        serde_json::to_writer(&mut lines, &spilled)?;
+        lines.push(b'\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(SPILL))?;
+    file.write_all(&lines)
+}
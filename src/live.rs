@@ -0,0 +1,528 @@
+//! Watching events as they are ingested.
+//!
+//! A `StartLiveTail` session is a response body in the
+//! `application/vnd.amazon.eventstream` framing. It opens with a
+//! `sessionStart` event, then carries a `sessionUpdate` for every
+//! `PutLogEvents` batch with matching events, and ends with a
+//! `SessionTimeoutException` once the session timeout passes on the mock's
+//! clock, so moving the clock forward ends sessions early.
+//!
+//! `GET /_mock/tail` is the same feed without the AWS ceremony: a
+//! `text/event-stream` with one `data:` line of JSON per event, which
+//! `EventSource` and most HTTP clients can read directly. It never times
+//! out.
+//!
+//! Sessions and feeds are dropped from the hub as soon as their client goes
+//! away, and sessions also once they time out.
+
+use crate::account::group_name;
+use crate::actions::{supports, ServiceError};
+use crate::clock::Clock;
+use crate::filter::Pattern;
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::{Async, Future, Poll, Stream};
+use hyper::StatusCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio_timer::Delay;
+
+pub const CONTENT_TYPE: &str = "application/vnd.amazon.eventstream";
+
+pub const TAIL_PATH: &str = "/_mock/tail";
+
+/// How long a session lasts by default, the same three hours as the
+/// service.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// How often an idle session checks the clock for its deadline.
+const RECHECK: Duration = Duration::from_secs(1);
+
+/// At most this many events are sent in one update. Beyond it updates are
+/// sampled.
+const MAX_RESULTS: usize = 500;
+
+/// The open sessions.
+#[derive(Debug)]
+pub struct Hub {
+    sessions: Mutex<Vec<Session>>,
+    feeds: Mutex<Vec<Feed>>,
+    timeout: Mutex<Duration>,
+    next_id: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Session {
+    /// Group names and the identifiers the client used for them.
+    groups: Vec<(String, String)>,
+    streams: Option<Vec<String>>,
+    prefixes: Option<Vec<String>>,
+    pattern: Pattern,
+    frames: UnboundedSender<Vec<u8>>,
+    /// When the session times out, by the mock's clock.
+    deadline: i64,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Hub {
+            sessions: Mutex::default(),
+            feeds: Mutex::default(),
+            timeout: Mutex::new(DEFAULT_TIMEOUT),
+            next_id: AtomicUsize::new(1),
+        }
+    }
+}
+
+impl Hub {
+    pub fn timeout(&self) -> Duration {
+        *lock(&self.timeout)
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) {
+        *lock(&self.timeout) = timeout;
+    }
+
+    /// Whether any session or feed is open at `now`, so that callers can
+    /// skip preparing events nobody will see.
+    ///
+    /// Sessions past their deadline are dropped first. Their responses end
+    /// on their own, but would otherwise count as watching until the next
+    /// publish.
+    pub fn is_watched(&self, now: i64) -> bool {
+        let mut sessions = lock(&self.sessions);
+        sessions.retain(|session| now < session.deadline);
+
+        !sessions.is_empty() || !lock(&self.feeds).is_empty()
+    }
+
+    /// End every session and feed, as if they had timed out.
+    pub fn close_all(&self) {
+        lock(&self.sessions).clear();
+        lock(&self.feeds).clear();
+    }
+
+    /// Send `events`, just stored in `group`/`stream`, to every session
+    /// and feed that selects them.
+    pub fn publish(
+        &self,
+        group: &str,
+        stream: &str,
+        events: &[InputLogEvent],
+        ingestion_time: i64,
+    ) {
+        lock(&self.sessions).retain(|session| {
+            ingestion_time < session.deadline
+                && session.publish(group, stream, events, ingestion_time)
+        });
+        lock(&self.feeds).retain(|feed| feed.publish(group, stream, events, ingestion_time));
+    }
+}
+
+impl Session {
+    /// Returns `false` once the client has gone away.
+    fn publish(
+        &self,
+        group: &str,
+        stream: &str,
+        events: &[InputLogEvent],
+        ingestion_time: i64,
+    ) -> bool {
+        let identifier = match self.groups.iter().find(|(name, _)| name == group) {
+            Some((_, identifier)) => identifier,
+            None => return true,
+        };
+
+        let selected = match (&self.streams, &self.prefixes) {
+            (Some(names), _) => names.iter().any(|name| name == stream),
+            (_, Some(prefixes)) => prefixes.iter().any(|p| stream.starts_with(p.as_str())),
+            _ => true,
+        };
+
+        if !selected {
+            return true;
+        }
+
+        let mut results = events
+            .iter()
+            .filter(|event| self.pattern.matches(&event.message))
+            .map(|event| LiveTailSessionLogEvent {
+                ingestion_time,
+                log_group_identifier: identifier.clone(),
+                log_stream_name: stream.to_string(),
+                message: event.message.clone(),
+                timestamp: event.timestamp,
+            })
+            .collect::<Vec<_>>();
+
+        if results.is_empty() {
+            return true;
+        }
+
+        let sampled = results.len() > MAX_RESULTS;
+        results.truncate(MAX_RESULTS);
+
+        let update = LiveTailSessionUpdate {
+            session_metadata: LiveTailSessionMetadata { sampled },
+            session_results: results,
+        };
+
+        self.frames
+            .unbounded_send(event("sessionUpdate", &update))
+            .is_ok()
+    }
+}
+
+pub fn start_live_tail(
+    context: &Context,
+    request: StartLiveTailRequest,
+) -> Result<Response, ServiceError> {
+    let identifiers = &request.log_group_identifiers;
+    if identifiers.is_empty() || identifiers.len() > 10 {
+        return Err(ServiceError::InvalidParameter(
+            "logGroupIdentifiers must name between 1 and 10 log groups".into(),
+        ));
+    }
+
+    if request.log_stream_names.is_some() && request.log_stream_name_prefixes.is_some() {
+        return Err(ServiceError::InvalidParameter(
+            "logStreamNames and logStreamNamePrefixes are mutually exclusive".into(),
+        ));
+    }
+
+    let pattern = match &request.log_event_filter_pattern {
+        Some(pattern) => pattern
+            .parse::<Pattern>()
+            .map_err(|e| ServiceError::InvalidParameter(e.to_string()))?,
+        None => Pattern::All,
+    };
+
+    let mut groups = Vec::new();
+    for identifier in identifiers {
+        let name = group_name(identifier);
+        match context.group(name) {
+            Some(group) => supports(&lock(&group), "Live Tail")?,
+            None => {
+                return Err(ServiceError::NotFound(format!(
+                    "The specified log group does not exist: {}",
+                    identifier
+                )));
+            }
+        }
+        groups.push((name.to_string(), identifier.clone()));
+    }
+
+    let hub = &context.live;
+    let id = hub.next_id.fetch_add(1, Ordering::AcqRel);
+    let start = LiveTailSessionStart {
+        log_event_filter_pattern: request.log_event_filter_pattern.clone(),
+        log_group_identifiers: request.log_group_identifiers.clone(),
+        log_stream_name_prefixes: request.log_stream_name_prefixes.clone(),
+        log_stream_names: request.log_stream_names.clone(),
+        request_id: uuid(id, 1),
+        session_id: uuid(id, 2),
+    };
+
+    let timeout = hub.timeout();
+    let timeout = timeout.as_secs() as i64 * 1000 + i64::from(timeout.subsec_millis());
+    let deadline = context.clock().now().saturating_add(timeout);
+
+    let (frames, updates) = mpsc::unbounded();
+    lock(&hub.sessions).push(Session {
+        groups,
+        streams: request.log_stream_names,
+        prefixes: request.log_stream_name_prefixes,
+        pattern,
+        frames,
+        deadline,
+    });
+
+    let tail = Tail {
+        start: Some(event("sessionStart", &start)),
+        updates,
+        clock: context.clock().clone(),
+        deadline,
+        recheck: Delay::new(Instant::now() + RECHECK),
+        done: false,
+    };
+
+    Ok(hyper::Response::builder()
+        .header("Content-Type", CONTENT_TYPE)
+        .body(Body::wrap_stream(tail))
+        .unwrap())
+}
+
+/// A `/_mock/tail` subscriber. Each filter is optional.
+#[derive(Debug)]
+struct Feed {
+    group: Option<String>,
+    stream: Option<String>,
+    pattern: Pattern,
+    lines: UnboundedSender<Vec<u8>>,
+}
+
+/// One event on a `/_mock/tail` feed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailEvent<'a> {
+    log_group_name: &'a str,
+    log_stream_name: &'a str,
+    timestamp: i64,
+    message: &'a str,
+    ingestion_time: i64,
+}
+
+impl Feed {
+    /// Returns `false` once the client has gone away.
+    fn publish(
+        &self,
+        group: &str,
+        stream: &str,
+        events: &[InputLogEvent],
+        ingestion_time: i64,
+    ) -> bool {
+        let selected = self.group.as_ref().map_or(true, |g| g == group)
+            && self.stream.as_ref().map_or(true, |s| s == stream);
+
+        if !selected {
+            return true;
+        }
+
+        let mut lines = Vec::new();
+        for event in events.iter().filter(|e| self.pattern.matches(&e.message)) {
+            let event = TailEvent {
+                log_group_name: group,
+                log_stream_name: stream,
+                timestamp: event.timestamp,
+                message: &event.message,
+                ingestion_time,
+            };
+            lines.extend_from_slice(b"data: ");
+            lines.extend(serde_json::to_vec(&event).unwrap());
+            lines.extend_from_slice(b"\n\n");
+        }
+
+        lines.is_empty() || self.lines.unbounded_send(lines).is_ok()
+    }
+}
+
+/// Open a `/_mock/tail` feed. `query` may set `group`, `stream` and a
+/// filter `pattern`; the group need not exist yet.
+pub fn tail(context: &Context, query: Option<&str>) -> Response {
+    let mut params = params(query.unwrap_or(""));
+
+    let pattern = match params.remove("pattern") {
+        Some(pattern) => match pattern.parse::<Pattern>() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                let body = serde_json::json!({ "message": e.to_string() }).to_string();
+                return hyper::Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap();
+            }
+        },
+        None => Pattern::All,
+    };
+
+    let (lines, feed) = mpsc::unbounded();
+    lock(&context.live.feeds).push(Feed {
+        group: params.remove("group"),
+        stream: params.remove("stream"),
+        pattern,
+        lines,
+    });
+
+    // The comment goes out at once, so a client that has read it knows the
+    // feed is registered and will see every event put from then on.
+    let opened = futures::stream::once(Ok(b": tailing\n\n".to_vec()));
+    let feed = feed.map_err(|()| io::Error::new(io::ErrorKind::Other, "feed closed"));
+
+    hyper::Response::builder()
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(Body::wrap_stream(opened.chain(feed)))
+        .unwrap()
+}
+
+/// Split a query string into its decoded parameters.
+fn params(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = unescape(parts.next().unwrap_or(""));
+            let value = unescape(parts.next().unwrap_or(""));
+            (key, value)
+        })
+        .collect()
+}
+
+/// Undo form URL encoding: `+` for spaces and `%XX` escapes.
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = s
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A made up but well formed UUID.
+fn uuid(id: usize, kind: u16) -> String {
+    format!("{:08x}-{:04x}-4000-8000-{:012x}", id, kind, id)
+}
+
+/// The frames of one session's response body.
+struct Tail {
+    start: Option<Vec<u8>>,
+    updates: UnboundedReceiver<Vec<u8>>,
+    clock: Clock,
+    deadline: i64,
+    /// Wakes the session to look at the clock when no updates arrive.
+    recheck: Delay,
+    done: bool,
+}
+
+impl Tail {
+    fn timed_out(&mut self) -> bool {
+        loop {
+            if self.clock.now() >= self.deadline {
+                return true;
+            }
+
+            match self.recheck.poll() {
+                Ok(Async::NotReady) => return false,
+                Ok(Async::Ready(())) => self.recheck.reset(Instant::now() + RECHECK),
+                // A timer error ends the session the same way the timeout
+                // does.
+                Err(_) => return true,
+            }
+        }
+    }
+}
+
+impl Stream for Tail {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Vec<u8>>, io::Error> {
+        if let Some(start) = self.start.take() {
+            return Ok(Async::Ready(Some(start)));
+        }
+
+        if self.done {
+            return Ok(Async::Ready(None));
+        }
+
+        if self.timed_out() {
+            self.done = true;
+            let message = "Live Tail session has ended as it reached its maximum duration";
+            return Ok(Async::Ready(Some(exception(
+                "SessionTimeoutException",
+                message,
+            ))));
+        }
+
+        match self.updates.poll() {
+            Ok(Async::Ready(Some(frame))) => Ok(Async::Ready(Some(frame))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(None)) | Err(()) => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+fn event<T: Serialize>(kind: &str, payload: &T) -> Vec<u8> {
+    let headers = [
+        (":message-type", "event"),
+        (":event-type", kind),
+        (":content-type", "application/json"),
+    ];
+    frame(&headers, &serde_json::to_vec(payload).unwrap())
+}
+
+fn exception(kind: &str, message: &str) -> Vec<u8> {
+    let headers = [
+        (":message-type", "exception"),
+        (":exception-type", kind),
+        (":content-type", "application/json"),
+    ];
+    let payload = serde_json::json!({ "message": message });
+    frame(&headers, &serde_json::to_vec(&payload).unwrap())
+}
+
+/// Encode one event stream message:
+///
+/// ```text
+/// total length (u32) | headers length (u32) | prelude crc (u32)
+/// headers | payload | message crc (u32)
+/// ```
+///
+/// Every header here is a string, encoded as its name length (u8), name,
+/// value type 7, value length (u16) and value.
+pub fn frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for (name, value) in headers {
+        encoded.push(name.len() as u8);
+        encoded.extend_from_slice(name.as_bytes());
+        encoded.push(7);
+        encoded.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        encoded.extend_from_slice(value.as_bytes());
+    }
+
+    let total = 12 + encoded.len() + payload.len() + 4;
+
+    let mut message = Vec::with_capacity(total);
+    message.extend_from_slice(&(total as u32).to_be_bytes());
+    message.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+    let prelude_crc = crc32(&message);
+    message.extend_from_slice(&prelude_crc.to_be_bytes());
+    message.extend_from_slice(&encoded);
+    message.extend_from_slice(payload);
+    let message_crc = crc32(&message);
+    message.extend_from_slice(&message_crc.to_be_bytes());
+
+    message
+}
+
+/// CRC-32 (IEEE), as used by the event stream encoding.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
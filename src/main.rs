@@ -2,12 +2,199 @@
 extern crate tokio_trace;
 
 use hyper::rt;
-use mockwatchlogs::serve;
+use mockwatchlogs::faults::Rule;
+use mockwatchlogs::fixture::Fixture;
+use mockwatchlogs::limits::{Limits, Policy};
+use mockwatchlogs::mirror::Mirror;
+use mockwatchlogs::quotas::Quota;
+use mockwatchlogs::MockServer;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
 
-use tokio_trace::field;
+use tokio_trace::{dispatcher, field};
 use tokio_trace_futures::Instrument;
 
+const USAGE: &str = "usage: mockwatchlogs [options]
+
+options:
+    --addr <addr>        address to listen on (default 0.0.0.0:6000)
+    --fixture <path>     load a JSON or YAML fixture before serving
+    --data-dir <path>    persist state to, and recover it from, <path>
+    --max-bytes <n>      store at most <n> bytes of events
+    --max-events-per-stream <n>
+                         store at most <n> events in each stream
+    --max-groups <n>     store at most <n> log groups
+    --on-limit <policy>  reject (default), drop-oldest or spill
+    --spill-dir <path>   where the spill policy writes evicted events
+                         (defaults to the data directory)
+    --fault <rule>       inject a fault, e.g. action=PutLogEvents,nth=2,error=ThrottlingException
+                         (may be repeated)
+    --throttle           enforce the CloudWatch Logs rate quotas
+    --quota <quota>      override a quota, e.g. PutLogEvents/stream=10, implies --throttle
+                         (may be repeated)
+    --record <path>      append every request and response to <path> as JSONL
+    --replay <path>      apply a recording before serving
+    --replay-diff <path> replay a recording, report responses that differ from
+                         it and exit
+    --live-tail-timeout <secs>
+                         end StartLiveTail sessions after <secs> (default 3 hours)
+    --max-requests <n>   list at most the last <n> requests at /_mock/requests
+                         (default 1000, 0 turns request capture off)
+    --mirror             echo every accepted log event to stdout
+    --mirror-color       color the group/stream prefixes, implies --mirror
+    --mirror-group <name>
+                         only echo events in <name>, which may end in *, implies
+                         --mirror (may be repeated)
+    --healthcheck        exit 0 if the mock listening on --addr is ready, 1 if not";
+
+struct Args {
+    addr: SocketAddr,
+    fixture: Option<String>,
+    data_dir: Option<String>,
+    limits: Limits,
+    on_limit: Option<String>,
+    spill_dir: Option<String>,
+    faults: Vec<Rule>,
+    quotas: Vec<Quota>,
+    record: Option<String>,
+    replay: Option<String>,
+    diff: bool,
+    healthcheck: bool,
+    live_tail_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+    mirror: bool,
+    mirror_color: bool,
+    mirror_groups: Vec<String>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Args {
+            addr: "0.0.0.0:6000".parse().unwrap(),
+            fixture: None,
+            data_dir: None,
+            limits: Limits::default(),
+            on_limit: None,
+            spill_dir: None,
+            faults: Vec::new(),
+            quotas: Vec::new(),
+            record: None,
+            replay: None,
+            diff: false,
+            healthcheck: false,
+            live_tail_timeout: None,
+            max_requests: None,
+            mirror: false,
+            mirror_color: false,
+            mirror_groups: Vec::new(),
+        };
+
+        let mut throttle = false;
+        let mut overrides = Vec::new();
+
+        let mut argv = std::env::args().skip(1);
+        while let Some(arg) = argv.next() {
+            let mut value = || argv.next().ok_or_else(|| format!("{} needs a value", arg));
+
+            match arg.as_str() {
+                "--addr" => {
+                    let addr = value()?;
+                    args.addr = addr
+                        .parse()
+                        .map_err(|e| format!("invalid address {:?}: {}", addr, e))?;
+                }
+                "--fixture" => args.fixture = Some(value()?),
+                "--data-dir" => args.data_dir = Some(value()?),
+                "--max-bytes" => args.limits.max_bytes = Some(count(&arg, value()?)?),
+                "--max-events-per-stream" => {
+                    args.limits.max_events_per_stream = Some(count(&arg, value()?)?)
+                }
+                "--max-groups" => args.limits.max_groups = Some(count(&arg, value()?)?),
+                "--on-limit" => args.on_limit = Some(value()?),
+                "--spill-dir" => args.spill_dir = Some(value()?),
+                "--fault" => {
+                    let rule = value()?;
+                    let rule = rule
+                        .parse()
+                        .map_err(|e| format!("invalid fault {:?}: {}", rule, e))?;
+                    args.faults.push(rule);
+                }
+                "--throttle" => throttle = true,
+                "--quota" => {
+                    let quota = value()?;
+                    overrides.push(quota.parse::<Quota>().map_err(|e| e.to_string())?);
+                }
+                "--record" => args.record = Some(value()?),
+                "--replay" => args.replay = Some(value()?),
+                "--replay-diff" => {
+                    args.replay = Some(value()?);
+                    args.diff = true;
+                }
+                "--live-tail-timeout" => {
+                    let secs = count(&arg, value()?)?;
+                    args.live_tail_timeout = Some(Duration::from_secs(secs as u64));
+                }
+                "--max-requests" => args.max_requests = Some(count(&arg, value()?)?),
+                "--mirror" => args.mirror = true,
+                "--mirror-color" => {
+                    args.mirror = true;
+                    args.mirror_color = true;
+                }
+                "--mirror-group" => {
+                    args.mirror = true;
+                    args.mirror_groups.push(value()?);
+                }
+                "--healthcheck" => args.healthcheck = true,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("unknown argument {:?}", arg)),
+            }
+        }
+
+        if throttle || !overrides.is_empty() {
+            args.quotas = Quota::defaults();
+
+            for quota in overrides {
+                Quota::merge(&mut args.quotas, quota);
+            }
+        }
+
+        args.limits.policy = match args.on_limit.as_ref().map(String::as_str) {
+            None | Some("reject") => Policy::Reject,
+            Some("drop-oldest") => Policy::DropOldest,
+            Some("spill") => match args.spill_dir.as_ref().or_else(|| args.data_dir.as_ref()) {
+                Some(dir) => Policy::Spill(PathBuf::from(dir)),
+                None => return Err("--on-limit spill needs --spill-dir or --data-dir".into()),
+            },
+            Some(policy) => return Err(format!("unknown limit policy {:?}", policy)),
+        };
+
+        Ok(args)
+    }
+}
+
+fn count(arg: &str, value: String) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid value {:?} for {}: {}", value, arg, e))
+}
+
 fn main() {
+    let args = Args::parse().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    if args.healthcheck {
+        process::exit(if healthcheck(args.addr) { 0 } else { 1 });
+    }
+
     let subscriber = tokio_trace_fmt::FmtSubscriber::builder()
         .with_filter(tokio_trace_fmt::filter::EnvFilter::from(
             "mockwatchlogs=trace",
@@ -17,10 +204,137 @@ fn main() {
     tokio_trace_env_logger::try_init().expect("init log adapter");
 
     tokio_trace::subscriber::with_default(subscriber, || {
-        let addr = "0.0.0.0:6000".parse().unwrap();
+        let mut server = MockServer::new();
+        server.set_limits(args.limits.clone());
+        server.set_quotas(args.quotas.clone());
+
+        if let Some(timeout) = args.live_tail_timeout {
+            server.set_live_tail_timeout(timeout);
+        }
+        if let Some(max) = args.max_requests {
+            server.set_max_requests(max);
+        }
+
+        if args.diff {
+            // Nothing is served, `load` exits once the diff is reported.
+            load(&server, &args);
+        }
+
+        if let Some(path) = &args.record {
+            if let Err(e) = server.record(path) {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+        }
+
+        // Serve straight away so that probes can tell a mock that is still
+        // recovering from one that is down.
+        server.set_ready(false);
+
+        let addr = args.addr;
+        let loader = server.clone();
+        let dispatch = dispatcher::get_default(|dispatch| dispatch.clone());
+        thread::spawn(move || {
+            dispatcher::with_default(&dispatch, || {
+                load(&loader, &args);
+
+                // Only now, so that recovered and replayed events are not
+                // echoed again.
+                if args.mirror {
+                    let mut mirror = Mirror::stdout();
+                    mirror.color = args.mirror_color;
+                    mirror.groups = args.mirror_groups.clone();
+                    loader.set_mirror(Some(mirror));
+                }
+
+                loader.set_ready(true);
+                info!("Ready");
+            })
+        });
+
         let mut server_span = span!("server", local = &field::debug(addr));
-        let serve = serve(addr).instrument(server_span.clone());
+        let serve = server.serve(addr).instrument(server_span.clone());
 
         server_span.enter(|| rt::run(serve));
     });
 }
+
+/// Recover, load and replay whatever state `args` asks for, then add the
+/// fault injection rules.
+fn load(server: &MockServer, args: &Args) {
+    let mut recovered = false;
+
+    if let Some(dir) = &args.data_dir {
+        recovered = server.persist(dir).unwrap_or_else(|e| {
+            eprintln!("{}: {}", dir, e);
+            process::exit(1);
+        });
+    }
+
+    if let Some(path) = &args.fixture {
+        let fixture = Fixture::from_path(path).and_then(|fixture| {
+            // A recovered data directory already contains the fixture's
+            // state, but fault rules are not persisted.
+            if recovered {
+                fixture
+                    .faults
+                    .iter()
+                    .for_each(|r| server.add_fault(r.clone()));
+                Ok(())
+            } else {
+                server.load_fixture(&fixture)
+            }
+        });
+
+        if let Err(e) = fixture {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+
+        info!("Loaded fixture {}", path);
+    }
+
+    if let Some(path) = &args.replay {
+        let mismatches = server.replay(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        });
+
+        if args.diff {
+            for mismatch in &mismatches {
+                println!("{}", mismatch);
+            }
+
+            eprintln!("{} responses differ from {}", mismatches.len(), path);
+            process::exit(if mismatches.is_empty() { 0 } else { 1 });
+        }
+
+        info!("Replayed {}", path);
+    }
+
+    for rule in &args.faults {
+        server.add_fault(rule.clone());
+    }
+}
+
+/// Ask the mock listening on `addr` whether it is ready.
+fn healthcheck(addr: SocketAddr) -> bool {
+    let ip = if addr.ip().is_unspecified() {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        addr.ip()
+    };
+    let timeout = Duration::from_secs(2);
+
+    let check = || -> io::Result<bool> {
+        let mut stream = TcpStream::connect_timeout(&SocketAddr::new(ip, addr.port()), timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.write_all(b"GET /ready HTTP/1.0\r\n\r\n")?;
+
+        let mut res = String::new();
+        stream.read_to_string(&mut res)?;
+        Ok(res.starts_with("HTTP/1.1 200") || res.starts_with("HTTP/1.0 200"))
+    };
+
+    check().unwrap_or(false)
+}
@@ -0,0 +1,312 @@
+//! Prometheus metrics, served at `/metrics`.
+//!
+//! Actions do not know about metrics. Instead the responses they build
+//! carry what the dispatcher needs as extensions: an `ErrorType` on every
+//! AWS error and an `Ingested` on successful `PutLogEvents` calls. The
+//! dispatcher records them itself rather than through the `action` tracing
+//! spans, so metrics work whether or not a subscriber is installed.
+//!
+//! Calls to actions the mock does not implement are counted as
+//! `action="unknown"`, so clients cannot create a series per target.
+
+use crate::streams::{lock, Context};
+use crate::Response;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The `__type` of an error response.
+#[derive(Debug, Clone)]
+pub struct ErrorType(pub String);
+
+/// Events a `PutLogEvents` call stored, counted the way `storedBytes` is.
+#[derive(Debug, Clone)]
+pub struct Ingested {
+    pub group: String,
+    pub events: usize,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    connections: AtomicUsize,
+    /// Requests refused because the mock was still starting.
+    refused: AtomicUsize,
+    registry: Mutex<Registry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: BTreeMap<String, u64>,
+    errors: BTreeMap<(String, String), u64>,
+    latency: BTreeMap<String, Histogram>,
+    ingested_events: BTreeMap<String, u64>,
+    ingested_bytes: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Counts per bucket, not yet cumulative.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; BUCKETS.len()];
+        }
+
+        if let Some(i) = BUCKETS.iter().position(|&le| value <= le) {
+            self.buckets[i] += 1;
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Counts an open connection until it is dropped.
+#[derive(Debug)]
+pub struct Connection(Arc<Metrics>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl Metrics {
+    pub fn connection(metrics: &Arc<Metrics>) -> Connection {
+        metrics.connections.fetch_add(1, Ordering::AcqRel);
+        Connection(metrics.clone())
+    }
+
+    /// Count a request refused while the mock was starting.
+    pub fn refuse(&self) {
+        self.refused.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Count a call to `action` that produced `res` after `elapsed`.
+    pub fn observe(&self, action: &str, res: &Response, elapsed: Duration) {
+        let action = match res.extensions().get::<ErrorType>() {
+            Some(ErrorType(kind)) if kind == "UnknownOperationException" => "unknown",
+            _ => action.rsplit('.').next().unwrap_or(action),
+        };
+        let action = action.to_string();
+        let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+
+        let mut registry = lock(&self.registry);
+        *registry.requests.entry(action.clone()).or_insert(0) += 1;
+
+        if let Some(ErrorType(kind)) = res.extensions().get::<ErrorType>() {
+            *registry
+                .errors
+                .entry((action.clone(), kind.clone()))
+                .or_insert(0) += 1;
+        }
+
+        if let Some(ingested) = res.extensions().get::<Ingested>() {
+            *registry
+                .ingested_events
+                .entry(ingested.group.clone())
+                .or_insert(0) += ingested.events as u64;
+            *registry
+                .ingested_bytes
+                .entry(ingested.group.clone())
+                .or_insert(0) += ingested.bytes as u64;
+        }
+
+        registry
+            .latency
+            .entry(action)
+            .or_insert_with(Histogram::default)
+            .observe(seconds);
+    }
+
+    /// Everything in the Prometheus text exposition format.
+    pub fn render(&self, context: &Context) -> String {
+        let mut out = String::new();
+
+        {
+            let registry = lock(&self.registry);
+
+            header(
+                &mut out,
+                "requests_total",
+                "counter",
+                "AWS requests by action.",
+            );
+            for (action, count) in &registry.requests {
+                sample(
+                    &mut out,
+                    "requests_total",
+                    &[("action", action.as_str())],
+                    *count,
+                );
+            }
+
+            header(
+                &mut out,
+                "errors_total",
+                "counter",
+                "AWS error responses by action and __type.",
+            );
+            for ((action, kind), count) in &registry.errors {
+                let labels = [("action", action.as_str()), ("type", kind.as_str())];
+                sample(&mut out, "errors_total", &labels, *count);
+            }
+
+            header(
+                &mut out,
+                "request_duration_seconds",
+                "histogram",
+                "Time spent producing AWS responses.",
+            );
+            for (action, histogram) in &registry.latency {
+                let mut cumulative = 0;
+                for (le, count) in BUCKETS.iter().zip(&histogram.buckets) {
+                    cumulative += count;
+                    let le = le.to_string();
+                    let labels = [("action", action.as_str()), ("le", le.as_str())];
+                    sample(
+                        &mut out,
+                        "request_duration_seconds_bucket",
+                        &labels,
+                        cumulative,
+                    );
+                }
+
+                let labels = [("action", action.as_str()), ("le", "+Inf")];
+                let name = "request_duration_seconds_bucket";
+                sample(&mut out, name, &labels, histogram.count);
+                let labels = [("action", action.as_str())];
+                sample(
+                    &mut out,
+                    "request_duration_seconds_sum",
+                    &labels,
+                    histogram.sum,
+                );
+                sample(
+                    &mut out,
+                    "request_duration_seconds_count",
+                    &labels,
+                    histogram.count,
+                );
+            }
+
+            header(
+                &mut out,
+                "ingested_events_total",
+                "counter",
+                "Events stored by PutLogEvents, by log group.",
+            );
+            for (group, count) in &registry.ingested_events {
+                sample(
+                    &mut out,
+                    "ingested_events_total",
+                    &[("group", group.as_str())],
+                    *count,
+                );
+            }
+
+            header(
+                &mut out,
+                "ingested_bytes_total",
+                "counter",
+                "Bytes stored by PutLogEvents, by log group.",
+            );
+            for (group, count) in &registry.ingested_bytes {
+                sample(
+                    &mut out,
+                    "ingested_bytes_total",
+                    &[("group", group.as_str())],
+                    *count,
+                );
+            }
+        }
+
+        let mut stored = Vec::new();
+        for group in context.groups() {
+            let name = lock(&group).name.clone();
+
+            if let Ok(usage) = context.group_usage(&name) {
+                stored.push((name, usage.events, usage.stored_bytes));
+            }
+        }
+
+        header(
+            &mut out,
+            "stored_events",
+            "gauge",
+            "Events currently stored.",
+        );
+        for (group, events, _) in &stored {
+            sample(
+                &mut out,
+                "stored_events",
+                &[("group", group.as_str())],
+                *events,
+            );
+        }
+
+        header(&mut out, "stored_bytes", "gauge", "Bytes currently stored.");
+        for (group, _, bytes) in &stored {
+            sample(
+                &mut out,
+                "stored_bytes",
+                &[("group", group.as_str())],
+                *bytes,
+            );
+        }
+
+        header(
+            &mut out,
+            "refused_starting_total",
+            "counter",
+            "Requests refused while the mock was starting.",
+        );
+        let refused = self.refused.load(Ordering::Acquire);
+        sample(&mut out, "refused_starting_total", &[], refused);
+
+        header(&mut out, "active_connections", "gauge", "Open connections.");
+        let connections = self.connections.load(Ordering::Acquire);
+        sample(&mut out, "active_connections", &[], connections);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP mockwatchlogs_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE mockwatchlogs_{} {}", name, kind);
+}
+
+fn sample<V: std::fmt::Display>(out: &mut String, name: &str, labels: &[(&str, &str)], value: V) {
+    let _ = write!(out, "mockwatchlogs_{}", name);
+
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+            .collect::<Vec<_>>();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,136 @@
+//! Echoing ingested events to the mock's own output.
+//!
+//! With a `Mirror` set, every event `PutLogEvents` stores is written out as
+//! one line:
+//!
+//! ```text
+//! app/web | 2019-03-04T05:06:07.089Z GET /health 200
+//! ```
+//!
+//! so that services run against the mock under `docker compose up` show
+//! their logs inline, as they would with a local logging driver.
+
+use crate::streams::{lock, DAY};
+use crate::types::InputLogEvent;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// The colors prefixes cycle through, as ANSI foreground codes.
+const COLORS: &[u8] = &[36, 33, 32, 35, 34, 31];
+
+pub struct Mirror {
+    /// Only groups matching one of these names are echoed, or every group
+    /// if there are none. A trailing `*` matches any suffix.
+    pub groups: Vec<String>,
+    /// Color the `group/stream` prefixes, each in its own color.
+    pub color: bool,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Mirror {
+    /// Echo to `out`, uncolored and for every group.
+    pub fn new<W: Write + Send + 'static>(out: W) -> Self {
+        Mirror {
+            groups: Vec::new(),
+            color: false,
+            out: Mutex::new(Box::new(out)),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Mirror::new(io::stdout())
+    }
+
+    pub fn is_mirrored(&self, group: &str) -> bool {
+        self.groups.is_empty()
+            || self.groups.iter().any(|name| {
+                if name.ends_with('*') {
+                    group.starts_with(&name[..name.len() - 1])
+                } else {
+                    name == group
+                }
+            })
+    }
+
+    /// Echo `events`, just stored in `group`/`stream`.
+    pub fn write(&self, group: &str, stream: &str, events: &[InputLogEvent]) {
+        if !self.is_mirrored(group) {
+            return;
+        }
+
+        let prefix = format!("{}/{}", group, stream);
+        let prefix = if self.color {
+            format!(
+                "\x1b[{}m{}\x1b[0m",
+                COLORS[hash(&prefix) % COLORS.len()],
+                prefix
+            )
+        } else {
+            prefix
+        };
+
+        let mut lines = String::new();
+        for event in events {
+            let message = event.message.trim_end_matches(|c| c == '\n' || c == '\r');
+            lines.push_str(&format!(
+                "{} | {} {}\n",
+                prefix,
+                iso8601(event.timestamp),
+                message
+            ));
+        }
+
+        // Mirroring is best effort, a closed stdout must not fail requests.
+        let mut out = lock(&self.out);
+        let _ = out.write_all(lines.as_bytes()).and_then(|_| out.flush());
+    }
+}
+
+impl fmt::Debug for Mirror {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mirror")
+            .field("groups", &self.groups)
+            .field("color", &self.color)
+            .finish()
+    }
+}
+
+/// FNV-1a, so that a prefix keeps its color across runs.
+fn hash(s: &str) -> usize {
+    s.bytes().fold(0x811c_9dc5u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    }) as usize
+}
+
+/// Format milliseconds since the epoch as `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+fn iso8601(millis: i64) -> String {
+    let (days, ms) = floor_div(millis, DAY);
+
+    // Howard Hinnant's civil_from_days.
+    let (era, doe) = floor_div(days + 719_468, 146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// Division rounding towards negative infinity, and its non-negative
+/// remainder.
+fn floor_div(a: i64, b: i64) -> (i64, i64) {
+    let r = ((a % b) + b) % b;
+    ((a - r) / b, r)
+}
@@ -0,0 +1,212 @@
+//! Optional on-disk persistence.
+//!
+//! Every successful mutating action is appended to `wal.jsonl` in the data
+//! directory, and synced to disk, before its response is sent. Once the log grows past
+//! `SNAPSHOT_EVERY` entries the whole `Context` is written to
+//! `snapshot.json` and the log is truncated. On startup the snapshot is
+//! loaded and the remaining log entries are replayed on top of it.
+//!
+//! Actions run concurrently, so writers hold a shared checkpoint lock while
+//! they apply and log an action. Snapshots take it exclusively, which
+//! guarantees that every action in the truncated log is part of the
+//! snapshot and none is applied twice on recovery.
+//!
+//! Only the `Context` is persisted. The admin API's clock, limits, fault
+//! rules and quotas are settings rather than state, and start over from
+//! the command line after a restart.
+
+use crate::streams::{lock, read, write, Context};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+
+const SNAPSHOT: &str = "snapshot.json";
+const WAL: &str = "wal.jsonl";
+
+/// The number of log entries written between snapshots.
+pub const SNAPSHOT_EVERY: usize = 1000;
+
+/// Actions that only read state and therefore never need to be logged.
+const READ_ONLY_PREFIXES: &[&str] = &["Describe", "Get", "List", "Filter", "Test", "StartLiveTail"];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    target: String,
+    body: String,
+    /// The clock's time when the action was applied. Replay runs at this
+    /// time so that ingestion times and expiry come out the same.
+    #[serde(default)]
+    time: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct Persistence {
+    dir: PathBuf,
+    log: Mutex<Log>,
+    checkpoint: RwLock<()>,
+}
+
+#[derive(Debug)]
+struct Log {
+    wal: File,
+    entries: usize,
+}
+
+/// Whether `action` can change the mock's state.
+pub fn is_mutating(action: &str) -> bool {
+    let name = action.rsplit('.').next().unwrap_or(action);
+    !READ_ONLY_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+impl Persistence {
+    /// Open the data directory at `dir`, creating it if needed, and recover
+    /// whatever state it holds into `context`.
+    ///
+    /// Returns `true` if there was previous state to recover. `replay` is
+    /// called for each log entry written since the last snapshot.
+    pub fn open<P, F>(dir: P, context: &Context, mut replay: F) -> io::Result<(Persistence, bool)>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Context, &str, &Bytes),
+    {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut recovered = false;
+
+        let snapshot_path = dir.join(SNAPSHOT);
+        if snapshot_path.exists() {
+            let contents = fs::read(&snapshot_path)?;
+            let snapshot = serde_json::from_slice(&contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            context.restore(snapshot);
+            recovered = true;
+        }
+
+        let wal_path = dir.join(WAL);
+        if wal_path.exists() {
+            context.replaying.store(true, Ordering::Release);
+            let replayed = replay_log(&wal_path, context, &mut replay);
+            context.replaying.store(false, Ordering::Release);
+
+            recovered |= replayed?;
+        }
+
+        let wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&wal_path)?;
+        let persistence = Persistence {
+            dir,
+            log: Mutex::new(Log { wal, entries: 0 }),
+            checkpoint: RwLock::new(()),
+        };
+
+        if recovered {
+            info!("Recovered state from {}", persistence.dir.display());
+            persistence.snapshot(context)?;
+        }
+
+        Ok((persistence, recovered))
+    }
+
+    /// Hold off snapshots while an action is applied and logged.
+    pub fn writer(&self) -> RwLockReadGuard<()> {
+        read(&self.checkpoint)
+    }
+
+    /// Log a mutating action that was applied at `time`.
+    ///
+    /// Returns `true` once enough entries have been written that a snapshot
+    /// is due.
+    pub fn append(&self, target: &str, body: &Bytes, time: i64) -> io::Result<bool> {
+        let entry = Entry {
+            target: target.to_string(),
+            body: String::from_utf8_lossy(&body[..]).into_owned(),
+            time: Some(time),
+        };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        let mut log = lock(&self.log);
+        log.wal.write_all(&line)?;
+        log.wal.flush()?;
+        log.wal.sync_data()?;
+        log.entries += 1;
+
+        Ok(log.entries >= SNAPSHOT_EVERY)
+    }
+
+    /// Write `context` out in full and truncate the log.
+    ///
+    /// This waits for in-flight writers, so it must not be called while
+    /// holding a `writer` guard.
+    pub fn snapshot(&self, context: &Context) -> io::Result<()> {
+        let _quiesced = write(&self.checkpoint);
+        let mut log = lock(&self.log);
+
+        let tmp_path = self.dir.join(format!("{}.tmp", SNAPSHOT));
+
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            serde_json::to_writer(&mut tmp, context)?;
+            tmp.sync_all()?;
+        }
+
+        fs::rename(&tmp_path, self.dir.join(SNAPSHOT))?;
+
+        log.wal.set_len(0)?;
+        log.wal.sync_all()?;
+        log.entries = 0;
+
+        debug!("Wrote snapshot to {}", self.dir.display());
+
+        Ok(())
+    }
+}
+
+/// Replay the entries in the log at `path`, returning whether there were
+/// any.
+fn replay_log<F>(path: &Path, context: &Context, replay: &mut F) -> io::Result<bool>
+where
+    F: FnMut(&Context, &str, &Bytes),
+{
+    let reader = BufReader::new(File::open(path)?);
+    let mut replayed = false;
+
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // A crash can leave a partially written final entry behind, which
+        // is the only thing that can fail to parse here.
+        let entry: Entry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Ignoring corrupt log entry {}: {}", n + 1, e);
+                break;
+            }
+        };
+
+        let body = Bytes::from(entry.body);
+
+        match entry.time {
+            Some(time) => context
+                .clock()
+                .at(time, || replay(context, &entry.target, &body)),
+            None => replay(context, &entry.target, &body),
+        }
+        replayed = true;
+    }
+
+    Ok(replayed)
+}
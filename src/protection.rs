@@ -0,0 +1,392 @@
+//! Data protection policies, which mask sensitive data in a group's events.
+//!
+//! A policy has an `Audit` statement and a `Deidentify` statement naming
+//! the same data identifiers: managed ones, by ARN, or custom ones defined
+//! by a regex in the policy's `Configuration`. Events are stored as they
+//! were sent and masked as they are read, so `GetLogEvents` and
+//! `FilterLogEvents` callers passing `unmask` see the original messages.
+//! Live tails and the mirror only ever see masked ones.
+//! An account level `DATA_PROTECTION_POLICY` masks the groups it selects
+//! on top of their own policies. Audit findings are not delivered anywhere.
+
+use crate::account::{account_policy, group_name, json, DATA_PROTECTION_POLICY};
+use crate::actions::ServiceError;
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+use regex::{Captures, Regex};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// The largest policy document, in bytes.
+const MAX_POLICY_SIZE: usize = 30720;
+
+/// How many custom data identifiers a policy may define.
+const MAX_CUSTOM_IDENTIFIERS: usize = 10;
+
+/// The longest custom data identifier regex.
+const MAX_CUSTOM_REGEX: usize = 200;
+
+const MANAGED_PREFIX: &str = "arn:aws:dataprotection::aws:data-identifier/";
+
+/// The managed data identifiers the mock knows, with the pattern finding
+/// candidates and the check a candidate must pass to be masked.
+const MANAGED: &[(&str, &str, fn(&str) -> bool)] = &[
+    (
+        "EmailAddress",
+        r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+        any,
+    ),
+    ("CreditCardNumber", r"\b\d(?:[ -]?\d){12,18}\b", luhn),
+    ("AwsSecretKey", r"[A-Za-z0-9/+]+", secret_key),
+];
+
+/// A group's data protection policy, compiled when it is put or loaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataProtectionPolicy {
+    pub document: String,
+    pub last_updated_time: i64,
+    #[serde(skip)]
+    pub(crate) masker: Arc<Masker>,
+}
+
+impl<'de> Deserialize<'de> for DataProtectionPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Stored {
+            document: String,
+            last_updated_time: i64,
+        }
+
+        let stored = Stored::deserialize(deserializer)?;
+        let masker = Masker::parse(&stored.document).map_err(<D::Error as de::Error>::custom)?;
+
+        Ok(DataProtectionPolicy {
+            document: stored.document,
+            last_updated_time: stored.last_updated_time,
+            masker: Arc::new(masker),
+        })
+    }
+}
+
+fn any(_: &str) -> bool {
+    true
+}
+
+/// Whether the digits of `number` pass the Luhn check card numbers carry.
+fn luhn(number: &str) -> bool {
+    let digits = number.chars().filter_map(|c| c.to_digit(10));
+    let sum: u32 = digits
+        .collect::<Vec<_>>()
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Secret access keys are 40 base64 characters. Requiring all of upper
+/// case, lower case and digits keeps hex digests and long words unmasked.
+fn secret_key(word: &str) -> bool {
+    word.len() == 40
+        && word.chars().any(|c| c.is_ascii_uppercase())
+        && word.chars().any(|c| c.is_ascii_lowercase())
+        && word.chars().any(|c| c.is_ascii_digit())
+}
+
+#[derive(Debug)]
+struct Identifier {
+    pattern: Regex,
+    check: fn(&str) -> bool,
+}
+
+/// The data identifiers of a policy, compiled.
+#[derive(Debug)]
+pub(crate) struct Masker {
+    identifiers: Vec<Identifier>,
+}
+
+impl Masker {
+    /// Compile the policy `document`, or describe why it is invalid.
+    pub fn parse(document: &str) -> Result<Masker, String> {
+        let policy = match serde_json::from_str::<Value>(document) {
+            Ok(Value::Object(policy)) => policy,
+            _ => return Err("The policy document is not a JSON object".into()),
+        };
+
+        let mut custom = Vec::new();
+        let configured = policy
+            .get("Configuration")
+            .and_then(|c| c.get("CustomDataIdentifier"));
+        if let Some(configured) = configured {
+            let configured = configured
+                .as_array()
+                .ok_or("CustomDataIdentifier must be a list")?;
+
+            if configured.len() > MAX_CUSTOM_IDENTIFIERS {
+                return Err(format!(
+                    "A policy may define at most {} custom data identifiers",
+                    MAX_CUSTOM_IDENTIFIERS
+                ));
+            }
+
+            for identifier in configured {
+                let name = identifier.get("Name").and_then(Value::as_str);
+                let regex = identifier.get("Regex").and_then(Value::as_str);
+                let (name, regex) = match (name, regex) {
+                    (Some(name), Some(regex)) => (name, regex),
+                    _ => return Err("Custom data identifiers need a Name and a Regex".into()),
+                };
+
+                if name.is_empty() || name.starts_with("arn:") {
+                    return Err(format!("Invalid custom data identifier name: {:?}", name));
+                }
+                if custom.iter().any(|(n, _)| n == name) {
+                    return Err(format!("Duplicate custom data identifier: {}", name));
+                }
+                if regex.is_empty() || regex.len() > MAX_CUSTOM_REGEX {
+                    return Err(format!(
+                        "The regex of {} must be between 1 and {} characters",
+                        name, MAX_CUSTOM_REGEX
+                    ));
+                }
+
+                let pattern = Regex::new(regex)
+                    .map_err(|_| format!("Invalid regex for custom data identifier {}", name))?;
+                custom.push((name.to_string(), pattern));
+            }
+        }
+
+        let statements = policy
+            .get("Statement")
+            .and_then(Value::as_array)
+            .ok_or("The policy must have a Statement list")?;
+
+        let mut audited = None;
+        let mut deidentified = None;
+        for statement in statements {
+            let identifiers = statement
+                .get("DataIdentifier")
+                .and_then(Value::as_array)
+                .filter(|identifiers| !identifiers.is_empty())
+                .ok_or("Every statement must name at least one DataIdentifier")?;
+            let identifiers = identifiers
+                .iter()
+                .map(|i| i.as_str().map(str::to_string))
+                .collect::<Option<HashSet<_>>>()
+                .ok_or("Data identifiers must be strings")?;
+
+            let operation = statement.get("Operation").and_then(Value::as_object);
+            let slot = match operation {
+                Some(op) if op.len() == 1 && op.contains_key("Audit") => &mut audited,
+                Some(op) if op.len() == 1 && op.get("Deidentify").is_some() => {
+                    if op["Deidentify"].get("MaskConfig").is_none() {
+                        return Err("Deidentify operations must have a MaskConfig".into());
+                    }
+                    &mut deidentified
+                }
+                _ => {
+                    return Err(
+                        "Every statement must have an Operation of Audit or Deidentify".into(),
+                    );
+                }
+            };
+
+            if slot.is_some() {
+                return Err("A policy must have one Audit and one Deidentify statement".into());
+            }
+            *slot = Some(identifiers);
+        }
+
+        let identifiers = match (audited, deidentified) {
+            (Some(audited), Some(deidentified)) => {
+                if audited != deidentified {
+                    return Err(
+                        "The Audit and Deidentify statements must name the same data identifiers"
+                            .into(),
+                    );
+                }
+                deidentified
+            }
+            _ => return Err("A policy must have one Audit and one Deidentify statement".into()),
+        };
+
+        let mut identifiers = identifiers.into_iter().collect::<Vec<_>>();
+        identifiers.sort();
+
+        let mut compiled = Vec::new();
+        for identifier in identifiers {
+            if identifier.starts_with(MANAGED_PREFIX) {
+                let name = &identifier[MANAGED_PREFIX.len()..];
+                let &(_, pattern, check) = MANAGED
+                    .iter()
+                    .find(|(managed, _, _)| *managed == name)
+                    .ok_or_else(|| format!("Unsupported data identifier: {}", identifier))?;
+
+                compiled.push(Identifier {
+                    pattern: Regex::new(pattern).unwrap(),
+                    check,
+                });
+            } else {
+                let (_, pattern) = custom
+                    .iter()
+                    .find(|(name, _)| *name == identifier)
+                    .ok_or_else(|| format!("Unknown data identifier: {}", identifier))?;
+
+                compiled.push(Identifier {
+                    pattern: pattern.clone(),
+                    check: any,
+                });
+            }
+        }
+
+        Ok(Masker {
+            identifiers: compiled,
+        })
+    }
+
+    /// `message` with every match of the policy's identifiers replaced by
+    /// as many asterisks.
+    pub fn mask<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        let mut message = Cow::Borrowed(message);
+
+        for identifier in &self.identifiers {
+            let masked = identifier.pattern.replace_all(&message, |caps: &Captures| {
+                let found = &caps[0];
+                if (identifier.check)(found) {
+                    "*".repeat(found.chars().count())
+                } else {
+                    found.to_string()
+                }
+            });
+
+            if let Cow::Owned(masked) = masked {
+                message = Cow::Owned(masked);
+            }
+        }
+
+        message
+    }
+}
+
+/// The policies masking a group's events, applied in turn.
+pub(crate) struct Masking(Vec<Arc<Masker>>);
+
+impl Masking {
+    pub fn mask<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        let mut message = Cow::Borrowed(message);
+
+        for masker in &self.0 {
+            if let Cow::Owned(masked) = masker.mask(&message) {
+                message = Cow::Owned(masked);
+            }
+        }
+
+        message
+    }
+}
+
+/// The masking for the events of the group `name`, from its own policy and
+/// any account policy selecting it, unless the caller asked to `unmask`
+/// them or there are no policies.
+pub(crate) fn masker(context: &Context, name: &str, unmask: Option<bool>) -> Option<Masking> {
+    if unmask == Some(true) {
+        return None;
+    }
+
+    let group = context.group(name)?;
+    let own = lock(&group)
+        .data_protection_policy
+        .as_ref()
+        .map(|policy| policy.masker.clone());
+    let account =
+        account_policy(context, DATA_PROTECTION_POLICY, name).and_then(|policy| policy.masker);
+
+    let maskers = own.into_iter().chain(account).collect::<Vec<_>>();
+    if maskers.is_empty() {
+        None
+    } else {
+        Some(Masking(maskers))
+    }
+}
+
+fn group_not_found() -> ServiceError {
+    ServiceError::NotFound("The specified log group does not exist.".into())
+}
+
+pub fn put_data_protection_policy(
+    context: &Context,
+    request: PutDataProtectionPolicyRequest,
+) -> Result<Response, ServiceError> {
+    if request.policy_document.len() > MAX_POLICY_SIZE {
+        return Err(ServiceError::LimitExceeded(format!(
+            "policyDocument must be at most {} bytes",
+            MAX_POLICY_SIZE
+        )));
+    }
+    let masker = Masker::parse(&request.policy_document).map_err(ServiceError::InvalidParameter)?;
+
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let mut group = lock(&group);
+
+    let policy = DataProtectionPolicy {
+        document: request.policy_document,
+        last_updated_time: context.clock().now(),
+        masker: Arc::new(masker),
+    };
+
+    let res = PutDataProtectionPolicyResponse {
+        last_updated_time: Some(policy.last_updated_time),
+        log_group_identifier: Some(request.log_group_identifier),
+        policy_document: Some(policy.document.clone()),
+    };
+    group.data_protection_policy = Some(policy);
+
+    Ok(json(&res))
+}
+
+pub fn get_data_protection_policy(
+    context: &Context,
+    request: GetDataProtectionPolicyRequest,
+) -> Result<Response, ServiceError> {
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let group = lock(&group);
+    let policy = group.data_protection_policy.as_ref();
+
+    Ok(json(&GetDataProtectionPolicyResponse {
+        last_updated_time: policy.map(|p| p.last_updated_time),
+        log_group_identifier: Some(request.log_group_identifier),
+        policy_document: policy.map(|p| p.document.clone()),
+    }))
+}
+
+pub fn delete_data_protection_policy(
+    context: &Context,
+    request: DeleteDataProtectionPolicyRequest,
+) -> Result<Response, ServiceError> {
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let mut group = lock(&group);
+
+    match group.data_protection_policy.take() {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(ServiceError::NotFound(format!(
+            "The log group {} has no data protection policy",
+            request.log_group_identifier
+        ))),
+    }
+}
@@ -0,0 +1,242 @@
+//! Per-action request rate quotas.
+//!
+//! Quotas are off unless configured. `Quota::defaults` mirrors the
+//! CloudWatch Logs service quotas, and requests over a quota fail with
+//! `ThrottlingException` the way the real service does. Each quota is a
+//! token bucket holding up to one second's worth of requests, refilled by
+//! the mock's clock, so freezing the clock keeps a throttled action
+//! throttled until it is advanced.
+
+use crate::faults::Names;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quota {
+    /// The action name, such as `PutLogEvents`.
+    pub action: String,
+    #[serde(default)]
+    pub scope: Scope,
+    /// Requests allowed per second.
+    #[serde(deserialize_with = "deserialize_tps")]
+    pub tps: f64,
+}
+
+/// What a quota is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Account,
+    /// Each log stream separately.
+    Stream,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope::Account
+    }
+}
+
+impl Quota {
+    fn new(action: &str, scope: Scope, tps: f64) -> Self {
+        Quota {
+            action: action.to_string(),
+            scope,
+            tps,
+        }
+    }
+
+    /// The CloudWatch Logs defaults for the actions the mock implements.
+    pub fn defaults() -> Vec<Quota> {
+        vec![
+            Quota::new("PutLogEvents", Scope::Stream, 5.0),
+            Quota::new("PutLogEvents", Scope::Account, 800.0),
+            Quota::new("GetLogEvents", Scope::Account, 25.0),
+            Quota::new("FilterLogEvents", Scope::Account, 5.0),
+            Quota::new("DescribeLogStreams", Scope::Account, 5.0),
+            Quota::new("DescribeLogGroups", Scope::Account, 10.0),
+            Quota::new("CreateLogGroup", Scope::Account, 5.0),
+            Quota::new("CreateLogStream", Scope::Account, 50.0),
+        ]
+    }
+
+    /// Replace the quota in `quotas` for the same action and scope, or add
+    /// it if there is none.
+    pub fn merge(quotas: &mut Vec<Quota>, quota: Quota) {
+        match quotas
+            .iter_mut()
+            .find(|q| q.action == quota.action && q.scope == quota.scope)
+        {
+            Some(existing) => *existing = quota,
+            None => quotas.push(quota),
+        }
+    }
+}
+
+/// Parse the `--quota` command line syntax, `<action>[/stream]=<tps>`, as
+/// in `PutLogEvents/stream=10` or `DescribeLogGroups=2`.
+impl FromStr for Quota {
+    type Err = ParseQuotaError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseQuotaError(format!("invalid quota {:?}", spec));
+
+        let mut parts = spec.splitn(2, '=');
+        let target = parts.next().ok_or_else(invalid)?;
+        let tps = parts.next().ok_or_else(invalid)?;
+        let tps = tps.parse::<f64>().map_err(|_| invalid())?;
+        let tps = rate(tps).map_err(ParseQuotaError)?;
+
+        let mut target = target.splitn(2, '/');
+        let action = target
+            .next()
+            .filter(|a| !a.is_empty())
+            .ok_or_else(invalid)?;
+        let scope = match target.next() {
+            None | Some("account") => Scope::Account,
+            Some("stream") => Scope::Stream,
+            Some(_) => return Err(invalid()),
+        };
+
+        Ok(Quota::new(action, scope, tps))
+    }
+}
+
+/// `tps`, if it is a usable rate.
+fn rate(tps: f64) -> Result<f64, String> {
+    if tps.is_finite() && tps > 0.0 {
+        Ok(tps)
+    } else {
+        Err(format!(
+            "a quota must allow more than 0 requests per second, not {}",
+            tps
+        ))
+    }
+}
+
+fn deserialize_tps<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    rate(f64::deserialize(deserializer)?).map_err(<D::Error as de::Error>::custom)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseQuotaError(String);
+
+impl fmt::Display for ParseQuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseQuotaError {}
+
+/// The configured quotas and the buckets counting against them.
+#[derive(Debug, Default)]
+pub struct Limiter {
+    quotas: Vec<Quota>,
+    /// Keyed by quota index and, for stream quotas, `group/stream`.
+    buckets: HashMap<(usize, Option<String>), Bucket>,
+    /// When full buckets were last dropped.
+    pruned: i64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: i64,
+}
+
+impl Limiter {
+    pub fn quotas(&self) -> &[Quota] {
+        &self.quotas
+    }
+
+    pub fn set(&mut self, quotas: Vec<Quota>) {
+        self.quotas = quotas;
+        self.buckets.clear();
+    }
+
+    /// Drop the buckets that have refilled completely by `now`, at most
+    /// once a second.
+    ///
+    /// A full bucket behaves exactly like a missing one, so this only
+    /// forgets streams that have gone quiet, including every stream that
+    /// has been deleted, and keeps the map from growing with each new
+    /// stream name.
+    fn prune(&mut self, now: i64) {
+        if now - self.pruned < 1000 {
+            return;
+        }
+        self.pruned = now;
+
+        let quotas = &self.quotas;
+        self.buckets.retain(|(i, _), bucket| {
+            let tps = quotas[*i].tps;
+            let elapsed = (now - bucket.updated).max(0) as f64 / 1000.0;
+            bucket.tokens + elapsed * tps < tps.max(1.0)
+        });
+    }
+
+    /// Take a request to `action` at `now` out of its quotas, returning
+    /// `false` if any of them is exhausted.
+    pub fn check(&mut self, action: &str, body: &[u8], now: i64) -> bool {
+        let name = action.rsplit('.').next().unwrap_or(action);
+
+        let matching = self
+            .quotas
+            .iter()
+            .enumerate()
+            .filter(|(_, quota)| quota.action == name)
+            .map(|(i, quota)| (i, quota.scope, quota.tps))
+            .collect::<Vec<_>>();
+
+        if matching.is_empty() {
+            return true;
+        }
+
+        self.prune(now);
+
+        let names = serde_json::from_slice::<Names>(body).unwrap_or_default();
+        let stream = match (names.group, names.stream) {
+            (Some(group), Some(stream)) => Some(format!("{}/{}", group, stream)),
+            _ => None,
+        };
+
+        let keys = matching
+            .into_iter()
+            .filter_map(|(i, scope, tps)| match scope {
+                Scope::Account => Some(((i, None), tps)),
+                Scope::Stream => stream.clone().map(|s| ((i, Some(s)), tps)),
+            })
+            .collect::<Vec<_>>();
+
+        // Refill everything first so that a request rejected by one quota
+        // does not use up another.
+        for (key, tps) in &keys {
+            // Quotas below one per second still allow a single request.
+            let capacity = tps.max(1.0);
+            let bucket = self.buckets.entry(key.clone()).or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+
+            let elapsed = (now - bucket.updated).max(0) as f64 / 1000.0;
+            bucket.tokens = (bucket.tokens + elapsed * tps).min(capacity);
+            bucket.updated = now;
+        }
+
+        if keys.iter().any(|(key, _)| self.buckets[key].tokens < 1.0) {
+            return false;
+        }
+
+        for (key, _) in &keys {
+            if let Some(bucket) = self.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,201 @@
+//! Recording traffic and replaying it.
+//!
+//! With recording on, every request the mock serves is appended to a JSONL
+//! file as an `Exchange`: the request line, headers and body, the response
+//! the mock sent back and how long it took. Credentials are redacted, and
+//! requests whose connection is dropped by fault injection are not recorded.
+//!
+//! A recording can later be replayed into another mock, either to rebuild
+//! the state an agent left behind or to check that the mock still answers
+//! the same way. Replay applies AWS actions directly, at the time they were
+//! originally received, so fault injection, throttling and the admin
+//! toggle are not involved.
+
+use crate::streams::lock;
+use crate::Response;
+use bytes::Bytes;
+use futures::{Future, Stream as _};
+use hyper::http::request::Parts;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Headers whose values are never written to a recording.
+const REDACTED: &[&str] = &["authorization", "x-amz-security-token"];
+
+/// One request and the response it got.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    pub status: u16,
+    pub response: String,
+    /// The mock's clock when the request arrived.
+    #[serde(rename = "receivedAt")]
+    pub received_at: i64,
+    /// Wall clock time spent producing the response.
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    /// Set for event stream responses, whose bodies are not recorded.
+    /// These are skipped on replay.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub streaming: bool,
+}
+
+impl Exchange {
+    pub(crate) fn new(parts: &Parts, body: &Bytes, received_at: i64) -> Self {
+        let headers = parts
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if REDACTED.contains(&name.as_str()) {
+                    "<redacted>".to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+
+        Exchange {
+            method: parts.method.to_string(),
+            path: parts.uri.path().to_string(),
+            target: parts
+                .headers
+                .get("X-Amz-Target")
+                .and_then(|t| t.to_str().ok())
+                .map(str::to_string),
+            headers,
+            body: String::from_utf8_lossy(&body[..]).into_owned(),
+            status: 0,
+            response: String::new(),
+            received_at,
+            duration_ms: 0,
+            streaming: false,
+        }
+    }
+
+    pub(crate) fn finish(&mut self, status: u16, response: &[u8], elapsed: Duration) {
+        self.status = status;
+        self.response = String::from_utf8_lossy(response).into_owned();
+        self.duration_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+    }
+
+    /// How the request is shown in replay reports.
+    fn describe(&self) -> String {
+        match &self.target {
+            Some(target) => target.clone(),
+            None => format!("{} {}", self.method, self.path),
+        }
+    }
+}
+
+/// Appends exchanges to a recording file.
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Record to `path`, appending if it already exists.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Recorder> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Recorder {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn write(&self, exchange: &Exchange) -> io::Result<()> {
+        let mut line = serde_json::to_vec(exchange)?;
+        line.push(b'\n');
+
+        // One write per line keeps concurrent requests from interleaving.
+        lock(&self.file).write_all(&line)
+    }
+}
+
+/// Read every exchange in the recording at `path`.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<Exchange>> {
+    let file = File::open(path)?;
+    let mut exchanges = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let exchange = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        exchanges.push(exchange);
+    }
+
+    Ok(exchanges)
+}
+
+/// A replayed request that got a different response than was recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The request's position in the recording, counting from one.
+    pub index: usize,
+    pub request: String,
+    pub expected_status: u16,
+    pub expected: String,
+    pub actual_status: u16,
+    pub actual: String,
+}
+
+impl Mismatch {
+    /// Compare `exchange` against what replaying it returned, ignoring
+    /// differences in JSON formatting and key order.
+    pub(crate) fn check(index: usize, exchange: &Exchange, res: Response) -> Option<Mismatch> {
+        let (parts, body) = res.into_parts();
+        // Responses are built in memory, so this never has to wait.
+        let body = body.concat2().wait().map(|b| b.into_bytes());
+        let actual = body
+            .map(|b| String::from_utf8_lossy(&b[..]).into_owned())
+            .unwrap_or_default();
+        let actual_status = parts.status.as_u16();
+
+        let same_body = match (
+            serde_json::from_str::<Value>(&exchange.response),
+            serde_json::from_str::<Value>(&actual),
+        ) {
+            (Ok(expected), Ok(actual)) => expected == actual,
+            _ => exchange.response == actual,
+        };
+
+        if same_body && exchange.status == actual_status {
+            return None;
+        }
+
+        Some(Mismatch {
+            index,
+            request: exchange.describe(),
+            expected_status: exchange.status,
+            expected: exchange.response.clone(),
+            actual_status,
+            actual,
+        })
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "#{} {}", self.index, self.request)?;
+        writeln!(f, "  recorded: {} {}", self.expected_status, self.expected)?;
+        write!(f, "  replayed: {} {}", self.actual_status, self.actual)
+    }
+}
@@ -1,27 +1,983 @@
-use crate::types::{InputLogEvent, LogStream};
-use std::collections::HashMap;
+use crate::account::Account;
+use crate::clock::Clock;
+use crate::limits::{self, Limits, Usage};
+use crate::live::Hub;
+use crate::mirror::Mirror;
+use crate::protection::DataProtectionPolicy;
+use crate::transform::GroupTransformer;
+use crate::types::{InputLogEvent, LogStream, MetricFilter, SubscriptionFilter};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::btree_map::{self, Range};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::ops::{Bound, Deref};
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-#[derive(Default, Clone)]
+/// Milliseconds in a day.
+pub const DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// How far the clock has to move between retention sweeps.
+const EXPIRY_INTERVAL: i64 = 1000;
+
+/// A group or stream that can be locked independently of its siblings.
+pub type Handle<T> = Arc<Mutex<T>>;
+
+/// Lock `mutex`, ignoring poisoning.
+///
+/// Every mutation of the mock's state leaves it consistent between
+/// statements, so a panic in one request is no reason to fail every
+/// request after it.
+pub fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(crate) fn read<T: ?Sized>(lock: &RwLock<T>) -> RwLockReadGuard<T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(crate) fn write<T: ?Sized>(lock: &RwLock<T>) -> RwLockWriteGuard<T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The mock's state, stored in some `Storage` backend.
+///
+/// `Context` derefs to its backend, so actions call storage operations on it
+/// directly. It serializes as a map of group name to `Group`.
 pub struct Context {
+    storage: Box<dyn Storage>,
+    clock: Clock,
+    pub(crate) limits: RwLock<Limits>,
+    expired_at: Mutex<Option<i64>>,
+    /// Open `StartLiveTail` sessions.
+    pub(crate) live: Hub,
+    /// Where accepted events are echoed, if anywhere.
+    pub(crate) mirror: RwLock<Option<Mirror>>,
+    /// Resources that belong to the account rather than to a group.
+    pub(crate) account: Mutex<Account>,
+    /// Set while the write-ahead log is replayed, when evicted events have
+    /// already been spilled once.
+    pub(crate) replaying: AtomicBool,
+}
+
+impl Context {
+    pub fn new(storage: Box<dyn Storage>) -> Self {
+        Context {
+            storage,
+            clock: Clock::default(),
+            limits: RwLock::default(),
+            expired_at: Mutex::default(),
+            live: Hub::default(),
+            mirror: RwLock::default(),
+            account: Mutex::default(),
+            replaying: AtomicBool::new(false),
+        }
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Drop events that have outlived their group's retention.
+    ///
+    /// This sweeps every group, so it does nothing unless the clock has
+    /// moved by at least `EXPIRY_INTERVAL` since the previous sweep.
+    pub fn expire(&self) {
+        let now = self.clock.now();
+
+        {
+            let mut expired_at = lock(&self.expired_at);
+
+            if let Some(last) = *expired_at {
+                if (now - last).abs() < EXPIRY_INTERVAL {
+                    return;
+                }
+            }
+
+            *expired_at = Some(now);
+        }
+
+        self.storage.drop_expired(now);
+    }
+
+    /// Remove every group and every account level resource.
+    pub fn clear(&self) {
+        self.storage.clear();
+        *lock(&self.account) = Account::default();
+    }
+
+    /// Replace everything in the backend with `snapshot`.
+    pub fn restore(&self, snapshot: Snapshot) {
+        self.clear();
+
+        for (name, mut group) in snapshot.groups {
+            group.name = name;
+            // Names are unique after clearing, so this cannot fail.
+            let _ = self.create_group(group, None);
+        }
+
+        *lock(&self.account) = snapshot.account;
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new(Box::new(InMemory::default()))
+    }
+}
+
+impl Deref for Context {
+    type Target = dyn Storage;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.storage
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("groups", &self.groups().len())
+            .finish()
+    }
+}
+
+/// An owned copy of every group and the account level resources, as
+/// produced by serializing a `Context`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    #[serde(default)]
     pub groups: HashMap<String, Group>,
+    #[serde(default)]
+    pub account: Account,
 }
 
-#[derive(Debug, Clone, Default)]
+impl Serialize for Context {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SnapshotRef<'a> {
+            groups: BTreeMap<String, Locked<'a, Group>>,
+            account: Locked<'a, Account>,
+        }
+
+        let handles = self.groups();
+        let groups = handles
+            .iter()
+            .map(|group| (lock(group).name.clone(), Locked(group)))
+            .collect();
+
+        SnapshotRef {
+            groups,
+            account: Locked(&self.account),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Context {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = Snapshot::deserialize(deserializer)?;
+
+        let context = Context::default();
+        context.restore(snapshot);
+        Ok(context)
+    }
+}
+
+/// Serializes the value behind a mutex, locking it for the duration.
+struct Locked<'a, T: 'a>(&'a Mutex<T>);
+
+impl<'a, T: Serialize> Serialize for Locked<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        lock(self.0).serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageError {
+    GroupNotFound,
+    StreamNotFound,
+    GroupAlreadyExists,
+    StreamAlreadyExists,
+    LimitExceeded(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            StorageError::GroupNotFound => "Group not found",
+            StorageError::StreamNotFound => "Stream not found",
+            StorageError::GroupAlreadyExists => "Group already exists",
+            StorageError::StreamAlreadyExists => "Stream already exists",
+            StorageError::LimitExceeded(message) => message.as_str(),
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+/// Where groups, streams and events are kept.
+///
+/// Backends are shared between every connection and synchronize
+/// internally. Groups and streams are handed out as `Handle`s so that
+/// writers to different streams never wait on each other.
+///
+/// Backends have to implement the group operations, and the enumeration,
+/// usage and eviction operations, which hand back owned data so that
+/// nothing outside the backend walks its groups. The stream and event
+/// operations have default implementations on top of the group operations
+/// that a backend can override when it can do better.
+pub trait Storage: Send + Sync {
+    /// Every group, ordered by name.
+    fn groups(&self) -> Vec<Handle<Group>>;
+
+    fn group(&self, name: &str) -> Option<Handle<Group>>;
+
+    /// Add `group`, unless that would make more than `max_groups`.
+    ///
+    /// The cap is checked under the same lock as the insert, so concurrent
+    /// creates cannot overshoot it.
+    fn create_group(&self, group: Group, max_groups: Option<usize>) -> Result<(), StorageError>;
+
+    fn delete_group(&self, name: &str) -> Result<Handle<Group>, StorageError>;
+
+    /// Remove every group.
+    fn clear(&self);
+
+    /// The names of the streams of `group`, in order.
+    fn stream_names(&self, group: &str) -> Result<Vec<String>, StorageError>;
+
+    /// How much is stored across every group.
+    fn usage(&self) -> Usage;
+
+    /// How much is stored in `group`.
+    fn group_usage(&self, group: &str) -> Result<Usage, StorageError>;
+
+    /// Remove the events that have outlived their group's retention at
+    /// `now`, returning how many were removed.
+    fn drop_expired(&self, now: i64) -> usize;
+
+    /// Remove the oldest event across every stream.
+    fn evict_oldest(&self) -> Option<Evicted>;
+
+    /// Remove the oldest events of a stream until at most `keep` are left,
+    /// returning them oldest first.
+    fn evict_stream(
+        &self,
+        group: &str,
+        stream: &str,
+        keep: usize,
+    ) -> Result<Vec<LogEvent>, StorageError>;
+
+    /// The streams of `group`, ordered by name.
+    fn streams(&self, group: &str) -> Result<Vec<Handle<Stream>>, StorageError> {
+        let group = self.group(group).ok_or(StorageError::GroupNotFound)?;
+        let streams = lock(&group).streams.values().cloned().collect();
+        Ok(streams)
+    }
+
+    fn stream(&self, group: &str, stream: &str) -> Result<Handle<Stream>, StorageError> {
+        let group = self.group(group).ok_or(StorageError::GroupNotFound)?;
+        let stream = lock(&group).streams.get(stream).cloned();
+        stream.ok_or(StorageError::StreamNotFound)
+    }
+
+    fn create_stream(&self, group: &str, stream: Stream) -> Result<(), StorageError> {
+        let group = self.group(group).ok_or(StorageError::GroupNotFound)?;
+        let mut group = lock(&group);
+
+        if group.streams.contains_key(&stream.name) {
+            return Err(StorageError::StreamAlreadyExists);
+        }
+
+        let name = stream.name.clone();
+        group.streams.insert(name, Arc::new(Mutex::new(stream)));
+        Ok(())
+    }
+
+    fn delete_stream(&self, group: &str, stream: &str) -> Result<Handle<Stream>, StorageError> {
+        let group = self.group(group).ok_or(StorageError::GroupNotFound)?;
+        let stream = lock(&group).streams.remove(stream);
+        stream.ok_or(StorageError::StreamNotFound)
+    }
+
+    /// Add `events` to a stream.
+    fn put_events(
+        &self,
+        group: &str,
+        stream: &str,
+        events: Vec<LogEvent>,
+    ) -> Result<(), StorageError> {
+        let stream = self.stream(group, stream)?;
+        lock(&stream).logs.extend(events);
+        Ok(())
+    }
+
+    /// Copies of the events of a stream with `start <= timestamp < end`, in
+    /// timestamp order.
+    fn events(
+        &self,
+        group: &str,
+        stream: &str,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<Vec<LogEvent>, StorageError> {
+        let stream = self.stream(group, stream)?;
+        let stream = lock(&stream);
+
+        Ok(stream
+            .logs
+            .range(start, end)
+            .map(|(_, e)| e.clone())
+            .collect())
+    }
+}
+
+/// The default backend, which keeps everything in memory.
+///
+/// Alongside the groups it keeps a `Ledger` of running totals and of each
+/// stream's oldest event, so that usage is a lookup and eviction a seek
+/// rather than a walk over every stream. Every change to a stream's events
+/// goes through `change_stream`, which updates the ledger while the stream
+/// is still locked.
+#[derive(Debug, Default)]
+pub struct InMemory {
+    groups: RwLock<HashMap<String, Handle<Group>>>,
+    ledger: Mutex<Ledger>,
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    /// Streams, events and bytes across every group. Groups are counted
+    /// from the map instead.
+    usage: Usage,
+    /// Every stream that holds events, by the key of its oldest one.
+    oldest: BTreeSet<(EventKey, String, String)>,
+}
+
+/// What a stream contributes to the `Ledger`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Tally {
+    events: usize,
+    bytes: usize,
+    first: Option<EventKey>,
+}
+
+impl Tally {
+    fn of(logs: &EventLog) -> Self {
+        Tally {
+            events: logs.len(),
+            bytes: logs.stored_bytes(),
+            first: logs.first_key(),
+        }
+    }
+}
+
+impl Ledger {
+    /// Account for the events of `group`/`stream` going from `before` to
+    /// `after`.
+    fn update(&mut self, group: &str, stream: &str, before: Tally, after: Tally) {
+        let usage = &mut self.usage;
+        usage.events = (usage.events + after.events).saturating_sub(before.events);
+        usage.stored_bytes = (usage.stored_bytes + after.bytes).saturating_sub(before.bytes);
+
+        if before.first != after.first {
+            if let Some(key) = before.first {
+                self.oldest
+                    .remove(&(key, group.to_string(), stream.to_string()));
+            }
+
+            if let Some(key) = after.first {
+                self.oldest
+                    .insert((key, group.to_string(), stream.to_string()));
+            }
+        }
+    }
+}
+
+impl InMemory {
+    /// Run `f` on a group while it is locked.
+    ///
+    /// The group is locked before the map is released, so a group that is
+    /// being deleted is either seen whole or not at all.
+    fn change_group<T, F>(&self, group: &str, f: F) -> Result<T, StorageError>
+    where
+        F: FnOnce(&mut Group) -> T,
+    {
+        let groups = read(&self.groups);
+        let group = groups.get(group).ok_or(StorageError::GroupNotFound)?;
+        let mut group = lock(group);
+
+        Ok(f(&mut group))
+    }
+
+    /// Run `f` on a stream while it is locked, and update the ledger with
+    /// whatever it did to the events.
+    ///
+    /// Like `change_group`, the stream is locked before its group is
+    /// released, so `delete_stream` and `delete_group` wait for `f` before
+    /// they take the stream out of the ledger.
+    fn change_stream<T, F>(&self, group: &str, stream: &str, f: F) -> Result<T, StorageError>
+    where
+        F: FnOnce(&mut Stream) -> T,
+    {
+        let groups = read(&self.groups);
+        let handle = groups.get(group).ok_or(StorageError::GroupNotFound)?;
+        let handle = lock(handle).streams.get(stream).cloned();
+        let handle = handle.ok_or(StorageError::StreamNotFound)?;
+        let mut stream = lock(&handle);
+        drop(groups);
+
+        let before = Tally::of(&stream.logs);
+        let result = f(&mut stream);
+        let after = Tally::of(&stream.logs);
+
+        lock(&self.ledger).update(group, &stream.name, before, after);
+        Ok(result)
+    }
+
+    /// Take a removed group's streams out of the ledger.
+    fn forget(&self, group: &Handle<Group>) {
+        let group = lock(group);
+
+        for stream in group.streams.values() {
+            let stream = lock(stream);
+            let mut ledger = lock(&self.ledger);
+
+            ledger.update(
+                &group.name,
+                &stream.name,
+                Tally::of(&stream.logs),
+                Tally::default(),
+            );
+            ledger.usage.streams -= 1;
+        }
+    }
+}
+
+impl Storage for InMemory {
+    fn groups(&self) -> Vec<Handle<Group>> {
+        let groups = read(&self.groups);
+
+        let mut names = groups.keys().collect::<Vec<_>>();
+        names.sort();
+        names.into_iter().map(|name| groups[name].clone()).collect()
+    }
+
+    fn group(&self, name: &str) -> Option<Handle<Group>> {
+        read(&self.groups).get(name).cloned()
+    }
+
+    fn create_group(&self, group: Group, max_groups: Option<usize>) -> Result<(), StorageError> {
+        let mut groups = write(&self.groups);
+
+        if groups.contains_key(&group.name) {
+            return Err(StorageError::GroupAlreadyExists);
+        }
+
+        if let Some(max) = max_groups {
+            if groups.len() >= max {
+                return Err(StorageError::LimitExceeded(format!(
+                    "Resource limit exceeded: at most {} log groups can be stored",
+                    max
+                )));
+            }
+        }
+
+        // Restored groups come with their streams and events.
+        for stream in group.streams.values() {
+            let stream = lock(stream);
+            let mut ledger = lock(&self.ledger);
+
+            ledger.update(
+                &group.name,
+                &stream.name,
+                Tally::default(),
+                Tally::of(&stream.logs),
+            );
+            ledger.usage.streams += 1;
+        }
+
+        groups.insert(group.name.clone(), Arc::new(Mutex::new(group)));
+        Ok(())
+    }
+
+    fn delete_group(&self, name: &str) -> Result<Handle<Group>, StorageError> {
+        let group = write(&self.groups)
+            .remove(name)
+            .ok_or(StorageError::GroupNotFound)?;
+
+        self.forget(&group);
+        Ok(group)
+    }
+
+    fn clear(&self) {
+        let groups = std::mem::replace(&mut *write(&self.groups), HashMap::new());
+
+        for group in groups.values() {
+            self.forget(group);
+        }
+    }
+
+    fn stream_names(&self, group: &str) -> Result<Vec<String>, StorageError> {
+        self.change_group(group, |group| group.streams.keys().cloned().collect())
+    }
+
+    fn usage(&self) -> Usage {
+        let groups = read(&self.groups).len();
+
+        Usage {
+            groups,
+            ..lock(&self.ledger).usage.clone()
+        }
+    }
+
+    fn group_usage(&self, group: &str) -> Result<Usage, StorageError> {
+        self.change_group(group, |group| Usage::of(group))
+    }
+
+    fn drop_expired(&self, now: i64) -> usize {
+        let mut expired = 0;
+
+        for group in self.groups() {
+            let (name, streams, days) = {
+                let group = lock(&group);
+                let streams = group.streams.keys().cloned().collect::<Vec<_>>();
+                (group.name.clone(), streams, group.retention_in_days)
+            };
+
+            let cutoff = match days {
+                Some(days) => now.saturating_sub(days.saturating_mul(DAY)),
+                None => continue,
+            };
+
+            for stream in &streams {
+                let removed =
+                    self.change_stream(&name, stream, |stream| stream.logs.expire(cutoff));
+                expired += removed.unwrap_or(0);
+            }
+        }
+
+        expired
+    }
+
+    fn evict_oldest(&self) -> Option<Evicted> {
+        loop {
+            let oldest = lock(&self.ledger).oldest.iter().next().cloned()?;
+            let (_, group, stream) = oldest.clone();
+
+            match self.change_stream(&group, &stream, |stream| stream.logs.pop_first()) {
+                Ok(Some(event)) => {
+                    return Some(Evicted {
+                        group,
+                        stream,
+                        event,
+                    })
+                }
+                // The ledger never points at a missing or empty stream, but
+                // if it did this would spin, so drop the entry and move on.
+                _ => {
+                    lock(&self.ledger).oldest.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    fn evict_stream(
+        &self,
+        group: &str,
+        stream: &str,
+        keep: usize,
+    ) -> Result<Vec<LogEvent>, StorageError> {
+        self.change_stream(group, stream, |stream| {
+            let mut evicted = Vec::new();
+            while stream.logs.len() > keep {
+                evicted.extend(stream.logs.pop_first());
+            }
+            evicted
+        })
+    }
+
+    fn create_stream(&self, group: &str, stream: Stream) -> Result<(), StorageError> {
+        self.change_group(group, |group| {
+            if group.streams.contains_key(&stream.name) {
+                return Err(StorageError::StreamAlreadyExists);
+            }
+
+            let mut ledger = lock(&self.ledger);
+            ledger.update(
+                &group.name,
+                &stream.name,
+                Tally::default(),
+                Tally::of(&stream.logs),
+            );
+            ledger.usage.streams += 1;
+            drop(ledger);
+
+            let name = stream.name.clone();
+            group.streams.insert(name, Arc::new(Mutex::new(stream)));
+            Ok(())
+        })?
+    }
+
+    fn delete_stream(&self, group: &str, stream: &str) -> Result<Handle<Stream>, StorageError> {
+        let (group, handle) = self.change_group(group, |group| {
+            let handle = group.streams.remove(stream);
+            (group.name.clone(), handle)
+        })?;
+        let handle = handle.ok_or(StorageError::StreamNotFound)?;
+
+        {
+            let stream = lock(&handle);
+            let mut ledger = lock(&self.ledger);
+
+            ledger.update(
+                &group,
+                &stream.name,
+                Tally::of(&stream.logs),
+                Tally::default(),
+            );
+            ledger.usage.streams -= 1;
+        }
+
+        Ok(handle)
+    }
+
+    fn put_events(
+        &self,
+        group: &str,
+        stream: &str,
+        events: Vec<LogEvent>,
+    ) -> Result<(), StorageError> {
+        self.change_stream(group, stream, |stream| stream.logs.extend(events))
+    }
+}
+
+/// An event removed to make room, and where it was stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evicted {
+    pub group: String,
+    pub stream: String,
+    pub event: LogEvent,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Group {
     pub name: String,
-    pub streams: Vec<Stream>,
+    /// When the group was created, by the mock's clock.
+    #[serde(default)]
+    pub creation_time: Option<i64>,
+    #[serde(with = "stream_list")]
+    pub streams: BTreeMap<String, Handle<Stream>>,
+    #[serde(default)]
+    pub retention_in_days: Option<i64>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    #[serde(default)]
+    pub log_group_class: LogGroupClass,
+    /// Metric filters by name.
+    #[serde(default)]
+    pub metric_filters: BTreeMap<String, MetricFilter>,
+    /// Subscription filters by name.
+    #[serde(default)]
+    pub subscription_filters: BTreeMap<String, SubscriptionFilter>,
+    #[serde(default)]
+    pub data_protection_policy: Option<DataProtectionPolicy>,
+    #[serde(default)]
+    pub transformer: Option<GroupTransformer>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A group's storage class. `InfrequentAccess` groups are cheaper in the
+/// service but cannot have metric filters, subscription filters or Live
+/// Tail sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LogGroupClass {
+    Standard,
+    InfrequentAccess,
+}
+
+impl Default for LogGroupClass {
+    fn default() -> Self {
+        LogGroupClass::Standard
+    }
+}
+
+impl LogGroupClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogGroupClass::Standard => "STANDARD",
+            LogGroupClass::InfrequentAccess => "INFREQUENT_ACCESS",
+        }
+    }
+}
+
+impl FromStr for LogGroupClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "STANDARD" => Ok(LogGroupClass::Standard),
+            "INFREQUENT_ACCESS" => Ok(LogGroupClass::InfrequentAccess),
+            _ => Err(format!(
+                "logGroupClass must be STANDARD or INFREQUENT_ACCESS, not {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// Streams are stored by name but (de)serialized as a list.
+mod stream_list {
+    use super::{Handle, Locked, Stream};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    pub fn serialize<S: Serializer>(
+        streams: &BTreeMap<String, Handle<Stream>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(streams.values().map(|stream| Locked(stream)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, Handle<Stream>>, D::Error> {
+        let streams = Vec::<Stream>::deserialize(deserializer)?;
+
+        Ok(streams
+            .into_iter()
+            .map(|stream| (stream.name.clone(), Arc::new(Mutex::new(stream))))
+            .collect())
+    }
+}
+
+/// An event as stored, along with when the mock received it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEvent {
+    pub timestamp: i64,
+    pub message: String,
+    #[serde(rename = "ingestionTime", default)]
+    pub ingestion_time: i64,
+}
+
+impl LogEvent {
+    pub fn new(event: InputLogEvent, ingestion_time: i64) -> Self {
+        LogEvent {
+            timestamp: event.timestamp,
+            message: event.message,
+            ingestion_time,
+        }
+    }
+}
+
+impl From<LogEvent> for InputLogEvent {
+    fn from(event: LogEvent) -> Self {
+        InputLogEvent {
+            message: event.message,
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Stream {
     pub name: String,
-    pub logs: Vec<InputLogEvent>,
+    pub logs: EventLog,
+}
+
+/// The position of an event within its stream.
+///
+/// Events are ordered by timestamp and then by arrival, so keys stay valid
+/// as cursors no matter where later events are inserted. Sequence numbers
+/// start at one, which leaves `seq: 0` free to mean "before every event at
+/// this timestamp".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventKey {
+    pub timestamp: i64,
+    pub seq: u64,
+}
+
+impl EventKey {
+    /// A key that sorts before every event at `timestamp`.
+    pub fn at(timestamp: i64) -> Self {
+        EventKey { timestamp, seq: 0 }
+    }
+}
+
+/// A stream's events, indexed by timestamp.
+///
+/// This serializes as a plain list of events; sequence numbers are
+/// reassigned in that order when it is read back.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    events: BTreeMap<EventKey, LogEvent>,
+    last_seq: u64,
+    bytes: usize,
+    last_ingestion_time: Option<i64>,
+}
+
+impl EventLog {
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The size of every event, as counted against `Limits::max_bytes`.
+    pub fn stored_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn insert(&mut self, event: LogEvent) -> EventKey {
+        self.last_seq += 1;
+
+        let key = EventKey {
+            timestamp: event.timestamp,
+            seq: self.last_seq,
+        };
+
+        self.bytes += limits::event_size(&event.message);
+        self.last_ingestion_time = self.last_ingestion_time.max(Some(event.ingestion_time));
+        self.events.insert(key, event);
+        key
+    }
+
+    /// The key of the oldest event.
+    pub fn first_key(&self) -> Option<EventKey> {
+        self.events.keys().next().cloned()
+    }
+
+    /// Remove and return the oldest event.
+    pub fn pop_first(&mut self) -> Option<LogEvent> {
+        let key = *self.events.keys().next()?;
+        let event = self.events.remove(&key)?;
+
+        self.bytes -= limits::event_size(&event.message);
+        Some(event)
+    }
+
+    /// Remove every event with `timestamp < cutoff`, returning how many
+    /// were removed.
+    pub fn expire(&mut self, cutoff: i64) -> usize {
+        let kept = self.events.split_off(&EventKey::at(cutoff));
+        let expired = std::mem::replace(&mut self.events, kept);
+
+        for event in expired.values() {
+            self.bytes -= limits::event_size(&event.message);
+        }
+
+        expired.len()
+    }
+
+    /// When the most recent event was received.
+    pub fn last_ingestion_time(&self) -> Option<i64> {
+        self.last_ingestion_time
+    }
+
+    pub fn extend<I: IntoIterator<Item = LogEvent>>(&mut self, events: I) {
+        for event in events {
+            self.insert(event);
+        }
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<EventKey, LogEvent> {
+        self.events.iter()
+    }
+
+    pub fn get(&self, key: &EventKey) -> Option<&LogEvent> {
+        self.events.get(key)
+    }
+
+    pub fn first(&self) -> Option<&LogEvent> {
+        self.events.values().next()
+    }
+
+    pub fn last(&self) -> Option<&LogEvent> {
+        self.events.values().next_back()
+    }
+
+    /// Events with `start <= timestamp < end`.
+    pub fn range(&self, start: Option<i64>, end: Option<i64>) -> Range<EventKey, LogEvent> {
+        let lower = start.map(|t| Bound::Included(EventKey::at(t)));
+        let upper = end.map(|t| Bound::Excluded(EventKey::at(t)));
+
+        self.bounded(
+            lower.unwrap_or(Bound::Unbounded),
+            upper.unwrap_or(Bound::Unbounded),
+        )
+    }
+
+    /// Events after `key`, with `timestamp < end`.
+    pub fn after(&self, key: EventKey, end: Option<i64>) -> Range<EventKey, LogEvent> {
+        let upper = end.map(|t| Bound::Excluded(EventKey::at(t)));
+        self.bounded(Bound::Excluded(key), upper.unwrap_or(Bound::Unbounded))
+    }
+
+    /// Events before `key`, with `start <= timestamp`.
+    pub fn before(&self, key: EventKey, start: Option<i64>) -> Range<EventKey, LogEvent> {
+        let lower = start.map(|t| Bound::Included(EventKey::at(t)));
+        self.bounded(lower.unwrap_or(Bound::Unbounded), Bound::Excluded(key))
+    }
+
+    /// Events between `lower` and `upper`.
+    ///
+    /// `BTreeMap::range` panics on inverted bounds, so those are turned into
+    /// an empty range instead.
+    pub fn bounded(
+        &self,
+        lower: Bound<EventKey>,
+        upper: Bound<EventKey>,
+    ) -> Range<EventKey, LogEvent> {
+        let empty = match (&lower, &upper) {
+            (Bound::Included(l), Bound::Included(u)) => l > u,
+            (Bound::Included(l), Bound::Excluded(u))
+            | (Bound::Excluded(l), Bound::Included(u))
+            | (Bound::Excluded(l), Bound::Excluded(u)) => l >= u,
+            _ => false,
+        };
+
+        if empty {
+            let key = EventKey::at(0);
+            return self
+                .events
+                .range((Bound::Included(key), Bound::Excluded(key)));
+        }
+
+        self.events.range((lower, upper))
+    }
+}
+
+impl Serialize for EventLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.events.values())
+    }
+}
+
+impl<'de> Deserialize<'de> for EventLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let events = Vec::<LogEvent>::deserialize(deserializer)?;
+
+        let mut log = EventLog::default();
+        log.extend(events);
+        Ok(log)
+    }
 }
 
-impl From<Stream> for LogStream {
-    fn from(stream: Stream) -> Self {
+impl<'a> From<&'a Stream> for LogStream {
+    fn from(stream: &'a Stream) -> Self {
         LogStream {
-            log_stream_name: Some(stream.name),
+            log_stream_name: Some(stream.name.clone()),
+            first_event_timestamp: stream.logs.first().map(|e| e.timestamp),
+            last_event_timestamp: stream.logs.last().map(|e| e.timestamp),
+            last_ingestion_time: stream.logs.last_ingestion_time(),
+            stored_bytes: Some(stream.logs.stored_bytes() as i64),
             ..Default::default()
         }
     }
@@ -0,0 +1,295 @@
+//! Assertions over the logs a mock has captured.
+//!
+//! ```ignore
+//! let server = MockServer::new();
+//! // ... serve it and point the code under test at it ...
+//!
+//! let web = server.group("app").stream("web-1");
+//! web.wait_for(3, Duration::from_secs(5)).unwrap();
+//! assert_logged(&server.group("app"), |msg| msg.contains("started"));
+//! ```
+
+use crate::streams::Context;
+use crate::types::InputLogEvent;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Something that captured log events can be read from.
+pub trait Events {
+    /// A snapshot of the events, ordered by timestamp.
+    fn events(&self) -> Vec<InputLogEvent>;
+
+    /// A human readable name used in assertion failures.
+    fn describe(&self) -> String;
+
+    /// The messages of `events`.
+    fn messages(&self) -> Vec<String> {
+        self.events().into_iter().map(|e| e.message).collect()
+    }
+
+    /// Block until at least `count` events have been captured.
+    fn wait_for(&self, count: usize, timeout: Duration) -> Result<Vec<InputLogEvent>, WaitTimeout> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let events = self.events();
+
+            if events.len() >= count {
+                return Ok(events);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(WaitTimeout {
+                    source: self.describe(),
+                    expected: count,
+                    found: events.len(),
+                    messages: events.into_iter().map(|e| e.message).collect(),
+                });
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Returned when `Events::wait_for` or `wait_for_logged` gives up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitTimeout {
+    pub source: String,
+    pub expected: usize,
+    /// How many events there were when the wait gave up.
+    pub found: usize,
+    /// The messages of those events.
+    pub messages: Vec<String>,
+}
+
+impl fmt::Display for WaitTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `wait_for_logged` can give up with plenty of events, none of
+        // which matched.
+        if self.found >= self.expected {
+            write!(
+                f,
+                "timed out waiting for a matching message in {}, none of {} matched",
+                self.source, self.found
+            )?;
+        } else {
+            write!(
+                f,
+                "timed out waiting for {} events in {}, found {}",
+                self.expected, self.source, self.found
+            )?;
+        }
+
+        if !self.messages.is_empty() {
+            write!(f, ": {:?}", self.messages)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for WaitTimeout {}
+
+/// A log group, which may not have been created yet.
+#[derive(Clone)]
+pub struct GroupRef {
+    context: Arc<Context>,
+    name: String,
+}
+
+impl GroupRef {
+    pub(crate) fn new(context: Arc<Context>, name: &str) -> Self {
+        GroupRef {
+            context,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn exists(&self) -> bool {
+        self.context.group(&self.name).is_some()
+    }
+
+    /// The names of the group's streams, ordered by name.
+    pub fn streams(&self) -> Vec<String> {
+        self.context.stream_names(&self.name).unwrap_or_default()
+    }
+
+    pub fn stream(&self, name: &str) -> StreamRef {
+        StreamRef {
+            context: self.context.clone(),
+            group: self.name.clone(),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Events for GroupRef {
+    fn events(&self) -> Vec<InputLogEvent> {
+        let streams = self.context.stream_names(&self.name).unwrap_or_default();
+
+        let mut events = streams
+            .iter()
+            .flat_map(|stream| {
+                self.context
+                    .events(&self.name, stream, None, None)
+                    .unwrap_or_default()
+            })
+            .map(InputLogEvent::from)
+            .collect::<Vec<_>>();
+
+        events.sort_by_key(|e| e.timestamp);
+        events
+    }
+
+    fn describe(&self) -> String {
+        format!("group `{}`", self.name)
+    }
+}
+
+/// A log stream, which may not have been created yet.
+#[derive(Clone)]
+pub struct StreamRef {
+    context: Arc<Context>,
+    group: String,
+    name: String,
+}
+
+impl StreamRef {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn exists(&self) -> bool {
+        self.context.stream(&self.group, &self.name).is_ok()
+    }
+}
+
+impl Events for StreamRef {
+    fn events(&self) -> Vec<InputLogEvent> {
+        self.context
+            .events(&self.group, &self.name, None, None)
+            .unwrap_or_default()
+            .into_iter()
+            .map(InputLogEvent::from)
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        format!("stream `{}` in group `{}`", self.name, self.group)
+    }
+}
+
+/// Panic unless some captured message matches `predicate`.
+pub fn assert_logged<E, F>(source: &E, predicate: F)
+where
+    E: Events,
+    F: Fn(&str) -> bool,
+{
+    let messages = source.messages();
+
+    if !messages.iter().any(|m| predicate(m)) {
+        panic!(
+            "no matching message logged to {}, captured:\n{}",
+            source.describe(),
+            format_messages(&messages)
+        );
+    }
+}
+
+/// Panic if any captured message matches `predicate`.
+pub fn assert_not_logged<E, F>(source: &E, predicate: F)
+where
+    E: Events,
+    F: Fn(&str) -> bool,
+{
+    let messages = source.messages();
+
+    if let Some(message) = messages.iter().find(|m| predicate(m)) {
+        panic!(
+            "unexpected message logged to {}: {:?}",
+            source.describe(),
+            message
+        );
+    }
+}
+
+/// Block until a captured message matches `predicate`.
+pub fn wait_for_logged<E, F>(
+    source: &E,
+    predicate: F,
+    timeout: Duration,
+) -> Result<String, WaitTimeout>
+where
+    E: Events,
+    F: Fn(&str) -> bool,
+{
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let messages = source.messages();
+
+        if let Some(message) = messages.iter().find(|m| predicate(m)) {
+            return Ok(message.clone());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(WaitTimeout {
+                source: source.describe(),
+                expected: 1,
+                found: messages.len(),
+                messages,
+            });
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The captured messages, one per line, for snapshot comparisons.
+pub fn snapshot<E: Events>(source: &E) -> String {
+    source.messages().join("\n")
+}
+
+/// Compare the captured messages against `expected`, one message per line.
+///
+/// Surrounding whitespace on each line is ignored, so the expectation can be
+/// written as an indented raw string.
+pub fn assert_snapshot<E: Events>(source: &E, expected: &str) {
+    let expected = expected
+        .trim()
+        .lines()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>();
+    let actual = source.messages();
+    let actual = actual.iter().map(|m| m.trim()).collect::<Vec<_>>();
+
+    if expected != actual {
+        panic!(
+            "snapshot mismatch for {}\nexpected:\n{}\nactual:\n{}",
+            source.describe(),
+            format_messages(&expected),
+            format_messages(&actual)
+        );
+    }
+}
+
+fn format_messages<S: AsRef<str>>(messages: &[S]) -> String {
+    if messages.is_empty() {
+        return "    <none>".to_string();
+    }
+
+    messages
+        .iter()
+        .map(|m| format!("    {}", m.as_ref()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,565 @@
+//! Log transformers, which rewrite a group's events as they are ingested.
+//!
+//! A transformer is a list of processors, the first of which must be a
+//! parser. Processors work on a JSON object built up from the event's
+//! message, which is stored in place of the message once all have run.
+//! Keys may name nested fields with dots, like `http.status`, and the
+//! source `@message` is the original message. If a parser cannot handle an
+//! event, the event is stored untouched.
+
+use crate::account::{group_name, json};
+use crate::actions::{supports, ServiceError};
+use crate::streams::{lock, Context};
+use crate::types::*;
+use crate::{Body, Response};
+use regex::Regex;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// How many processors a transformer may have.
+const MAX_PROCESSORS: usize = 20;
+
+/// How many messages `TestTransformer` takes.
+const MAX_TEST_MESSAGES: usize = 100;
+
+const MESSAGE: &str = "@message";
+
+/// The grok patterns the mock knows, by name.
+const GROK_PATTERNS: &[(&str, &str)] = &[
+    ("WORD", r"\b\w+\b"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    ("INT", r"[+-]?\d+"),
+    ("POSINT", r"\b[1-9]\d*\b"),
+    ("NUMBER", r"[+-]?(?:\d+(?:\.\d*)?|\.\d+)"),
+    ("BASE10NUM", r"[+-]?(?:\d+(?:\.\d*)?|\.\d+)"),
+    ("IPV4", r"(?:\d{1,3}\.){3}\d{1,3}"),
+    ("IP", r"(?:\d{1,3}\.){3}\d{1,3}"),
+    (
+        "HOSTNAME",
+        r"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?\b",
+    ),
+    ("USERNAME", r"[a-zA-Z0-9._-]+"),
+    ("USER", r"[a-zA-Z0-9._-]+"),
+    (
+        "EMAILADDRESS",
+        r"[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)+",
+    ),
+    (
+        "LOGLEVEL",
+        r"(?i:trace|debug|info|notice|warn(?:ing)?|err(?:or)?|crit(?:ical)?|fatal|severe|emerg(?:ency)?)",
+    ),
+    (
+        "TIMESTAMP_ISO8601",
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?",
+    ),
+    ("HTTPDATE", r"\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4}"),
+    ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*""#),
+    (
+        "UUID",
+        r"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}",
+    ),
+    ("URIPATH", r"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_\-]*)+"),
+];
+
+/// A group's transformer, as configured and compiled when it is put or
+/// loaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupTransformer {
+    pub config: Vec<Value>,
+    pub creation_time: i64,
+    pub last_modified_time: i64,
+    #[serde(skip)]
+    pub(crate) compiled: Arc<Transformer>,
+}
+
+impl<'de> Deserialize<'de> for GroupTransformer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Stored {
+            config: Vec<Value>,
+            creation_time: i64,
+            last_modified_time: i64,
+        }
+
+        let stored = Stored::deserialize(deserializer)?;
+        let compiled =
+            Transformer::parse(&stored.config).map_err(<D::Error as de::Error>::custom)?;
+
+        Ok(GroupTransformer {
+            config: stored.config,
+            creation_time: stored.creation_time,
+            last_modified_time: stored.last_modified_time,
+            compiled: Arc::new(compiled),
+        })
+    }
+}
+
+/// What a grok field's text is turned into.
+#[derive(Debug)]
+enum Conversion {
+    String,
+    Int,
+    Float,
+}
+
+/// Key and value pairs of the `entries` of `addKeys`, `renameKeys` and
+/// `copyValue`, and whether to overwrite existing keys.
+#[derive(Debug)]
+struct Entry {
+    from: String,
+    to: String,
+    overwrite: bool,
+}
+
+#[derive(Debug)]
+enum Processor {
+    ParseJson {
+        source: String,
+        destination: String,
+    },
+    Grok {
+        source: String,
+        pattern: Regex,
+        fields: Vec<(String, Conversion)>,
+    },
+    AddKeys(Vec<Entry>),
+    DeleteKeys(Vec<String>),
+    RenameKeys(Vec<Entry>),
+    LowerCaseString(Vec<String>),
+    CopyValue(Vec<Entry>),
+}
+
+fn string<'a>(config: &'a Value, processor: &str, key: &str) -> Result<&'a str, String> {
+    config
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{} needs a string {}", processor, key))
+}
+
+fn source(config: &Value) -> String {
+    config
+        .get("source")
+        .and_then(Value::as_str)
+        .unwrap_or(MESSAGE)
+        .to_string()
+}
+
+fn with_keys(config: &Value, processor: &str) -> Result<Vec<String>, String> {
+    config
+        .get("withKeys")
+        .and_then(Value::as_array)
+        .filter(|keys| !keys.is_empty())
+        .and_then(|keys| {
+            keys.iter()
+                .map(|key| key.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| format!("{} needs a list of withKeys", processor))
+}
+
+fn entries(config: &Value, processor: &str, from: &str, to: &str) -> Result<Vec<Entry>, String> {
+    let entries = config
+        .get("entries")
+        .and_then(Value::as_array)
+        .filter(|entries| !entries.is_empty())
+        .ok_or_else(|| format!("{} needs a list of entries", processor))?;
+
+    entries
+        .iter()
+        .map(|entry| -> Result<Entry, String> {
+            Ok(Entry {
+                from: string(entry, processor, from)?.to_string(),
+                to: string(entry, processor, to)?.to_string(),
+                overwrite: entry
+                    .get("overwriteIfExists")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Turn a grok pattern into a regex, with a group for each named field.
+fn grok(pattern: &str) -> Result<(Regex, Vec<(String, Conversion)>), String> {
+    let mut regex = String::new();
+    let mut fields = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find("%{") {
+        regex.push_str(&rest[..start]);
+
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unterminated grok pattern: {}", pattern))?;
+        let mut parts = rest[start + 2..end].split(':');
+
+        let name = parts.next().unwrap_or_default();
+        let body = GROK_PATTERNS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, body)| *body)
+            .ok_or_else(|| format!("Unsupported grok pattern: {}", name))?;
+
+        match parts.next() {
+            Some(field) => {
+                let conversion = match parts.next() {
+                    None => Conversion::String,
+                    Some("int") => Conversion::Int,
+                    Some("float") => Conversion::Float,
+                    Some(other) => return Err(format!("Unsupported grok conversion: {}", other)),
+                };
+                regex.push_str(&format!("(?P<g{}>{})", fields.len(), body));
+                fields.push((field.to_string(), conversion));
+            }
+            None => regex.push_str(&format!("(?:{})", body)),
+        }
+
+        rest = &rest[end + 1..];
+    }
+    regex.push_str(rest);
+
+    let regex = Regex::new(&regex).map_err(|_| format!("Invalid grok pattern: {}", pattern))?;
+    Ok((regex, fields))
+}
+
+impl Processor {
+    fn parse(config: &Value) -> Result<Processor, String> {
+        let (name, config) = match config.as_object() {
+            Some(object) if object.len() == 1 => object.iter().next().unwrap(),
+            _ => return Err("Every processor must be an object with one key".into()),
+        };
+
+        let processor = match name.as_str() {
+            "parseJSON" => Processor::ParseJson {
+                source: source(config),
+                destination: config
+                    .get("destination")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+            },
+            "grok" => {
+                let (pattern, fields) = grok(string(config, name, "match")?)?;
+                Processor::Grok {
+                    source: source(config),
+                    pattern,
+                    fields,
+                }
+            }
+            "addKeys" => Processor::AddKeys(entries(config, name, "key", "value")?),
+            "deleteKeys" => Processor::DeleteKeys(with_keys(config, name)?),
+            "renameKeys" => Processor::RenameKeys(entries(config, name, "key", "renameTo")?),
+            "lowerCaseString" => Processor::LowerCaseString(with_keys(config, name)?),
+            "copyValue" => Processor::CopyValue(entries(config, name, "source", "target")?),
+            _ => return Err(format!("Unsupported processor: {}", name)),
+        };
+
+        Ok(processor)
+    }
+
+    fn is_parser(&self) -> bool {
+        match self {
+            Processor::ParseJson { .. } | Processor::Grok { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Apply the processor to `record`, or fail if it is a parser that
+    /// cannot parse it.
+    fn apply(&self, record: &mut Record) -> Option<()> {
+        match self {
+            Processor::ParseJson {
+                source,
+                destination,
+            } => {
+                let text = record.get(source)?;
+                let parsed = match serde_json::from_str::<Value>(text.as_str()?) {
+                    Ok(Value::Object(parsed)) => parsed,
+                    _ => return None,
+                };
+
+                if destination.is_empty() {
+                    record.fields.extend(parsed);
+                } else {
+                    record.insert(destination, Value::Object(parsed));
+                }
+            }
+            Processor::Grok {
+                source,
+                pattern,
+                fields,
+            } => {
+                let text = record.get(source)?;
+                let captures = pattern.captures(text.as_str()?)?;
+
+                let values = fields
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, (field, conversion))| {
+                        let text = captures.name(&format!("g{}", i))?.as_str();
+                        let value = match conversion {
+                            Conversion::String => None,
+                            Conversion::Int => text.parse::<i64>().ok().map(Value::from),
+                            Conversion::Float => text.parse::<f64>().ok().map(Value::from),
+                        };
+                        Some((field, value.unwrap_or_else(|| Value::from(text))))
+                    })
+                    .collect::<Vec<_>>();
+
+                for (field, value) in values {
+                    record.insert(field, value);
+                }
+            }
+            Processor::AddKeys(entries) => {
+                for entry in entries {
+                    if entry.overwrite || record.get(&entry.from).is_none() {
+                        record.insert(&entry.from, Value::from(entry.to.as_str()));
+                    }
+                }
+            }
+            Processor::DeleteKeys(keys) => {
+                for key in keys {
+                    record.remove(key);
+                }
+            }
+            Processor::RenameKeys(entries) => {
+                for entry in entries {
+                    if !entry.overwrite && record.get(&entry.to).is_some() {
+                        continue;
+                    }
+                    if let Some(value) = record.remove(&entry.from) {
+                        record.insert(&entry.to, value);
+                    }
+                }
+            }
+            Processor::LowerCaseString(keys) => {
+                for key in keys {
+                    let lowered = match record.get(key) {
+                        Some(Value::String(s)) => s.to_lowercase(),
+                        _ => continue,
+                    };
+                    record.insert(key, Value::from(lowered));
+                }
+            }
+            Processor::CopyValue(entries) => {
+                for entry in entries {
+                    if !entry.overwrite && record.get(&entry.to).is_some() {
+                        continue;
+                    }
+                    if let Some(value) = record.get(&entry.from) {
+                        record.insert(&entry.to, value);
+                    }
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// An event being transformed.
+struct Record<'a> {
+    message: &'a str,
+    fields: Map<String, Value>,
+}
+
+impl<'a> Record<'a> {
+    fn get(&self, path: &str) -> Option<Value> {
+        if path == MESSAGE {
+            return Some(Value::from(self.message));
+        }
+
+        let mut parts = path.split('.');
+        let mut value = self.fields.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_object()?.get(part)?;
+        }
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, path: &str, value: Value) {
+        let mut parts = path.split('.').collect::<Vec<_>>();
+        let last = parts.pop().unwrap_or_default();
+
+        let mut object = &mut self.fields;
+        for part in parts {
+            let entry = object
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            object = entry.as_object_mut().unwrap();
+        }
+        object.insert(last.to_string(), value);
+    }
+
+    fn remove(&mut self, path: &str) -> Option<Value> {
+        let (parents, last) = match path.rfind('.') {
+            Some(i) => (Some(&path[..i]), &path[i + 1..]),
+            None => (None, path),
+        };
+
+        let mut object = &mut self.fields;
+        for part in parents.into_iter().flat_map(|p| p.split('.')) {
+            object = object.get_mut(part)?.as_object_mut()?;
+        }
+        object.remove(last)
+    }
+}
+
+/// A compiled transformer.
+#[derive(Debug)]
+pub(crate) struct Transformer {
+    processors: Vec<Processor>,
+}
+
+impl Transformer {
+    /// Compile the processors of `config`, or describe why they are
+    /// invalid.
+    pub fn parse(config: &[Value]) -> Result<Transformer, String> {
+        if config.is_empty() || config.len() > MAX_PROCESSORS {
+            return Err(format!(
+                "A transformer must have between 1 and {} processors",
+                MAX_PROCESSORS
+            ));
+        }
+
+        let processors = config
+            .iter()
+            .map(Processor::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !processors[0].is_parser() {
+            return Err("The first processor must be parseJSON or grok".into());
+        }
+
+        Ok(Transformer { processors })
+    }
+
+    /// The transformed `message`, or `None` if a parser could not handle
+    /// it.
+    pub fn transform(&self, message: &str) -> Option<String> {
+        let mut record = Record {
+            message,
+            fields: Map::new(),
+        };
+
+        for processor in &self.processors {
+            processor.apply(&mut record)?;
+        }
+
+        Some(Value::Object(record.fields).to_string())
+    }
+}
+
+/// The transformer of the group `name`, if it has one.
+pub(crate) fn transformer(context: &Context, name: &str) -> Option<Arc<Transformer>> {
+    let group = context.group(name)?;
+    let group = lock(&group);
+    group
+        .transformer
+        .as_ref()
+        .map(|transformer| transformer.compiled.clone())
+}
+
+pub fn put_transformer(
+    context: &Context,
+    request: PutTransformerRequest,
+) -> Result<Response, ServiceError> {
+    let compiled =
+        Transformer::parse(&request.transformer_config).map_err(ServiceError::InvalidParameter)?;
+
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let mut group = lock(&group);
+    supports(&group, "Transformers")?;
+
+    let now = context.clock().now();
+    let creation_time = group
+        .transformer
+        .as_ref()
+        .map_or(now, |transformer| transformer.creation_time);
+
+    group.transformer = Some(GroupTransformer {
+        config: request.transformer_config,
+        creation_time,
+        last_modified_time: now,
+        compiled: Arc::new(compiled),
+    });
+
+    Ok(Response::new(Body::empty()))
+}
+
+pub fn get_transformer(
+    context: &Context,
+    request: GetTransformerRequest,
+) -> Result<Response, ServiceError> {
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let group = lock(&group);
+    let transformer = group.transformer.as_ref();
+
+    Ok(json(&GetTransformerResponse {
+        creation_time: transformer.map(|t| t.creation_time),
+        last_modified_time: transformer.map(|t| t.last_modified_time),
+        log_group_identifier: Some(request.log_group_identifier),
+        transformer_config: transformer.map(|t| t.config.clone()),
+    }))
+}
+
+pub fn delete_transformer(
+    context: &Context,
+    request: DeleteTransformerRequest,
+) -> Result<Response, ServiceError> {
+    let group = context
+        .group(group_name(&request.log_group_identifier))
+        .ok_or_else(group_not_found)?;
+    let mut group = lock(&group);
+
+    match group.transformer.take() {
+        Some(_) => Ok(Response::new(Body::empty())),
+        None => Err(ServiceError::NotFound(format!(
+            "The log group {} has no transformer",
+            request.log_group_identifier
+        ))),
+    }
+}
+
+pub fn test_transformer(
+    _context: &Context,
+    request: TestTransformerRequest,
+) -> Result<Response, ServiceError> {
+    let messages = &request.log_event_messages;
+    if messages.is_empty() || messages.len() > MAX_TEST_MESSAGES {
+        return Err(ServiceError::InvalidParameter(format!(
+            "logEventMessages must hold between 1 and {} messages",
+            MAX_TEST_MESSAGES
+        )));
+    }
+
+    let transformer =
+        Transformer::parse(&request.transformer_config).map_err(ServiceError::InvalidParameter)?;
+
+    let transformed = messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| TransformedLogRecord {
+            event_message: Some(message.clone()),
+            event_number: Some(i as i64 + 1),
+            transformed_event_message: transformer.transform(message),
+        })
+        .collect();
+
+    Ok(json(&TestTransformerResponse {
+        transformed_logs: Some(transformed),
+    }))
+}
@@ -1,5 +1,6 @@
 // Types taken from rusoto_logs
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InputLogEvent {
@@ -49,6 +50,10 @@ pub struct DescribeLogGroupsRequest {
     #[serde(rename = "limit")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<i64>,
+    /// <p>Specifies the log group class for this log group.</p>
+    #[serde(rename = "logGroupClass")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_class: Option<String>,
     /// <p>The prefix to match.</p>
     #[serde(rename = "logGroupNamePrefix")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -73,6 +78,14 @@ pub struct LogGroup {
     #[serde(rename = "kmsKeyId")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kms_key_id: Option<String>,
+    /// <p>Displays whether this log group has a protection policy, or whether it had one in the past.</p>
+    #[serde(rename = "dataProtectionStatus")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_protection_status: Option<String>,
+    /// <p>This specifies the log group class for this log group, either <code>STANDARD</code> or <code>INFREQUENT_ACCESS</code>.</p>
+    #[serde(rename = "logGroupClass")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_class: Option<String>,
     /// <p>The name of the log group.</p>
     #[serde(rename = "logGroupName")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -144,6 +157,10 @@ pub struct CreateLogGroupRequest {
     #[serde(rename = "kmsKeyId")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kms_key_id: Option<String>,
+    /// <p>Use this parameter to specify the log group class for this log group, <code>STANDARD</code> (the default) or <code>INFREQUENT_ACCESS</code>.</p>
+    #[serde(rename = "logGroupClass")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_class: Option<String>,
     /// <p>The name of the log group.</p>
     #[serde(rename = "logGroupName")]
     pub log_group_name: String,
@@ -237,6 +254,10 @@ pub struct GetLogEventsRequest {
     #[serde(rename = "startTime")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_time: Option<i64>,
+    /// <p>Specify <code>true</code> to display the log event fields with all sensitive data unmasked and visible. The default is <code>false</code>.</p>
+    #[serde(rename = "unmask")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unmask: Option<bool>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize)]
@@ -271,3 +292,928 @@ pub struct OutputLogEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct FilterLogEventsRequest {
+    /// <p>The end of the time range, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC. Events with a timestamp later than this time are not returned.</p>
+    #[serde(rename = "endTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<i64>,
+    /// <p>The filter pattern to use. If not provided, all the events are matched.</p>
+    #[serde(rename = "filterPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_pattern: Option<String>,
+    /// <p>If the value is true, the operation makes a best effort to provide responses that contain events from multiple log streams within the log group, interleaved in a single response.</p>
+    #[serde(rename = "interleaved")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interleaved: Option<bool>,
+    /// <p>The maximum number of events to return. The default is 10,000 events.</p>
+    #[serde(rename = "limit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    /// <p>The name of the log group to search.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+    /// <p>Filters the results to include only events from log streams that have names starting with this prefix.</p>
+    #[serde(rename = "logStreamNamePrefix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_name_prefix: Option<String>,
+    /// <p>Filters the results to only logs from the log streams in this list.</p>
+    #[serde(rename = "logStreamNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_names: Option<Vec<String>>,
+    /// <p>The token for the next set of events to return. (You received this token from a previous call.)</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>The start of the time range, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC. Events with a timestamp before this time are not returned.</p>
+    #[serde(rename = "startTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+    /// <p>Specify <code>true</code> to display the log event fields with all sensitive data unmasked and visible. The default is <code>false</code>.</p>
+    #[serde(rename = "unmask")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unmask: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct FilterLogEventsResponse {
+    /// <p>The matched events.</p>
+    #[serde(rename = "events")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<FilteredLogEvent>>,
+    /// <p>The token to use when requesting the next set of items. The token expires after 24 hours.</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>Indicates which log streams have been searched and whether each has been searched completely.</p>
+    #[serde(rename = "searchedLogStreams")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub searched_log_streams: Option<Vec<SearchedLogStream>>,
+}
+
+/// <p>Represents a matched event.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct FilteredLogEvent {
+    /// <p>The ID of the event.</p>
+    #[serde(rename = "eventId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    /// <p>The time the event was ingested, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "ingestionTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingestion_time: Option<i64>,
+    /// <p>The name of the log stream to which this event belongs.</p>
+    #[serde(rename = "logStreamName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_name: Option<String>,
+    /// <p>The data contained in the log event.</p>
+    #[serde(rename = "message")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// <p>The time the event occurred, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "timestamp")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+/// <p>Represents the search status of a log stream.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct SearchedLogStream {
+    /// <p>The name of the log stream.</p>
+    #[serde(rename = "logStreamName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_name: Option<String>,
+    /// <p>Indicates whether all the events in this log stream were searched.</p>
+    #[serde(rename = "searchedCompletely")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub searched_completely: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct StartLiveTailRequest {
+    /// <p>An optional pattern to use to filter the results to include only log events that match the pattern.</p>
+    #[serde(rename = "logEventFilterPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_event_filter_pattern: Option<String>,
+    /// <p>An array where each item in the array is a log group to include in the Live Tail session, given by name or ARN.</p>
+    #[serde(rename = "logGroupIdentifiers")]
+    pub log_group_identifiers: Vec<String>,
+    /// <p>If you specify this parameter, then only log events in the log streams that have names that start with the prefixes that you specify here are included in the Live Tail session.</p>
+    #[serde(rename = "logStreamNamePrefixes")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_name_prefixes: Option<Vec<String>>,
+    /// <p>If you specify this parameter, then only log events in the log streams that you specify here are included in the Live Tail session.</p>
+    #[serde(rename = "logStreamNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_names: Option<Vec<String>>,
+}
+
+/// <p>This object contains information about this Live Tail session, including the log groups included and the log stream filters, if any.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct LiveTailSessionStart {
+    #[serde(rename = "logEventFilterPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_event_filter_pattern: Option<String>,
+    #[serde(rename = "logGroupIdentifiers")]
+    pub log_group_identifiers: Vec<String>,
+    #[serde(rename = "logStreamNamePrefixes")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_name_prefixes: Option<Vec<String>>,
+    #[serde(rename = "logStreamNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_stream_names: Option<Vec<String>>,
+    /// <p>The unique ID generated by CloudWatch Logs to identify this Live Tail session request.</p>
+    #[serde(rename = "requestId")]
+    pub request_id: String,
+    /// <p>The unique ID generated by CloudWatch Logs to identify this Live Tail session.</p>
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+}
+
+/// <p>This object contains the log events and metadata for a Live Tail session.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct LiveTailSessionUpdate {
+    #[serde(rename = "sessionMetadata")]
+    pub session_metadata: LiveTailSessionMetadata,
+    #[serde(rename = "sessionResults")]
+    pub session_results: Vec<LiveTailSessionLogEvent>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct LiveTailSessionMetadata {
+    /// <p>If this is <code>true</code>, then more than 500 log events matched the request for this update, and the <code>sessionResults</code> includes a sample of 500 of those events.</p>
+    #[serde(rename = "sampled")]
+    pub sampled: bool,
+}
+
+/// <p>This object contains the information for one log event returned in a Live Tail stream.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct LiveTailSessionLogEvent {
+    #[serde(rename = "ingestionTime")]
+    pub ingestion_time: i64,
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+    #[serde(rename = "logStreamName")]
+    pub log_stream_name: String,
+    #[serde(rename = "message")]
+    pub message: String,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+}
+
+/// <p>A policy enabling one or more entities to put logs to a log group in this account.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourcePolicy {
+    /// <p>Timestamp showing when this policy was last updated, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "lastUpdatedTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated_time: Option<i64>,
+    /// <p>The details of the policy.</p>
+    #[serde(rename = "policyDocument")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_document: Option<String>,
+    /// <p>The name of the resource policy.</p>
+    #[serde(rename = "policyName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_name: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutResourcePolicyRequest {
+    /// <p>Details of the new policy, including the identity of the principal that is enabled to put logs to this account. This is formatted as a JSON string. This parameter is required.</p>
+    #[serde(rename = "policyDocument")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_document: Option<String>,
+    /// <p>Name of the new policy. This parameter is required.</p>
+    #[serde(rename = "policyName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_name: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PutResourcePolicyResponse {
+    /// <p>The new policy.</p>
+    #[serde(rename = "resourcePolicy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_policy: Option<ResourcePolicy>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeResourcePoliciesRequest {
+    /// <p>The maximum number of resource policies to be displayed with one call of this API.</p>
+    #[serde(rename = "limit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeResourcePoliciesResponse {
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>The resource policies that exist in this account.</p>
+    #[serde(rename = "resourcePolicies")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_policies: Option<Vec<ResourcePolicy>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteResourcePolicyRequest {
+    /// <p>The name of the policy to be revoked. This parameter is required.</p>
+    #[serde(rename = "policyName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_name: Option<String>,
+}
+
+/// <p>Represents a cross-account destination that receives subscription log events.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Destination {
+    /// <p>An IAM policy document that governs which AWS accounts can create subscription filters against this destination.</p>
+    #[serde(rename = "accessPolicy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_policy: Option<String>,
+    /// <p>The ARN of this destination.</p>
+    #[serde(rename = "arn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arn: Option<String>,
+    /// <p>The creation time of the destination, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "creationTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time: Option<i64>,
+    /// <p>The name of the destination.</p>
+    #[serde(rename = "destinationName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination_name: Option<String>,
+    /// <p>A role for impersonation, used when delivering log events to the target.</p>
+    #[serde(rename = "roleArn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_arn: Option<String>,
+    /// <p>The Amazon Resource Name (ARN) of the physical target where the log events are delivered (for example, a Kinesis stream).</p>
+    #[serde(rename = "targetArn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_arn: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutDestinationRequest {
+    /// <p>A name for the destination.</p>
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+    /// <p>The ARN of an IAM role that grants CloudWatch Logs permissions to call the Amazon Kinesis <code>PutRecord</code> operation on the destination stream.</p>
+    #[serde(rename = "roleArn")]
+    pub role_arn: String,
+    #[serde(rename = "tags")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<::std::collections::HashMap<String, String>>,
+    /// <p>The ARN of an Amazon Kinesis stream to which to deliver matching log events.</p>
+    #[serde(rename = "targetArn")]
+    pub target_arn: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PutDestinationResponse {
+    /// <p>The destination.</p>
+    #[serde(rename = "destination")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<Destination>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutDestinationPolicyRequest {
+    /// <p>An IAM policy document that authorizes cross-account users to deliver their log events to the associated destination.</p>
+    #[serde(rename = "accessPolicy")]
+    pub access_policy: String,
+    /// <p>A name for an existing destination.</p>
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+    #[serde(rename = "forceUpdate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_update: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeDestinationsRequest {
+    /// <p>The prefix to match. If you don't specify a value, no prefix filter is applied.</p>
+    #[serde(rename = "DestinationNamePrefix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination_name_prefix: Option<String>,
+    /// <p>The maximum number of items returned. If you don't specify a value, the default maximum value of 50 items is used.</p>
+    #[serde(rename = "limit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    /// <p>The token for the next set of items to return. (You received this token from a previous call.)</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeDestinationsResponse {
+    /// <p>The destinations.</p>
+    #[serde(rename = "destinations")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destinations: Option<Vec<Destination>>,
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteDestinationRequest {
+    /// <p>The name of the destination.</p>
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+}
+
+/// <p>This structure contains details about a saved CloudWatch Logs Insights query definition.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryDefinition {
+    /// <p>The date that the query definition was most recently modified.</p>
+    #[serde(rename = "lastModified")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<i64>,
+    /// <p>If this query definition contains a list of log groups that it is limited to, that list appears here.</p>
+    #[serde(rename = "logGroupNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_names: Option<Vec<String>>,
+    /// <p>The name of the query definition.</p>
+    #[serde(rename = "name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// <p>The unique ID of the query definition.</p>
+    #[serde(rename = "queryDefinitionId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_definition_id: Option<String>,
+    /// <p>The query string to use for this definition.</p>
+    #[serde(rename = "queryString")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_string: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutQueryDefinitionRequest {
+    /// <p>Use this parameter to include specific log groups as part of your query definition.</p>
+    #[serde(rename = "logGroupNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_names: Option<Vec<String>>,
+    /// <p>A name for the query definition.</p>
+    #[serde(rename = "name")]
+    pub name: String,
+    /// <p>If you are updating a query definition, use this parameter to specify the ID of the query definition that you want to update.</p>
+    #[serde(rename = "queryDefinitionId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_definition_id: Option<String>,
+    /// <p>The query string to use for this query definition.</p>
+    #[serde(rename = "queryString")]
+    pub query_string: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PutQueryDefinitionResponse {
+    /// <p>The ID of the query definition.</p>
+    #[serde(rename = "queryDefinitionId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_definition_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeQueryDefinitionsRequest {
+    /// <p>Limits the number of returned query definitions to the specified number.</p>
+    #[serde(rename = "maxResults")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<i64>,
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>Use this parameter to filter your results to only the query definitions that have names that start with the prefix you specify.</p>
+    #[serde(rename = "queryDefinitionNamePrefix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_definition_name_prefix: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeQueryDefinitionsResponse {
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>The list of query definitions that match your request.</p>
+    #[serde(rename = "queryDefinitions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_definitions: Option<Vec<QueryDefinition>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteQueryDefinitionRequest {
+    /// <p>The ID of the query definition that you want to delete.</p>
+    #[serde(rename = "queryDefinitionId")]
+    pub query_definition_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DeleteQueryDefinitionResponse {
+    /// <p>A value of TRUE indicates that the operation succeeded.</p>
+    #[serde(rename = "success")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct GetLogGroupFieldsRequest {
+    /// <p>The name of the log group to search.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+    /// <p>The time to set as the center of the query. If you specify <code>time</code>, the 15 minutes before this time are queries. If you omit <code>time</code>, the 15 minutes before now are queried. The <code>time</code> value is specified as epoch time, which is the number of seconds since <code>January 1, 1970, 00:00:00 UTC</code>.</p>
+    #[serde(rename = "time")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<i64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct GetLogGroupFieldsResponse {
+    /// <p>The array of fields found in the query.</p>
+    #[serde(rename = "logGroupFields")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_fields: Option<Vec<LogGroupField>>,
+}
+
+/// <p>The fields contained in log events found by a <code>GetLogGroupFields</code> operation, along with the percentage of queried log events in which each field appears.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct LogGroupField {
+    /// <p>The name of a log field.</p>
+    #[serde(rename = "name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// <p>The percentage of log events queried that contained the field.</p>
+    #[serde(rename = "percent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<i64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct GetLogRecordRequest {
+    /// <p>The pointer corresponding to the log event record you want to retrieve.</p>
+    #[serde(rename = "logRecordPointer")]
+    pub log_record_pointer: String,
+    /// <p>Specify <code>true</code> to display the log event fields with all sensitive data unmasked and visible. The default is <code>false</code>.</p>
+    #[serde(rename = "unmask")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unmask: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct GetLogRecordResponse {
+    /// <p>The requested log event, as a JSON string.</p>
+    #[serde(rename = "logRecord")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_record: Option<::std::collections::BTreeMap<String, String>>,
+}
+
+/// <p>Indicates how to transform ingested log events to metric data in a CloudWatch metric.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricTransformation {
+    /// <p>(Optional) The value to emit when a filter pattern does not match a log event.</p>
+    #[serde(rename = "defaultValue")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<f64>,
+    /// <p>The fields to use as dimensions for the metric.</p>
+    #[serde(rename = "dimensions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<::std::collections::HashMap<String, String>>,
+    /// <p>The name of the CloudWatch metric.</p>
+    #[serde(rename = "metricName")]
+    pub metric_name: String,
+    /// <p>A custom namespace to contain your metric in CloudWatch.</p>
+    #[serde(rename = "metricNamespace")]
+    pub metric_namespace: String,
+    /// <p>The value to publish to the CloudWatch metric when a filter pattern matches a log event.</p>
+    #[serde(rename = "metricValue")]
+    pub metric_value: String,
+    /// <p>The unit to assign to the metric.</p>
+    #[serde(rename = "unit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+/// <p>Metric filters express how CloudWatch Logs would extract metric observations from ingested log events and transform them into metric data in a CloudWatch metric.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricFilter {
+    /// <p>The creation time of the metric filter, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "creationTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time: Option<i64>,
+    /// <p>The name of the metric filter.</p>
+    #[serde(rename = "filterName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_name: Option<String>,
+    #[serde(rename = "filterPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_pattern: Option<String>,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_name: Option<String>,
+    /// <p>The metric transformations.</p>
+    #[serde(rename = "metricTransformations")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric_transformations: Option<Vec<MetricTransformation>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutMetricFilterRequest {
+    /// <p>A name for the metric filter.</p>
+    #[serde(rename = "filterName")]
+    pub filter_name: String,
+    /// <p>A filter pattern for extracting metric data out of ingested log events.</p>
+    #[serde(rename = "filterPattern")]
+    pub filter_pattern: String,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+    /// <p>A collection of information that defines how metric data gets emitted.</p>
+    #[serde(rename = "metricTransformations")]
+    pub metric_transformations: Vec<MetricTransformation>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeMetricFiltersRequest {
+    /// <p>The prefix to match. CloudWatch Logs uses the value you set here only if you also include the <code>logGroupName</code> parameter in your request.</p>
+    #[serde(rename = "filterNamePrefix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_name_prefix: Option<String>,
+    /// <p>The maximum number of items returned. If you don't specify a value, the default is up to 50 items.</p>
+    #[serde(rename = "limit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_name: Option<String>,
+    /// <p>Filters results to include only those with the specified metric name.</p>
+    #[serde(rename = "metricName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric_name: Option<String>,
+    /// <p>Filters results to include only those in the specified namespace.</p>
+    #[serde(rename = "metricNamespace")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric_namespace: Option<String>,
+    /// <p>The token for the next set of items to return. (You received this token from a previous call.)</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeMetricFiltersResponse {
+    /// <p>The metric filters.</p>
+    #[serde(rename = "metricFilters")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric_filters: Option<Vec<MetricFilter>>,
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteMetricFilterRequest {
+    /// <p>The name of the metric filter.</p>
+    #[serde(rename = "filterName")]
+    pub filter_name: String,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+}
+
+/// <p>Represents a subscription filter.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionFilter {
+    /// <p>The creation time of the subscription filter, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "creationTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time: Option<i64>,
+    /// <p>The Amazon Resource Name (ARN) of the destination.</p>
+    #[serde(rename = "destinationArn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination_arn: Option<String>,
+    #[serde(rename = "distribution")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+    /// <p>The name of the subscription filter.</p>
+    #[serde(rename = "filterName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_name: Option<String>,
+    #[serde(rename = "filterPattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_pattern: Option<String>,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_name: Option<String>,
+    #[serde(rename = "roleArn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_arn: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutSubscriptionFilterRequest {
+    /// <p>The ARN of the destination to deliver matching log events to.</p>
+    #[serde(rename = "destinationArn")]
+    pub destination_arn: String,
+    /// <p>The method used to distribute log data to the destination, <code>ByLogStream</code> or <code>Random</code>.</p>
+    #[serde(rename = "distribution")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+    /// <p>A name for the subscription filter.</p>
+    #[serde(rename = "filterName")]
+    pub filter_name: String,
+    /// <p>A filter pattern for subscribing to a filtered stream of log events.</p>
+    #[serde(rename = "filterPattern")]
+    pub filter_pattern: String,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+    /// <p>The ARN of an IAM role that grants CloudWatch Logs permissions to deliver ingested log events to the destination stream.</p>
+    #[serde(rename = "roleArn")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_arn: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeSubscriptionFiltersRequest {
+    /// <p>The prefix to match. If you don't specify a value, no prefix filter is applied.</p>
+    #[serde(rename = "filterNamePrefix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_name_prefix: Option<String>,
+    /// <p>The maximum number of items returned. If you don't specify a value, the default is up to 50 items.</p>
+    #[serde(rename = "limit")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+    /// <p>The token for the next set of items to return. (You received this token from a previous call.)</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeSubscriptionFiltersResponse {
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>The subscription filters.</p>
+    #[serde(rename = "subscriptionFilters")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_filters: Option<Vec<SubscriptionFilter>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteSubscriptionFilterRequest {
+    /// <p>The name of the subscription filter.</p>
+    #[serde(rename = "filterName")]
+    pub filter_name: String,
+    /// <p>The name of the log group.</p>
+    #[serde(rename = "logGroupName")]
+    pub log_group_name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutDataProtectionPolicyRequest {
+    /// <p>Specify either the log group name or log group ARN.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+    /// <p>Specify the data protection policy, in JSON.</p>
+    #[serde(rename = "policyDocument")]
+    pub policy_document: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PutDataProtectionPolicyResponse {
+    /// <p>The date and time that this policy was most recently updated.</p>
+    #[serde(rename = "lastUpdatedTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated_time: Option<i64>,
+    /// <p>The log group name or ARN that you specified in your request.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_identifier: Option<String>,
+    /// <p>The data protection policy used for this log group.</p>
+    #[serde(rename = "policyDocument")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_document: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct GetDataProtectionPolicyRequest {
+    /// <p>The name or ARN of the log group that contains the data protection policy that you want to see.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct GetDataProtectionPolicyResponse {
+    /// <p>The date and time that this policy was most recently updated.</p>
+    #[serde(rename = "lastUpdatedTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated_time: Option<i64>,
+    /// <p>The log group name or ARN that you specified in your request.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_identifier: Option<String>,
+    /// <p>The data protection policy document for this log group.</p>
+    #[serde(rename = "policyDocument")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_document: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteDataProtectionPolicyRequest {
+    /// <p>The name or ARN of the log group that you want to delete the data protection policy for.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+}
+
+/// <p>A structure that contains information about one CloudWatch Logs account policy.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountPolicy {
+    /// <p>The Amazon Web Services account ID that the policy applies to.</p>
+    #[serde(rename = "accountId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// <p>The date and time that this policy was most recently updated.</p>
+    #[serde(rename = "lastUpdatedTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated_time: Option<i64>,
+    /// <p>The policy document for this account policy.</p>
+    #[serde(rename = "policyDocument")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_document: Option<String>,
+    /// <p>The name of the account policy.</p>
+    #[serde(rename = "policyName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_name: Option<String>,
+    /// <p>The type of policy for this account policy.</p>
+    #[serde(rename = "policyType")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_type: Option<String>,
+    /// <p>The scope of the account policy.</p>
+    #[serde(rename = "scope")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// <p>The log group selection criteria that is used for this policy.</p>
+    #[serde(rename = "selectionCriteria")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_criteria: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutAccountPolicyRequest {
+    /// <p>Specify the policy, in JSON.</p>
+    #[serde(rename = "policyDocument")]
+    pub policy_document: String,
+    /// <p>A name for the policy. This must be unique within the account.</p>
+    #[serde(rename = "policyName")]
+    pub policy_name: String,
+    /// <p>The type of policy that you're creating or updating.</p>
+    #[serde(rename = "policyType")]
+    pub policy_type: String,
+    /// <p>Currently the only valid value for this parameter is <code>ALL</code>, which specifies that the data protection policy applies to all log groups in the account.</p>
+    #[serde(rename = "scope")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// <p>Use this parameter to apply the new policy to a subset of log groups in the account.</p>
+    #[serde(rename = "selectionCriteria")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_criteria: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PutAccountPolicyResponse {
+    /// <p>The account policy that you created.</p>
+    #[serde(rename = "accountPolicy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_policy: Option<AccountPolicy>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DescribeAccountPoliciesRequest {
+    /// <p>If you are using an account that is set up as a monitoring account for CloudWatch unified cross-account observability, you can use this to specify the account ID of a source account.</p>
+    #[serde(rename = "accountIdentifiers")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifiers: Option<Vec<String>>,
+    /// <p>The token for the next set of items to return. (You received this token from a previous call.)</p>
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+    /// <p>Use this parameter to limit the returned policies to only the policy with the name that you specify.</p>
+    #[serde(rename = "policyName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_name: Option<String>,
+    /// <p>Use this parameter to limit the returned policies to only the policies that match the policy type that you specify.</p>
+    #[serde(rename = "policyType")]
+    pub policy_type: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct DescribeAccountPoliciesResponse {
+    /// <p>An array of structures that contain information about the CloudWatch Logs account policies that match the specified filters.</p>
+    #[serde(rename = "accountPolicies")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_policies: Option<Vec<AccountPolicy>>,
+    #[serde(rename = "nextToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteAccountPolicyRequest {
+    /// <p>The name of the policy to delete.</p>
+    #[serde(rename = "policyName")]
+    pub policy_name: String,
+    /// <p>The type of policy to delete.</p>
+    #[serde(rename = "policyType")]
+    pub policy_type: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct PutTransformerRequest {
+    /// <p>Specify either the name or ARN of the log group to create the transformer for.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+    /// <p>This structure contains the configuration of this log transformer. A log transformer is an array of processors, where each processor applies one type of transformation to the log events that are ingested.</p>
+    #[serde(rename = "transformerConfig")]
+    pub transformer_config: Vec<Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct GetTransformerRequest {
+    /// <p>Specify either the name or ARN of the log group to return transformer information for.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct GetTransformerResponse {
+    /// <p>The creation time of the transformer, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "creationTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time: Option<i64>,
+    /// <p>The date and time when this transformer was most recently modified, expressed as the number of milliseconds after Jan 1, 1970 00:00:00 UTC.</p>
+    #[serde(rename = "lastModifiedTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified_time: Option<i64>,
+    /// <p>The ARN of the log group that you specified in your request.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_group_identifier: Option<String>,
+    /// <p>This structure contains the configuration of the requested transformer.</p>
+    #[serde(rename = "transformerConfig")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformer_config: Option<Vec<Value>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteTransformerRequest {
+    /// <p>Specify either the name or ARN of the log group to delete the transformer for.</p>
+    #[serde(rename = "logGroupIdentifier")]
+    pub log_group_identifier: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+pub struct TestTransformerRequest {
+    /// <p>An array of the raw log events that you want to use to test this transformer.</p>
+    #[serde(rename = "logEventMessages")]
+    pub log_event_messages: Vec<String>,
+    /// <p>This structure contains the configuration of this log transformer that you want to test.</p>
+    #[serde(rename = "transformerConfig")]
+    pub transformer_config: Vec<Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct TestTransformerResponse {
+    /// <p>An array where each member of the array includes both the original version and the transformed version of one of the log events that you input.</p>
+    #[serde(rename = "transformedLogs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformed_logs: Option<Vec<TransformedLogRecord>>,
+}
+
+/// <p>This structure contains information for one log event that has been processed by a log transformer.</p>
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct TransformedLogRecord {
+    /// <p>The original log event message before it was transformed.</p>
+    #[serde(rename = "eventMessage")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_message: Option<String>,
+    /// <p>The event number.</p>
+    #[serde(rename = "eventNumber")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_number: Option<i64>,
+    /// <p>The log event message after being transformed.</p>
+    #[serde(rename = "transformedEventMessage")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformed_event_message: Option<String>,
+}
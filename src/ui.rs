@@ -0,0 +1,42 @@
+//! A browser UI for looking through the mock's logs, served at `/_mock/ui`.
+//!
+//! The page is a single static file. It reads through `/_mock/ui/<Action>`,
+//! which runs the read only AWS actions directly, so what it shows is what
+//! the API would return, without the UI's polling being recorded as client
+//! traffic, throttled or hit by injected faults.
+
+use crate::actions::ServiceError;
+use crate::{Body, Response, Shared};
+use bytes::Bytes;
+use hyper::StatusCode;
+
+const PAGE: &str = include_str!("ui.html");
+
+/// The actions the UI may call.
+const ACTIONS: &[&str] = &[
+    "DescribeLogGroups",
+    "DescribeLogStreams",
+    "FilterLogEvents",
+    "GetLogEvents",
+];
+
+pub fn page() -> Response {
+    hyper::Response::builder()
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(Body::from(PAGE))
+        .unwrap()
+}
+
+pub fn query(shared: &Shared, action: &str, body: &Bytes) -> Response {
+    if !ACTIONS.contains(&action) {
+        return hyper::Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let target = format!("Logs_20140328.{}", action);
+    shared
+        .apply(&target, body)
+        .unwrap_or_else(|_| ServiceError::InternalFailure.into())
+}
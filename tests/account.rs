@@ -0,0 +1,284 @@
+use mockwatchlogs::MockServer;
+use serde_json::json;
+
+mod common;
+use common::{aws, serve, start_server};
+
+const POLICY: &str = r#"{"Version":"2012-10-17","Statement":[]}"#;
+
+#[test]
+fn resource_policies() {
+    let (_, addr) = start_server();
+
+    let put = json!({ "policyName": "route53", "policyDocument": POLICY });
+    let (status, body) = aws(addr, "PutResourcePolicy", put);
+    assert_eq!(status, 200);
+    assert_eq!(body["resourcePolicy"]["policyName"], "route53");
+    assert!(body["resourcePolicy"]["lastUpdatedTime"].is_i64());
+
+    let invalid = json!({ "policyName": "bad", "policyDocument": "{not json" });
+    let (status, body) = aws(addr, "PutResourcePolicy", invalid);
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "InvalidParameterException");
+
+    let huge = format!(r#"{{"Statement":"{}"}}"#, "x".repeat(5120));
+    let huge = json!({ "policyName": "huge", "policyDocument": huge });
+    assert_eq!(
+        aws(addr, "PutResourcePolicy", huge).1["__type"],
+        "LimitExceededException"
+    );
+
+    for i in 1..10 {
+        let put = json!({ "policyName": format!("policy-{}", i), "policyDocument": POLICY });
+        assert_eq!(aws(addr, "PutResourcePolicy", put).0, 200);
+    }
+
+    let eleventh = json!({ "policyName": "one-too-many", "policyDocument": POLICY });
+    let (status, body) = aws(addr, "PutResourcePolicy", eleventh);
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "LimitExceededException");
+
+    // Replacing a policy does not count against the limit.
+    let replace = json!({ "policyName": "route53", "policyDocument": "{}" });
+    assert_eq!(aws(addr, "PutResourcePolicy", replace).0, 200);
+
+    let (_, body) = aws(addr, "DescribeResourcePolicies", json!({ "limit": 8 }));
+    assert_eq!(body["resourcePolicies"].as_array().unwrap().len(), 8);
+    let token = body["nextToken"].clone();
+    let (_, body) = aws(
+        addr,
+        "DescribeResourcePolicies",
+        json!({ "nextToken": token }),
+    );
+    let rest = body["resourcePolicies"].as_array().unwrap();
+    assert_eq!(rest.len(), 2);
+    assert_eq!(rest[1]["policyName"], "route53");
+    assert_eq!(rest[1]["policyDocument"], "{}");
+    assert!(body["nextToken"].is_null());
+
+    let delete = json!({ "policyName": "route53" });
+    assert_eq!(aws(addr, "DeleteResourcePolicy", delete.clone()).0, 200);
+    let (status, body) = aws(addr, "DeleteResourcePolicy", delete);
+    assert_eq!(status, 404);
+    assert_eq!(body["__type"], "ResourceNotFoundException");
+}
+
+#[test]
+fn destinations() {
+    let (_, addr) = start_server();
+
+    let put = json!({
+        "destinationName": "central",
+        "targetArn": "arn:aws:kinesis:us-east-1:123456789012:stream/logs",
+        "roleArn": "arn:aws:iam::123456789012:role/logs",
+    });
+    let (status, body) = aws(addr, "PutDestination", put);
+    assert_eq!(status, 200);
+    assert_eq!(
+        body["destination"]["arn"],
+        "arn:aws:logs:us-east-1:123456789012:destination:central"
+    );
+
+    let policy = json!({ "destinationName": "central", "accessPolicy": POLICY });
+    assert_eq!(aws(addr, "PutDestinationPolicy", policy).0, 200);
+
+    let missing = json!({ "destinationName": "nope", "accessPolicy": POLICY });
+    assert_eq!(aws(addr, "PutDestinationPolicy", missing).0, 404);
+
+    let invalid = json!({ "destinationName": "central", "accessPolicy": "[]" });
+    assert_eq!(aws(addr, "PutDestinationPolicy", invalid).0, 400);
+
+    // Updating the target keeps the policy.
+    let update = json!({
+        "destinationName": "central",
+        "targetArn": "arn:aws:kinesis:us-east-1:123456789012:stream/other",
+        "roleArn": "arn:aws:iam::123456789012:role/logs",
+    });
+    aws(addr, "PutDestination", update);
+
+    let other = json!({ "destinationName": "backup", "targetArn": "t", "roleArn": "r" });
+    aws(addr, "PutDestination", other);
+
+    let prefix = json!({ "DestinationNamePrefix": "cen" });
+    let (_, body) = aws(addr, "DescribeDestinations", prefix);
+    let destinations = body["destinations"].as_array().unwrap();
+    assert_eq!(destinations.len(), 1);
+    assert_eq!(destinations[0]["accessPolicy"], POLICY);
+    assert_eq!(
+        destinations[0]["targetArn"],
+        "arn:aws:kinesis:us-east-1:123456789012:stream/other"
+    );
+
+    let delete = json!({ "destinationName": "central" });
+    assert_eq!(aws(addr, "DeleteDestination", delete.clone()).0, 200);
+    assert_eq!(aws(addr, "DeleteDestination", delete).0, 404);
+
+    let (_, body) = aws(addr, "DescribeDestinations", json!({}));
+    assert_eq!(body["destinations"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn query_definitions() {
+    let (_, addr) = start_server();
+
+    let put = json!({
+        "name": "errors/recent",
+        "queryString": "fields @message | filter @message like /ERROR/",
+        "logGroupNames": ["app"],
+    });
+    let (status, body) = aws(addr, "PutQueryDefinition", put);
+    assert_eq!(status, 200);
+    let id = body["queryDefinitionId"].as_str().unwrap().to_string();
+
+    let other = json!({ "name": "latency", "queryString": "stats avg(latency)" });
+    aws(addr, "PutQueryDefinition", other);
+
+    let update = json!({
+        "name": "errors/recent",
+        "queryDefinitionId": id,
+        "queryString": "fields @message | limit 20",
+    });
+    assert_eq!(
+        aws(addr, "PutQueryDefinition", update).1["queryDefinitionId"],
+        id
+    );
+
+    let missing = json!({ "name": "x", "queryDefinitionId": "nope", "queryString": "x" });
+    assert_eq!(aws(addr, "PutQueryDefinition", missing).0, 404);
+
+    let prefix = json!({ "queryDefinitionNamePrefix": "errors/" });
+    let (_, body) = aws(addr, "DescribeQueryDefinitions", prefix);
+    let definitions = body["queryDefinitions"].as_array().unwrap();
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(definitions[0]["queryDefinitionId"], id);
+    assert_eq!(definitions[0]["queryString"], "fields @message | limit 20");
+    assert!(definitions[0]["logGroupNames"].is_null());
+
+    let (_, body) = aws(addr, "DescribeQueryDefinitions", json!({ "maxResults": 1 }));
+    assert_eq!(body["queryDefinitions"][0]["name"], "errors/recent");
+    let next = json!({ "nextToken": body["nextToken"] });
+    let (_, body) = aws(addr, "DescribeQueryDefinitions", next);
+    assert_eq!(body["queryDefinitions"][0]["name"], "latency");
+
+    let delete = json!({ "queryDefinitionId": id });
+    assert_eq!(
+        aws(addr, "DeleteQueryDefinition", delete.clone()).1["success"],
+        true
+    );
+    assert_eq!(aws(addr, "DeleteQueryDefinition", delete).0, 404);
+}
+
+#[test]
+fn persist_account_resources() {
+    let dir = std::env::temp_dir().join(format!("mockwatchlogs-{}-account", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let server = MockServer::new();
+    server.persist(&dir).unwrap();
+    let addr = serve(&server);
+
+    let put = json!({ "name": "saved", "queryString": "fields @message" });
+    aws(addr, "PutQueryDefinition", put);
+    let put = json!({ "policyName": "route53", "policyDocument": POLICY });
+    aws(addr, "PutResourcePolicy", put);
+
+    let restarted = MockServer::new();
+    assert!(restarted.persist(&dir).unwrap());
+    let addr = serve(&restarted);
+
+    let (_, body) = aws(addr, "DescribeQueryDefinitions", json!({}));
+    assert_eq!(body["queryDefinitions"][0]["name"], "saved");
+    let (_, body) = aws(addr, "DescribeResourcePolicies", json!({}));
+    assert_eq!(body["resourcePolicies"][0]["policyName"], "route53");
+
+    // New IDs do not reuse those handed out before the restart.
+    let put = json!({ "name": "another", "queryString": "fields @message" });
+    aws(addr, "PutQueryDefinition", put);
+    let (_, body) = aws(addr, "DescribeQueryDefinitions", json!({}));
+    assert_eq!(body["queryDefinitions"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn account_policies() {
+    let (_, addr) = start_server();
+    for group in &["app-web", "other"] {
+        aws(addr, "CreateLogGroup", json!({ "logGroupName": group }));
+    }
+
+    let document = json!({
+        "DestinationArn": "arn:aws:logs:us-east-1:123456789012:destination:central",
+        "FilterPattern": "",
+        "Distribution": "Random",
+    })
+    .to_string();
+    let put = json!({
+        "policyName": "ship-everything",
+        "policyType": "SUBSCRIPTION_FILTER_POLICY",
+        "policyDocument": document,
+        "selectionCriteria": "LogGroupNamePrefix IN [\"app\"]",
+    });
+    let (status, body) = aws(addr, "PutAccountPolicy", put.clone());
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["accountPolicy"]["accountId"], "123456789012");
+    assert_eq!(body["accountPolicy"]["scope"], "ALL");
+
+    let mut renamed = put.clone();
+    renamed["policyName"] = json!("another");
+    let (_, body) = aws(addr, "PutAccountPolicy", renamed);
+    assert_eq!(body["__type"], "LimitExceededException");
+
+    let mut invalid = put.clone();
+    invalid["selectionCriteria"] = json!("LogGroupName LIKE [\"app\"]");
+    assert_eq!(aws(addr, "PutAccountPolicy", invalid).0, 400);
+    let mut invalid = put.clone();
+    invalid["policyDocument"] = json!("{\"FilterPattern\": \"\"}");
+    assert_eq!(aws(addr, "PutAccountPolicy", invalid).0, 400);
+    let mut invalid = put.clone();
+    invalid["policyType"] = json!("LOUD_POLICY");
+    assert_eq!(aws(addr, "PutAccountPolicy", invalid).0, 400);
+
+    let describe = json!({ "policyType": "SUBSCRIPTION_FILTER_POLICY" });
+    let (_, body) = aws(addr, "DescribeAccountPolicies", describe.clone());
+    let policies = body["accountPolicies"].as_array().unwrap();
+    assert_eq!(policies.len(), 1);
+    assert_eq!(policies[0]["policyName"], "ship-everything");
+    let (_, body) = aws(
+        addr,
+        "DescribeAccountPolicies",
+        json!({ "policyType": "DATA_PROTECTION_POLICY" }),
+    );
+    assert!(body["accountPolicies"].as_array().unwrap().is_empty());
+
+    // The policy takes one of the two subscription filter places of the
+    // groups it selects.
+    for (group, accepted) in &[("app-web", 1), ("other", 2)] {
+        for i in 0..2 {
+            let subscription = json!({
+                "logGroupName": group,
+                "filterName": format!("filter-{}", i),
+                "filterPattern": "",
+                "destinationArn": "arn:aws:kinesis:us-east-1:123456789012:stream/logs",
+            });
+            let (status, _) = aws(addr, "PutSubscriptionFilter", subscription);
+            assert_eq!(status == 200, i < *accepted, "{} {}", group, i);
+        }
+    }
+
+    let delete = json!({ "policyName": "another", "policyType": "SUBSCRIPTION_FILTER_POLICY" });
+    assert_eq!(aws(addr, "DeleteAccountPolicy", delete).0, 404);
+    let delete =
+        json!({ "policyName": "ship-everything", "policyType": "SUBSCRIPTION_FILTER_POLICY" });
+    assert_eq!(aws(addr, "DeleteAccountPolicy", delete).0, 200);
+
+    let (_, body) = aws(addr, "DescribeAccountPolicies", describe);
+    assert!(body["accountPolicies"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn unknown_action() {
+    let (_, addr) = start_server();
+
+    let (status, body) = aws(addr, "FrobnicateLogs", json!({}));
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "UnknownOperationException");
+}
@@ -0,0 +1,200 @@
+use hyper::Method;
+use serde_json::json;
+
+mod common;
+use common::{aws, call, start_server};
+
+#[test]
+fn reset_clears_state() {
+    let (server, addr) = start_server();
+
+    let (status, _) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 200);
+    assert!(server.group("app").exists());
+
+    let (status, _) = call(addr, Method::POST, "/_mock/reset", "");
+    assert_eq!(status, 200);
+    assert!(!server.group("app").exists());
+
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests, json!([]));
+}
+
+#[test]
+fn reset_restores_startup_faults_quotas_and_limits() {
+    let (server, addr) = start_server();
+    server.add_fault(
+        "action=DescribeLogGroups,error=ServiceUnavailableException"
+            .parse()
+            .unwrap(),
+    );
+    server.set_quotas(vec!["CreateLogGroup=1".parse().unwrap()]);
+    server.clock().freeze();
+    server.clock().set(0);
+
+    let rule = json!({
+        "action": "CreateLogStream",
+        "fault": { "type": "error", "code": "ThrottlingException" }
+    });
+    call(addr, Method::POST, "/_mock/faults/rules", &rule.to_string());
+    call(addr, Method::PUT, "/_mock/quotas", "[]");
+    call(addr, Method::PUT, "/_mock/limits", r#"{"maxGroups": 1}"#);
+
+    let (status, _) = call(addr, Method::POST, "/_mock/reset", "");
+    assert_eq!(status, 200);
+
+    assert!(!server.clock().is_frozen());
+    assert!(server.clock().now() > 0);
+
+    let (_, faults) = call(addr, Method::GET, "/_mock/faults", "");
+    assert_eq!(faults["rules"].as_array().unwrap().len(), 1);
+    let (status, _) = aws(addr, "DescribeLogGroups", json!({}));
+    assert_eq!(status, 503);
+
+    let (_, quotas) = call(addr, Method::GET, "/_mock/quotas", "");
+    assert_eq!(quotas.as_array().unwrap().len(), 1);
+
+    let (_, usage) = call(addr, Method::GET, "/_mock/usage", "");
+    assert_eq!(usage["limits"]["maxGroups"], json!(null));
+}
+
+#[test]
+fn dump_and_load_state() {
+    let (_, addr) = start_server();
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    let (_, state) = call(addr, Method::GET, "/_mock/state", "");
+    assert!(state["groups"]["app"].is_object());
+
+    let (other, other_addr) = start_server();
+    let (status, _) = call(other_addr, Method::PUT, "/_mock/state", &state.to_string());
+    assert_eq!(status, 200);
+    assert!(other.group("app").exists());
+
+    let (status, _) = call(other_addr, Method::PUT, "/_mock/state", "not json");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn list_requests() {
+    let (_, addr) = start_server();
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests[0]["target"], "Logs_20140328.CreateLogGroup");
+    assert_eq!(requests[0]["body"]["logGroupName"], "app");
+}
+
+#[test]
+fn requests_are_capped() {
+    let (server, addr) = start_server();
+    server.set_max_requests(2);
+
+    for name in &["a", "b", "c"] {
+        aws(addr, "CreateLogGroup", json!({ "logGroupName": name }));
+    }
+
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests.as_array().unwrap().len(), 2);
+    assert_eq!(requests[0]["body"]["logGroupName"], "b");
+    assert_eq!(requests[1]["body"]["logGroupName"], "c");
+
+    // The cap outlives a reset.
+    call(addr, Method::POST, "/_mock/reset", "");
+    for name in &["d", "e", "f"] {
+        aws(addr, "CreateLogGroup", json!({ "logGroupName": name }));
+    }
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests.as_array().unwrap().len(), 2);
+
+    server.set_max_requests(0);
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests, json!([]));
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "g" }));
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests, json!([]));
+}
+
+#[test]
+fn toggle_faults() {
+    let (_, addr) = start_server();
+
+    call(addr, Method::PUT, "/_mock/faults", r#"{"enabled":true}"#);
+    let (status, body) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 503);
+    assert_eq!(body["__type"], "ServiceUnavailableException");
+
+    call(addr, Method::PUT, "/_mock/faults", r#"{"enabled":false}"#);
+    let (status, _) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn reject_overflowing_clock_offsets() {
+    let (server, addr) = start_server();
+    let now = server.clock().now();
+
+    for body in &[
+        r#"{"advance":"+9999999999999999w"}"#,
+        r#"{"now":"+1000000000000000w"}"#,
+    ] {
+        let (status, _) = call(addr, Method::PUT, "/_mock/clock", body);
+        assert_eq!(status, 400);
+    }
+
+    assert!(server.clock().now() - now < 60 * 1000);
+}
+
+#[test]
+fn health_and_readiness() {
+    let (server, addr) = start_server();
+
+    let (status, body) = call(addr, Method::GET, "/health", "");
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(call(addr, Method::GET, "/ready", "").0, 200);
+
+    server.set_ready(false);
+    let (status, body) = call(addr, Method::GET, "/ready", "");
+    assert_eq!(status, 503);
+    assert_eq!(body["status"], "starting");
+    assert_eq!(call(addr, Method::GET, "/health", "").0, 200);
+
+    let (status, body) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 503);
+    assert_eq!(body["__type"], "ServiceUnavailableException");
+
+    server.set_ready(true);
+    assert_eq!(call(addr, Method::GET, "/ready", "").0, 200);
+}
+
+#[test]
+fn missing_target() {
+    let (_, addr) = start_server();
+
+    let (status, body) = call(addr, Method::POST, "/", "{}");
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "UnknownOperationException");
+}
+
+#[test]
+fn web_ui() {
+    let (_, addr) = start_server();
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+
+    assert_eq!(call(addr, Method::GET, "/_mock/ui", "").0, 200);
+
+    let (status, body) = call(addr, Method::POST, "/_mock/ui/DescribeLogGroups", "{}");
+    assert_eq!(status, 200);
+    assert_eq!(body["logGroups"][0]["logGroupName"], "app");
+    assert!(body["logGroups"][0]["creationTime"].as_i64().unwrap() > 0);
+
+    // Only reads are allowed, and they are not client traffic.
+    let create = json!({ "logGroupName": "other" }).to_string();
+    let (status, _) = call(addr, Method::POST, "/_mock/ui/CreateLogGroup", &create);
+    assert_eq!(status, 404);
+
+    let (_, requests) = call(addr, Method::GET, "/_mock/requests", "");
+    assert_eq!(requests.as_array().unwrap().len(), 1);
+}
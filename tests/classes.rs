@@ -0,0 +1,158 @@
+use serde_json::json;
+use std::time::Duration;
+
+mod common;
+use common::{aws, start_server};
+
+#[test]
+fn create_and_filter_by_class() {
+    let (_, addr) = start_server();
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    let ia = json!({ "logGroupName": "noisy", "logGroupClass": "INFREQUENT_ACCESS" });
+    assert_eq!(aws(addr, "CreateLogGroup", ia).0, 200);
+
+    let bad = json!({ "logGroupName": "bad", "logGroupClass": "GLACIER" });
+    let (status, body) = aws(addr, "CreateLogGroup", bad);
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "InvalidParameterException");
+
+    let (_, body) = aws(addr, "DescribeLogGroups", json!({}));
+    let classes: Vec<_> = body["logGroups"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|g| (g["logGroupName"].clone(), g["logGroupClass"].clone()))
+        .collect();
+    assert_eq!(
+        classes,
+        vec![
+            (json!("app"), json!("STANDARD")),
+            (json!("noisy"), json!("INFREQUENT_ACCESS")),
+        ]
+    );
+
+    let filter = json!({ "logGroupClass": "INFREQUENT_ACCESS" });
+    let (_, body) = aws(addr, "DescribeLogGroups", filter);
+    assert_eq!(body["logGroups"].as_array().unwrap().len(), 1);
+    assert_eq!(body["logGroups"][0]["logGroupName"], "noisy");
+
+    let (_, body) = aws(addr, "DescribeLogGroups", json!({ "limit": 1 }));
+    assert_eq!(body["logGroups"][0]["logGroupName"], "app");
+    let next = json!({ "nextToken": body["nextToken"] });
+    let (_, body) = aws(addr, "DescribeLogGroups", next);
+    assert_eq!(body["logGroups"][0]["logGroupName"], "noisy");
+    assert!(body["nextToken"].is_null());
+}
+
+#[test]
+fn infrequent_access_restrictions() {
+    let (server, addr) = start_server();
+    server.set_live_tail_timeout(Duration::from_millis(10));
+
+    for (name, class) in &[("app", "STANDARD"), ("noisy", "INFREQUENT_ACCESS")] {
+        let create = json!({ "logGroupName": name, "logGroupClass": class });
+        aws(addr, "CreateLogGroup", create);
+    }
+
+    for group in &["app", "noisy"] {
+        let metric = json!({
+            "logGroupName": group,
+            "filterName": "errors",
+            "filterPattern": "ERROR",
+            "metricTransformations": [{
+                "metricName": "Errors",
+                "metricNamespace": "App",
+                "metricValue": "1",
+            }],
+        });
+        let subscription = json!({
+            "logGroupName": group,
+            "filterName": "ship",
+            "filterPattern": "",
+            "destinationArn": "arn:aws:logs:us-east-1:123456789012:destination:central",
+        });
+        let tail = json!({ "logGroupIdentifiers": [group] });
+
+        let results = vec![
+            aws(addr, "PutMetricFilter", metric),
+            aws(addr, "PutSubscriptionFilter", subscription),
+            aws(addr, "StartLiveTail", tail),
+        ];
+
+        for (status, body) in results {
+            if *group == "app" {
+                assert_eq!(status, 200, "{}", body);
+            } else {
+                assert_eq!(status, 400);
+                assert_eq!(body["__type"], "InvalidParameterException");
+            }
+        }
+    }
+}
+
+#[test]
+fn metric_and_subscription_filters() {
+    let (_, addr) = start_server();
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+
+    let metric = json!({
+        "logGroupName": "app",
+        "filterName": "errors",
+        "filterPattern": "{ $.level = \"error\" }",
+        "metricTransformations": [{
+            "metricName": "Errors",
+            "metricNamespace": "App",
+            "metricValue": "1",
+        }],
+    });
+    assert_eq!(aws(addr, "PutMetricFilter", metric).0, 200);
+
+    let invalid = json!({
+        "logGroupName": "app",
+        "filterName": "broken",
+        "filterPattern": "{ $.level = ",
+        "metricTransformations": [{ "metricName": "M", "metricNamespace": "N", "metricValue": "1" }],
+    });
+    assert_eq!(aws(addr, "PutMetricFilter", invalid).0, 400);
+
+    let (_, body) = aws(
+        addr,
+        "DescribeMetricFilters",
+        json!({ "metricName": "Errors" }),
+    );
+    assert_eq!(body["metricFilters"][0]["filterName"], "errors");
+    assert_eq!(body["metricFilters"][0]["logGroupName"], "app");
+    let (_, body) = aws(addr, "DescribeLogGroups", json!({}));
+    assert_eq!(body["logGroups"][0]["metricFilterCount"], 1);
+
+    for name in &["one", "two", "three"] {
+        let subscription = json!({
+            "logGroupName": "app",
+            "filterName": name,
+            "filterPattern": "",
+            "destinationArn": "arn:aws:kinesis:us-east-1:123456789012:stream/logs",
+        });
+        let (status, body) = aws(addr, "PutSubscriptionFilter", subscription);
+        if *name == "three" {
+            assert_eq!(body["__type"], "LimitExceededException");
+        } else {
+            assert_eq!(status, 200);
+        }
+    }
+
+    let describe = json!({ "logGroupName": "app", "filterNamePrefix": "t" });
+    let (_, body) = aws(addr, "DescribeSubscriptionFilters", describe);
+    assert_eq!(body["subscriptionFilters"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        body["subscriptionFilters"][0]["distribution"],
+        "ByLogStream"
+    );
+
+    let delete = json!({ "logGroupName": "app", "filterName": "two" });
+    assert_eq!(aws(addr, "DeleteSubscriptionFilter", delete.clone()).0, 200);
+    assert_eq!(aws(addr, "DeleteSubscriptionFilter", delete).0, 404);
+
+    let delete = json!({ "logGroupName": "app", "filterName": "errors" });
+    assert_eq!(aws(addr, "DeleteMetricFilter", delete).0, 200);
+}
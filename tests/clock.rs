@@ -0,0 +1,110 @@
+use mockwatchlogs::fixture::Fixture;
+use mockwatchlogs::testing::Events;
+use mockwatchlogs::MockServer;
+use rusoto_logs::{
+    CloudWatchLogs, CreateLogStreamRequest, GetLogEventsRequest, InputLogEvent, OutputLogEvent,
+    PutLogEventsRequest, PutLogEventsResponse,
+};
+
+mod common;
+use common::{client, serve};
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+const NOW: i64 = 1_500_000_000_000;
+
+#[test]
+fn frozen_ingestion_time() {
+    let server = MockServer::new();
+    server.clock().freeze();
+    server.clock().set(NOW);
+    let client = client(serve(&server));
+
+    create_stream(&server, &client, None);
+    put(&client, &[NOW - 1000]);
+
+    let events = get(&client);
+    assert_eq!(events[0].ingestion_time, Some(NOW));
+}
+
+#[test]
+fn reject_too_old_and_too_new() {
+    let server = MockServer::new();
+    server.clock().freeze();
+    server.clock().set(NOW);
+    let client = client(serve(&server));
+
+    create_stream(&server, &client, None);
+    let res = put(&client, &[NOW - 15 * DAY, NOW, NOW + DAY]);
+
+    let rejected = res.rejected_log_events_info.unwrap();
+    assert_eq!(rejected.too_old_log_event_end_index, Some(1));
+    assert_eq!(rejected.too_new_log_event_start_index, Some(2));
+    assert_eq!(rejected.expired_log_event_end_index, None);
+
+    let events = server.group("app").stream("web-1").events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].timestamp, NOW);
+}
+
+#[test]
+fn retention_expiry() {
+    let server = MockServer::new();
+    server.clock().freeze();
+    server.clock().set(NOW);
+    let client = client(serve(&server));
+
+    create_stream(&server, &client, Some(7));
+    put(&client, &[NOW - 2 * DAY, NOW]);
+    assert_eq!(get(&client).len(), 2);
+
+    server.clock().advance(6 * DAY);
+
+    let events = get(&client);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].timestamp, Some(NOW));
+}
+
+fn get(client: &impl CloudWatchLogs) -> Vec<OutputLogEvent> {
+    let req = GetLogEventsRequest {
+        log_group_name: "app".into(),
+        log_stream_name: "web-1".into(),
+        start_from_head: Some(true),
+        ..Default::default()
+    };
+    client.get_log_events(req).sync().unwrap().events.unwrap()
+}
+
+/// Create `web-1` in an `app` group with `retention` days of retention.
+fn create_stream(server: &MockServer, client: &impl CloudWatchLogs, retention: Option<i64>) {
+    let fixture = match retention {
+        Some(days) => format!(
+            r#"{{ "groups": [{{ "name": "app", "retentionInDays": {} }}] }}"#,
+            days
+        ),
+        None => r#"{ "groups": [{ "name": "app" }] }"#.to_string(),
+    };
+    let fixture = Fixture::from_slice(fixture.as_bytes()).unwrap();
+    server.load_fixture(&fixture).unwrap();
+
+    let req = CreateLogStreamRequest {
+        log_group_name: "app".into(),
+        log_stream_name: "web-1".into(),
+    };
+    client.create_log_stream(req).sync().unwrap();
+}
+
+fn put(client: &impl CloudWatchLogs, timestamps: &[i64]) -> PutLogEventsResponse {
+    let req = PutLogEventsRequest {
+        log_events: timestamps
+            .iter()
+            .map(|&timestamp| InputLogEvent {
+                message: timestamp.to_string(),
+                timestamp,
+            })
+            .collect(),
+        log_group_name: "app".into(),
+        log_stream_name: "web-1".into(),
+        sequence_token: None,
+    };
+    client.put_log_events(req).sync().unwrap()
+}
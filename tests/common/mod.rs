@@ -0,0 +1,145 @@
+//! Helpers shared by the integration tests. Each test file pulls this in
+//! with `mod common;` and uses only some of it.
+
+#![allow(dead_code)]
+
+use futures::{Future, Stream};
+use hyper::{Body, Client, Method, Request};
+use mockwatchlogs::MockServer;
+use rusoto_core::Region;
+use rusoto_logs::{CloudWatchLogs, CloudWatchLogsClient};
+use serde_json::{json, Value};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static NEXT_PORT: AtomicUsize = AtomicUsize::new(20234);
+
+/// A local address no other test in this run has been given, skipping
+/// ports something else is listening on.
+pub fn next_addr() -> SocketAddr {
+    loop {
+        let port = NEXT_PORT.fetch_add(1, Ordering::AcqRel) as u16;
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
+
+        if TcpListener::bind(addr).is_ok() {
+            return addr;
+        }
+    }
+}
+
+/// Serve `server` on a fresh address from a thread of its own.
+pub fn serve(server: &MockServer) -> SocketAddr {
+    let addr = next_addr();
+
+    let handle = server.clone();
+    std::thread::spawn(move || {
+        use tokio::runtime::current_thread;
+        let serve = handle.serve(addr);
+
+        current_thread::run(serve);
+    });
+
+    std::thread::sleep(Duration::from_millis(100));
+
+    addr
+}
+
+pub fn start_server() -> (MockServer, SocketAddr) {
+    let server = MockServer::new();
+    let addr = serve(&server);
+    (server, addr)
+}
+
+pub fn client(addr: SocketAddr) -> impl CloudWatchLogs {
+    let endpoint = format!("http://localhost:{}", addr.port());
+    let region = Region::Custom {
+        name: "mockwatchlogs".into(),
+        endpoint,
+    };
+
+    CloudWatchLogsClient::new(region)
+}
+
+/// Send a request, returning the status and the raw body.
+pub fn request(
+    addr: SocketAddr,
+    method: Method,
+    path: &str,
+    target: Option<&str>,
+    body: &str,
+) -> (u16, Vec<u8>) {
+    let mut req = Request::builder();
+    req.method(method).uri(format!("http://{}{}", addr, path));
+
+    if let Some(target) = target {
+        req.header("X-Amz-Target", target);
+    }
+
+    let req = req.body(Body::from(body.to_string())).unwrap();
+
+    let client = Client::new();
+    let fut = client.request(req).and_then(|res| {
+        let status = res.status().as_u16();
+        res.into_body()
+            .concat2()
+            .map(move |body| (status, body.to_vec()))
+    });
+
+    tokio::runtime::current_thread::Runtime::new()
+        .unwrap()
+        .block_on(fut)
+        .unwrap()
+}
+
+/// Like `request`, with the body parsed as JSON, or `null` if it is not.
+pub fn send(
+    addr: SocketAddr,
+    method: Method,
+    path: &str,
+    target: Option<&str>,
+    body: &str,
+) -> (u16, Value) {
+    let (status, body) = request(addr, method, path, target, body);
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Call an AWS action.
+pub fn aws(addr: SocketAddr, action: &str, body: Value) -> (u16, Value) {
+    let (status, body) = raw(addr, action, body);
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Call an AWS action, returning the raw body.
+pub fn raw(addr: SocketAddr, action: &str, body: Value) -> (u16, Vec<u8>) {
+    let target = format!("Logs_20140328.{}", action);
+    request(addr, Method::POST, "/", Some(&target), &body.to_string())
+}
+
+/// Call the admin API, or anything else not under `X-Amz-Target`.
+pub fn call(addr: SocketAddr, method: Method, path: &str, body: &str) -> (u16, Value) {
+    send(addr, method, path, None, body)
+}
+
+/// Milliseconds since the epoch, per the system clock.
+pub fn now() -> i64 {
+    let since = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    since.as_secs() as i64 * 1000 + i64::from(since.subsec_millis())
+}
+
+/// Put `messages` into `app`/`web`, creating them as needed.
+pub fn put(addr: SocketAddr, messages: &[&str], timestamp: i64) {
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    aws(
+        addr,
+        "CreateLogStream",
+        json!({ "logGroupName": "app", "logStreamName": "web" }),
+    );
+
+    let events: Vec<Value> = messages
+        .iter()
+        .map(|message| json!({ "timestamp": timestamp, "message": message }))
+        .collect();
+    let put = json!({ "logGroupName": "app", "logStreamName": "web", "logEvents": events });
+    assert_eq!(aws(addr, "PutLogEvents", put).0, 200);
+}
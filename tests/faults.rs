@@ -0,0 +1,134 @@
+use futures::{Future, Stream};
+use hyper::{Body, Client, Method, Request};
+use mockwatchlogs::faults::{Fault, Injector, Rule};
+use serde_json::{json, Value};
+
+mod common;
+use common::{aws, send, start_server};
+
+#[test]
+fn parse_rule() {
+    let rule: Rule = "action=PutLogEvents,group=app,nth=2,error=ThrottlingException"
+        .parse()
+        .unwrap();
+
+    assert_eq!(rule.action, Some("PutLogEvents".into()));
+    assert_eq!(rule.group, Some("app".into()));
+    assert_eq!(rule.nth, Some(2));
+    assert_eq!(
+        rule.fault,
+        Fault::Error {
+            code: "ThrottlingException".into(),
+            message: None,
+            status: None,
+        }
+    );
+
+    assert!("action=PutLogEvents".parse::<Rule>().is_err());
+    assert!("latency=soon".parse::<Rule>().is_err());
+    assert!("probability=1.5,drop".parse::<Rule>().is_err());
+    assert!("probability=-0.1,drop".parse::<Rule>().is_err());
+    assert!("probability=NaN,drop".parse::<Rule>().is_err());
+
+    let rule = json!({ "probability": 2.0, "fault": { "type": "drop" } });
+    assert!(serde_json::from_value::<Rule>(rule).is_err());
+}
+
+#[test]
+fn nth_matching_call() {
+    let mut injector = Injector::default();
+    injector.add(
+        "action=PutLogEvents,stream=web-1,nth=2,drop"
+            .parse()
+            .unwrap(),
+    );
+
+    let web_1 = br#"{"logGroupName":"app","logStreamName":"web-1"}"#;
+    let web_2 = br#"{"logGroupName":"app","logStreamName":"web-2"}"#;
+
+    assert_eq!(injector.check("Logs_20140328.PutLogEvents", web_1), None);
+    assert_eq!(injector.check("Logs_20140328.PutLogEvents", web_2), None);
+    assert_eq!(injector.check("Logs_20140328.GetLogEvents", web_1), None);
+    assert_eq!(
+        injector.check("Logs_20140328.PutLogEvents", web_1),
+        Some(Fault::Drop)
+    );
+    assert_eq!(injector.check("Logs_20140328.PutLogEvents", web_1), None);
+}
+
+#[test]
+fn probability() {
+    let mut injector = Injector::default();
+    injector.add("probability=0,truncate".parse().unwrap());
+    injector.add("probability=1,drop".parse().unwrap());
+
+    for _ in 0..100 {
+        assert_eq!(
+            injector.check("Logs_20140328.CreateLogGroup", b"{}"),
+            Some(Fault::Drop)
+        );
+    }
+}
+
+#[test]
+fn injected_error() {
+    let (server, addr) = start_server();
+
+    let rule = json!({
+        "action": "CreateLogGroup",
+        "fault": { "type": "error", "code": "ThrottlingException", "message": "Rate exceeded" }
+    });
+    let (status, _) = send(
+        addr,
+        Method::POST,
+        "/_mock/faults/rules",
+        None,
+        &rule.to_string(),
+    );
+    assert_eq!(status, 200);
+
+    let (status, body) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "ThrottlingException");
+    assert_eq!(body["message"], "Rate exceeded");
+    assert!(!server.group("app").exists());
+
+    send(addr, Method::DELETE, "/_mock/faults/rules", None, "");
+    let (status, _) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn truncated_body() {
+    let (server, addr) = start_server();
+    server.add_fault("action=DescribeLogStreams,truncate".parse().unwrap());
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    let (status, body) = aws(addr, "DescribeLogStreams", json!({ "logGroupName": "app" }));
+
+    // The action ran, but the client cannot parse what it got back.
+    assert_eq!(status, 200);
+    assert_eq!(body, Value::Null);
+}
+
+#[test]
+fn dropped_connection() {
+    let (server, addr) = start_server();
+    server.add_fault("action=DescribeLogGroups,nth=1,drop".parse().unwrap());
+
+    let req = Request::post(format!("http://{}/", addr))
+        .header("X-Amz-Target", "Logs_20140328.DescribeLogGroups")
+        .body(Body::from("{}"))
+        .unwrap();
+    let fut = Client::new()
+        .request(req)
+        .and_then(|res| res.into_body().concat2());
+    let res = tokio::runtime::current_thread::Runtime::new()
+        .unwrap()
+        .block_on(fut);
+    assert!(res.is_err());
+
+    // The server survives the aborted connection.
+    let (status, _) = aws(addr, "DescribeLogGroups", json!({}));
+    assert_eq!(status, 200);
+}
@@ -0,0 +1,112 @@
+use serde_json::json;
+
+mod common;
+use common::{aws, now, put, start_server};
+
+#[test]
+fn discover_fields() {
+    let (_, addr) = start_server();
+    let now = now();
+
+    put(
+        addr,
+        &[
+            r#"{"level":"info","http":{"status":200,"path":"/"}}"#,
+            r#"{"level":"error","http":{"status":500}}"#,
+            r#"{"level":"info","tags":["a","b"]}"#,
+            "plain text",
+        ],
+        now,
+    );
+
+    let (status, body) = aws(addr, "GetLogGroupFields", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 200);
+
+    let fields: Vec<(String, i64)> = body["logGroupFields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            (
+                f["name"].as_str().unwrap().to_string(),
+                f["percent"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+
+    let expected = vec![
+        ("@timestamp", 100),
+        ("@message", 100),
+        ("@logStream", 100),
+        ("level", 75),
+        ("http.status", 50),
+        ("http.path", 25),
+        ("tags.0", 25),
+        ("tags.1", 25),
+    ];
+    let expected: Vec<(String, i64)> = expected.into_iter().map(|(n, p)| (n.into(), p)).collect();
+    assert_eq!(fields, expected);
+
+    // Only the 8 minutes either side of `time` are searched.
+    let long_ago = json!({ "logGroupName": "app", "time": now / 1000 - 3600 });
+    let (_, body) = aws(addr, "GetLogGroupFields", long_ago);
+    assert_eq!(body["logGroupFields"], json!([]));
+
+    for minutes in &[-10, 10] {
+        let time = now / 1000 + minutes * 60;
+        let off = json!({ "logGroupName": "app", "time": time });
+        let (_, body) = aws(addr, "GetLogGroupFields", off);
+        assert_eq!(body["logGroupFields"], json!([]));
+    }
+
+    let near = json!({ "logGroupName": "app", "time": now / 1000 + 7 * 60 });
+    let (_, body) = aws(addr, "GetLogGroupFields", near);
+    assert_eq!(body["logGroupFields"].as_array().unwrap().len(), 8);
+
+    for time in &[std::i64::MAX, std::i64::MIN] {
+        let extreme = json!({ "logGroupName": "app", "time": time });
+        let (status, body) = aws(addr, "GetLogGroupFields", extreme);
+        assert_eq!(status, 200);
+        assert_eq!(body["logGroupFields"], json!([]));
+    }
+
+    let (status, body) = aws(addr, "GetLogGroupFields", json!({ "logGroupName": "nope" }));
+    assert_eq!(status, 404);
+    assert_eq!(body["__type"], "ResourceNotFoundException");
+}
+
+#[test]
+fn get_record() {
+    let (_, addr) = start_server();
+    let now = now();
+    put(addr, &[r#"{"level":"error","http":{"status":500}}"#], now);
+
+    let (_, body) = aws(addr, "FilterLogEvents", json!({ "logGroupName": "app" }));
+    let pointer = format!("app:{}", body["events"][0]["eventId"].as_str().unwrap());
+
+    let (status, body) = aws(addr, "GetLogRecord", json!({ "logRecordPointer": pointer }));
+    assert_eq!(status, 200);
+
+    let record = &body["logRecord"];
+    assert_eq!(record["level"], "error");
+    assert_eq!(record["http.status"], "500");
+    assert_eq!(record["@logStream"], "web");
+    assert_eq!(record["@log"], "123456789012:app");
+    assert_eq!(record["@timestamp"], now.to_string());
+    assert_eq!(
+        record["@message"],
+        r#"{"level":"error","http":{"status":500}}"#
+    );
+
+    let missing = format!("app:{}/99/web", now);
+    let (status, _) = aws(addr, "GetLogRecord", json!({ "logRecordPointer": missing }));
+    assert_eq!(status, 404);
+
+    let (status, body) = aws(
+        addr,
+        "GetLogRecord",
+        json!({ "logRecordPointer": "garbage" }),
+    );
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "InvalidParameterException");
+}
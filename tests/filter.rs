@@ -0,0 +1,211 @@
+use mockwatchlogs::filter::Pattern;
+use mockwatchlogs::fixture::Fixture;
+use mockwatchlogs::MockServer;
+use rusoto_logs::{CloudWatchLogs, FilterLogEventsRequest};
+
+mod common;
+use common::{client, serve};
+
+fn matches(pattern: &str, message: &str) -> bool {
+    pattern.parse::<Pattern>().unwrap().matches(message)
+}
+
+#[test]
+fn terms() {
+    assert!(matches("", "anything"));
+    assert!(matches("ERROR", "ERROR disk full"));
+    assert!(!matches("ERROR", "error disk full"));
+    assert!(matches(r#"ERROR "disk full""#, "ERROR: disk full"));
+    assert!(!matches("ERROR -retrying", "ERROR retrying"));
+    assert!(matches("?ERROR ?WARN", "WARN low memory"));
+    assert!(!matches("?ERROR ?WARN", "INFO started"));
+}
+
+#[test]
+fn json() {
+    let message = r#"{"level":"error","latency":250,"user":{"id":"u-1"},"tags":["a"]}"#;
+
+    assert!(matches(r#"{ $.level = "error" }"#, message));
+    assert!(matches(
+        r#"{ $.level = "err*" && $.latency > 200 }"#,
+        message
+    ));
+    assert!(!matches(
+        r#"{ $.level = "error" && $.latency < 200 }"#,
+        message
+    ));
+    assert!(matches(
+        r#"{ ($.latency >= 1000) || $.user.id = "u-1" }"#,
+        message
+    ));
+    assert!(matches(r#"{ $.tags[0] = "a" }"#, message));
+    assert!(matches(r#"{ $.missing NOT EXISTS }"#, message));
+    assert!(!matches(r#"{ $.level = "error" }"#, "level=error"));
+
+    assert!("{ $.level = }".parse::<Pattern>().is_err());
+    assert!("{ level = 1 }".parse::<Pattern>().is_err());
+}
+
+#[test]
+fn delimited() {
+    let line = r#"127.0.0.1 frank [10/Oct/2000:13:55:36 -0700] "GET /a.gif" 404 2326"#;
+
+    assert!(matches(
+        "[ip, user, time, request, status = 404, size]",
+        line
+    ));
+    assert!(matches("[ip, ..., status = 4*, size > 1000]", line));
+    assert!(!matches("[ip, ..., status = 2*, size]", line));
+    assert!(!matches("[ip, user]", line));
+    assert!(matches("[..., status = 404, ...]", line));
+    assert!(matches("[ip, ..., ..., size, ...]", line));
+    assert!(!matches("[..., ip = 127*, ..., ip = 127*, ...]", line));
+
+    // Each `...` used to try every split, which never finished on this.
+    let many = vec!["..."; 30].join(", ");
+    let pattern = format!("[{}, missing = 1]", many);
+    assert!(!matches(&pattern, &vec!["x"; 60].join(" ")));
+}
+
+#[test]
+fn pattern_limits() {
+    let long = format!("\"{}\"", "a".repeat(1023));
+    assert!(long.parse::<Pattern>().is_err());
+    assert!(format!("{}\"", &long[..1023]).parse::<Pattern>().is_ok());
+
+    let nested = |depth| format!("{{ {}$.a = 1{} }}", "(".repeat(depth), ")".repeat(depth));
+    assert!(nested(32).parse::<Pattern>().is_ok());
+    assert!(nested(33).parse::<Pattern>().is_err());
+    assert!(nested(400).parse::<Pattern>().is_err());
+}
+
+#[test]
+fn filter_log_events() {
+    let server = MockServer::new();
+    let now = server.clock().now();
+    let fixture = format!(
+        r#"{{ "groups": [{{ "name": "app", "streams": [
+            {{ "name": "web-1", "events": [
+                {{ "timestamp": {0}, "message": "ERROR one" }},
+                {{ "timestamp": {2}, "message": "INFO two" }}
+            ] }},
+            {{ "name": "web-2", "events": [
+                {{ "timestamp": {1}, "message": "ERROR three" }},
+                {{ "timestamp": {2}, "message": "ERROR four" }}
+            ] }}
+        ] }}] }}"#,
+        now - 3000,
+        now - 2000,
+        now - 1000
+    );
+    server
+        .load_fixture(&Fixture::from_slice(fixture.as_bytes()).unwrap())
+        .unwrap();
+    let client = client(serve(&server));
+
+    let filter = |next_token: Option<String>| {
+        let req = FilterLogEventsRequest {
+            log_group_name: "app".into(),
+            filter_pattern: Some("ERROR".into()),
+            limit: Some(2),
+            next_token,
+            ..Default::default()
+        };
+        client.filter_log_events(req).sync().unwrap()
+    };
+
+    let page = filter(None);
+    let events = page.events.unwrap();
+    let messages = events
+        .iter()
+        .map(|e| e.message.clone().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, vec!["ERROR one", "ERROR three"]);
+    assert_eq!(events[1].log_stream_name, Some("web-2".into()));
+    assert_eq!(page.searched_log_streams.unwrap().len(), 2);
+
+    let page = filter(page.next_token);
+    let events = page.events.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].message, Some("ERROR four".into()));
+    assert_eq!(page.next_token, None);
+
+    let req = FilterLogEventsRequest {
+        log_group_name: "app".into(),
+        log_stream_names: Some(vec!["web-1".into()]),
+        start_time: Some(now - 1000),
+        ..Default::default()
+    };
+    let events = client
+        .filter_log_events(req)
+        .sync()
+        .unwrap()
+        .events
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].message, Some("INFO two".into()));
+}
+
+#[test]
+fn filter_log_events_pages() {
+    let server = MockServer::new();
+    let now = server.clock().now();
+    let stream = |name: &str| {
+        format!(
+            r#"{{ "name": "{}", "events": [
+                {{ "timestamp": {1}, "message": "{0} a" }},
+                {{ "timestamp": {2}, "message": "{0} b" }},
+                {{ "timestamp": {2}, "message": "{0} c" }}
+            ] }}"#,
+            name,
+            now - 2000,
+            now - 1000
+        )
+    };
+    let fixture = format!(
+        r#"{{ "groups": [{{ "name": "app", "streams": [{}, {}, {}] }}] }}"#,
+        stream("web-1"),
+        stream("web-2"),
+        stream("web-3")
+    );
+    server
+        .load_fixture(&Fixture::from_slice(fixture.as_bytes()).unwrap())
+        .unwrap();
+    let client = client(serve(&server));
+
+    let filter = |start_time: Option<i64>, limit: Option<i64>| {
+        let mut messages = Vec::new();
+        let mut next_token = None;
+
+        loop {
+            let req = FilterLogEventsRequest {
+                log_group_name: "app".into(),
+                start_time,
+                limit,
+                next_token,
+                ..Default::default()
+            };
+            let page = client.filter_log_events(req).sync().unwrap();
+
+            messages.extend(page.events.unwrap().into_iter().map(|e| e.message.unwrap()));
+            next_token = page.next_token;
+
+            if next_token.is_none() {
+                return messages;
+            }
+        }
+    };
+
+    let all = filter(None, None);
+    assert_eq!(
+        all,
+        vec![
+            "web-1 a", "web-2 a", "web-3 a", "web-1 b", "web-2 b", "web-3 b", "web-1 c", "web-2 c",
+            "web-3 c",
+        ]
+    );
+
+    assert_eq!(filter(None, Some(1)), all);
+    assert_eq!(filter(None, Some(2)), all);
+    assert_eq!(filter(Some(now - 1000), Some(2)), &all[3..]);
+}
@@ -0,0 +1,122 @@
+use mockwatchlogs::fixture::{Fixture, FixtureError};
+use mockwatchlogs::limits::Limits;
+use mockwatchlogs::streams::StorageError;
+use mockwatchlogs::testing::Events;
+use mockwatchlogs::MockServer;
+
+const FIXTURE: &str = r#"{
+    "groups": [{
+        "name": "app",
+        "retentionInDays": 14,
+        "tags": { "team": "core" },
+        "streams": [{
+            "name": "web-1",
+            "events": [
+                { "timestamp": "-4m30s", "message": "started" },
+                { "timestamp": "-5m", "message": "starting" },
+                { "timestamp": 1000, "message": "ancient" }
+            ]
+        }]
+    }]
+}"#;
+
+#[test]
+fn load_fixture() {
+    let server = MockServer::new();
+    let fixture = Fixture::from_slice(FIXTURE.as_bytes()).unwrap();
+
+    let before = chrono::Utc::now().timestamp_millis();
+    server.load_fixture(&fixture).unwrap();
+
+    let events = server.group("app").stream("web-1").events();
+    let messages = events
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, vec!["ancient", "starting", "started"]);
+
+    assert_eq!(events[0].timestamp, 1000);
+    let five_minutes = 5 * 60 * 1000;
+    assert!(events[1].timestamp >= before - five_minutes);
+    assert!(events[1].timestamp < before - five_minutes + 60 * 1000);
+
+    let group = server.context().group("app").unwrap();
+    let group = group.lock().unwrap();
+    assert_eq!(group.retention_in_days, Some(14));
+    assert_eq!(group.tags["team"], "core");
+}
+
+#[test]
+fn invalid_timestamp() {
+    let fixture = r#"{ "groups": [{ "name": "app", "streams": [{
+        "name": "web-1",
+        "events": [{ "timestamp": "five minutes ago", "message": "nope" }]
+    }]}]}"#;
+
+    let server = MockServer::new();
+    let fixture = Fixture::from_slice(fixture.as_bytes()).unwrap();
+
+    match server.load_fixture(&fixture) {
+        Err(FixtureError::InvalidTimestamp(t)) => assert_eq!(t, "five minutes ago"),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn overflowing_timestamp() {
+    let fixture = r#"{ "groups": [{ "name": "app", "streams": [{
+        "name": "web-1",
+        "events": [{ "timestamp": "-9999999999999999w", "message": "nope" }]
+    }]}]}"#;
+
+    let server = MockServer::new();
+    let fixture = Fixture::from_slice(fixture.as_bytes()).unwrap();
+
+    match server.load_fixture(&fixture) {
+        Err(FixtureError::InvalidTimestamp(t)) => assert_eq!(t, "-9999999999999999w"),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn load_yaml_fixture() {
+    let fixture = r#"
+groups:
+  - name: app
+    retentionInDays: 14
+    streams:
+      - name: web-1
+        events:
+          - timestamp: -5m
+            message: starting
+          - timestamp: 1000
+            message: ancient
+"#;
+
+    let server = MockServer::new();
+    let fixture = Fixture::from_yaml(fixture.as_bytes()).unwrap();
+    server.load_fixture(&fixture).unwrap();
+
+    let events = server.group("app").stream("web-1").events();
+    let messages = events
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, vec!["ancient", "starting"]);
+}
+
+#[test]
+fn fixture_over_limits() {
+    let server = MockServer::new();
+    server.set_limits(Limits {
+        max_events_per_stream: Some(2),
+        ..Default::default()
+    });
+
+    let fixture = Fixture::from_slice(FIXTURE.as_bytes()).unwrap();
+
+    match server.load_fixture(&fixture) {
+        Err(FixtureError::Storage(StorageError::LimitExceeded(_))) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,129 @@
+use mockwatchlogs::limits::{event_size, Limits, Policy};
+use mockwatchlogs::streams::{Context, Group, StorageError, Stream};
+use mockwatchlogs::types::InputLogEvent;
+
+#[test]
+fn drop_oldest_per_stream() {
+    let context = context(Limits {
+        max_events_per_stream: Some(3),
+        policy: Policy::DropOldest,
+        ..Default::default()
+    });
+
+    context.store_events("app", "web-1", events(0..5)).unwrap();
+
+    assert_eq!(messages(&context, "web-1"), vec!["2", "3", "4"]);
+    assert_eq!(context.usage().events, 3);
+}
+
+#[test]
+fn reject_per_stream() {
+    let context = context(Limits {
+        max_events_per_stream: Some(3),
+        policy: Policy::Reject,
+        ..Default::default()
+    });
+
+    context.store_events("app", "web-1", events(0..2)).unwrap();
+    match context.store_events("app", "web-1", events(2..4)) {
+        Err(StorageError::LimitExceeded(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    assert_eq!(messages(&context, "web-1"), vec!["0", "1"]);
+}
+
+#[test]
+fn drop_oldest_bytes_across_streams() {
+    let size = event_size("0");
+    let context = context(Limits {
+        max_bytes: Some(size * 3),
+        policy: Policy::DropOldest,
+        ..Default::default()
+    });
+
+    context.store_events("app", "web-1", events(0..2)).unwrap();
+    context.store_events("app", "web-2", events(5..7)).unwrap();
+
+    assert_eq!(messages(&context, "web-1"), vec!["1"]);
+    assert_eq!(messages(&context, "web-2"), vec!["5", "6"]);
+    assert_eq!(context.usage().stored_bytes, size * 3);
+}
+
+#[test]
+fn spill_evicted_events() {
+    let dir = std::env::temp_dir().join(format!("mockwatchlogs-{}-spill", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let context = context(Limits {
+        max_events_per_stream: Some(1),
+        policy: Policy::Spill(dir.clone()),
+        ..Default::default()
+    });
+
+    context.store_events("app", "web-1", events(0..2)).unwrap();
+
+    let spilled = std::fs::read_to_string(dir.join("spill.jsonl")).unwrap();
+    let spilled: serde_json::Value = serde_json::from_str(spilled.trim()).unwrap();
+    assert_eq!(spilled["logStreamName"], "web-1");
+    assert_eq!(spilled["message"], "0");
+    assert_eq!(messages(&context, "web-1"), vec!["1"]);
+}
+
+#[test]
+fn max_groups() {
+    let context = context(Limits {
+        max_groups: Some(1),
+        ..Default::default()
+    });
+
+    let group = Group {
+        name: "db".into(),
+        ..Default::default()
+    };
+
+    match context.create_group(group, context.limits().max_groups) {
+        Err(StorageError::LimitExceeded(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+/// A context with limits and an `app` group holding two streams.
+fn context(limits: Limits) -> Context {
+    let context = Context::default();
+    context.set_limits(limits);
+
+    let group = Group {
+        name: "app".into(),
+        ..Default::default()
+    };
+    context.create_group(group, None).unwrap();
+
+    for name in &["web-1", "web-2"] {
+        let stream = Stream {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        context.create_stream("app", stream).unwrap();
+    }
+
+    context
+}
+
+fn events(range: std::ops::Range<i64>) -> Vec<InputLogEvent> {
+    range
+        .map(|i| InputLogEvent {
+            message: i.to_string(),
+            timestamp: i,
+        })
+        .collect()
+}
+
+fn messages(context: &Context, stream: &str) -> Vec<String> {
+    context
+        .events("app", stream, None, None)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.message)
+        .collect()
+}
@@ -0,0 +1,186 @@
+use futures::Stream;
+use hyper::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+mod common;
+use common::{aws, now, raw, start_server};
+
+#[test]
+fn tail_matching_events() {
+    let (server, addr) = start_server();
+    server.set_live_tail_timeout(Duration::from_secs(1));
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    for stream in &["web", "worker"] {
+        let stream = json!({ "logGroupName": "app", "logStreamName": stream });
+        aws(addr, "CreateLogStream", stream);
+    }
+
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+
+        for stream in &["web", "worker"] {
+            let events = json!([
+                { "timestamp": now(), "message": "ERROR disk full" },
+                { "timestamp": now(), "message": "INFO all good" },
+            ]);
+            let put =
+                json!({ "logGroupName": "app", "logStreamName": stream, "logEvents": events });
+            aws(addr, "PutLogEvents", put);
+        }
+    });
+
+    let start = json!({
+        "logGroupIdentifiers": ["arn:aws:logs:us-east-1:123456789012:log-group:app"],
+        "logStreamNames": ["web"],
+        "logEventFilterPattern": "ERROR",
+    });
+    let (status, body) = raw(addr, "StartLiveTail", start);
+    assert_eq!(status, 200);
+
+    let frames = decode(&body);
+    assert_eq!(frames.len(), 3);
+
+    let (headers, payload) = &frames[0];
+    assert_eq!(headers[":event-type"], "sessionStart");
+    assert_eq!(payload["logStreamNames"], json!(["web"]));
+    assert!(payload["sessionId"].is_string());
+
+    let (headers, payload) = &frames[1];
+    assert_eq!(headers[":event-type"], "sessionUpdate");
+    let results = payload["sessionResults"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["message"], "ERROR disk full");
+    assert_eq!(results[0]["logStreamName"], "web");
+    assert_eq!(
+        results[0]["logGroupIdentifier"],
+        "arn:aws:logs:us-east-1:123456789012:log-group:app"
+    );
+
+    let (headers, _) = &frames[2];
+    assert_eq!(headers[":message-type"], "exception");
+    assert_eq!(headers[":exception-type"], "SessionTimeoutException");
+}
+
+#[test]
+fn advancing_the_clock_ends_sessions() {
+    let (server, addr) = start_server();
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+
+    let clock = server.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        clock.clock().advance(4 * 60 * 60 * 1000);
+    });
+
+    let start = json!({ "logGroupIdentifiers": ["app"] });
+    let (status, body) = raw(addr, "StartLiveTail", start);
+    assert_eq!(status, 200);
+
+    let frames = decode(&body);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].0[":event-type"], "sessionStart");
+    assert_eq!(frames[1].0[":exception-type"], "SessionTimeoutException");
+}
+
+#[test]
+fn reject_invalid_sessions() {
+    let (_, addr) = start_server();
+
+    let (status, body) = aws(
+        addr,
+        "StartLiveTail",
+        json!({ "logGroupIdentifiers": ["nope"] }),
+    );
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "ResourceNotFoundException");
+
+    let (status, body) = aws(addr, "StartLiveTail", json!({ "logGroupIdentifiers": [] }));
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "InvalidParameterException");
+}
+
+#[test]
+fn sse_feed() {
+    let (_, addr) = start_server();
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    for stream in &["web", "worker"] {
+        let stream = json!({ "logGroupName": "app", "logStreamName": stream });
+        aws(addr, "CreateLogStream", stream);
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let uri = format!(
+        "http://{}/_mock/tail?group=app&stream=web&pattern=%22disk+full%22",
+        addr
+    );
+    let res = rt
+        .block_on(Client::new().get(uri.parse().unwrap()))
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "text/event-stream");
+
+    let mut chunks = res.into_body().wait();
+    let opened = chunks.next().unwrap().unwrap();
+    assert!(opened.starts_with(b":"));
+
+    for (stream, message) in &[("worker", "disk full"), ("web", "ok"), ("web", "disk full")] {
+        let events = json!([{ "timestamp": now(), "message": message }]);
+        let put = json!({ "logGroupName": "app", "logStreamName": stream, "logEvents": events });
+        let (_, body) = aws(addr, "PutLogEvents", put);
+        assert!(body.get("rejectedLogEventsInfo").is_none());
+    }
+
+    let mut text = String::new();
+    while !text.ends_with("\n\n") {
+        let chunk = chunks.next().unwrap().unwrap();
+        text.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+
+    let lines: Vec<&str> = text.lines().filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines.len(), 1);
+    let event: Value = serde_json::from_str(&lines[0]["data: ".len()..]).unwrap();
+    assert_eq!(event["logGroupName"], "app");
+    assert_eq!(event["logStreamName"], "web");
+    assert_eq!(event["message"], "disk full");
+}
+
+/// Split an event stream body into its messages' string headers and JSON
+/// payloads.
+fn decode(mut body: &[u8]) -> Vec<(HashMap<String, String>, Value)> {
+    let mut frames = Vec::new();
+
+    while !body.is_empty() {
+        let total = u32_at(body, 0) as usize;
+        let headers_len = u32_at(body, 4) as usize;
+        let (message, rest) = body.split_at(total);
+
+        let mut headers = HashMap::new();
+        let mut raw = &message[12..12 + headers_len];
+        while !raw.is_empty() {
+            let name_len = raw[0] as usize;
+            let name = String::from_utf8(raw[1..1 + name_len].to_vec()).unwrap();
+            assert_eq!(raw[1 + name_len], 7);
+            let at = 2 + name_len;
+            let value_len = ((raw[at] as usize) << 8) | raw[at + 1] as usize;
+            let value = String::from_utf8(raw[at + 2..at + 2 + value_len].to_vec()).unwrap();
+            headers.insert(name, value);
+            raw = &raw[at + 2 + value_len..];
+        }
+
+        let payload = serde_json::from_slice(&message[12 + headers_len..total - 4]).unwrap();
+        frames.push((headers, payload));
+        body = rest;
+    }
+
+    frames
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[at..at + 4]);
+    u32::from_be_bytes(buf)
+}
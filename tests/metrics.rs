@@ -0,0 +1,84 @@
+use hyper::Method;
+use mockwatchlogs::MockServer;
+use serde_json::json;
+
+mod common;
+use common::{aws, request, serve};
+
+#[test]
+fn action_metrics() {
+    let server = MockServer::new();
+    let addr = serve(&server);
+
+    let now = server.clock().now();
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    aws(
+        addr,
+        "CreateLogStream",
+        json!({ "logGroupName": "app", "logStreamName": "web-1" }),
+    );
+    aws(
+        addr,
+        "PutLogEvents",
+        json!({
+            "logGroupName": "app",
+            "logStreamName": "web-1",
+            "logEvents": [
+                { "timestamp": now, "message": "hello" },
+                { "timestamp": now, "message": "world" }
+            ]
+        }),
+    );
+
+    let (status, metrics) = request(addr, Method::GET, "/metrics", None, "");
+    assert_eq!(status, 200);
+    let metrics = String::from_utf8(metrics).unwrap();
+
+    let lines = metrics.lines().collect::<Vec<_>>();
+    let expected = [
+        r#"mockwatchlogs_requests_total{action="CreateLogGroup"} 2"#,
+        r#"mockwatchlogs_errors_total{action="CreateLogGroup",type="ResourceAlreadyExistsException"} 1"#,
+        r#"mockwatchlogs_request_duration_seconds_count{action="PutLogEvents"} 1"#,
+        r#"mockwatchlogs_ingested_events_total{group="app"} 2"#,
+        r#"mockwatchlogs_ingested_bytes_total{group="app"} 62"#,
+        r#"mockwatchlogs_stored_events{group="app"} 2"#,
+        r#"mockwatchlogs_stored_bytes{group="app"} 62"#,
+    ];
+
+    for line in &expected {
+        assert!(lines.contains(line), "missing {:?} in\n{}", line, metrics);
+    }
+
+    // At least the connection serving this request is open.
+    let connections = lines
+        .iter()
+        .find(|l| l.starts_with("mockwatchlogs_active_connections "))
+        .and_then(|l| l.rsplit(' ').next())
+        .and_then(|n| n.parse::<usize>().ok());
+    assert!(connections.unwrap_or(0) >= 1);
+}
+
+#[test]
+fn unknown_actions_and_refusals() {
+    let server = MockServer::new();
+    let addr = serve(&server);
+
+    aws(addr, "NoSuchAction", json!({}));
+    aws(addr, "AnotherMadeUpAction", json!({}));
+
+    server.set_ready(false);
+    assert_eq!(aws(addr, "DescribeLogGroups", json!({})).0, 503);
+    server.set_ready(true);
+
+    let (status, _) = request(addr, Method::POST, "/metrics", None, "");
+    assert_ne!(status, 200);
+
+    let (_, metrics) = request(addr, Method::GET, "/metrics", None, "");
+    let metrics = String::from_utf8(metrics).unwrap();
+    let lines = metrics.lines().collect::<Vec<_>>();
+
+    assert!(lines.contains(&r#"mockwatchlogs_requests_total{action="unknown"} 2"#));
+    assert!(lines.contains(&"mockwatchlogs_refused_starting_total 1"));
+    assert!(!metrics.contains("NoSuchAction"));
+}
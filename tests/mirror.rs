@@ -0,0 +1,89 @@
+use mockwatchlogs::mirror::Mirror;
+use serde_json::json;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::{aws, start_server};
+
+#[test]
+fn echo_accepted_events() {
+    let (server, addr) = start_server();
+    // Just after the events, so that they are not rejected as too old.
+    server.clock().freeze();
+    server.clock().set(1551675970000);
+    let out = Buffer::default();
+
+    let mut mirror = Mirror::new(out.clone());
+    mirror.groups = vec!["app-*".to_string()];
+    server.set_mirror(Some(mirror));
+
+    for group in &["app-web", "other"] {
+        aws(addr, "CreateLogGroup", json!({ "logGroupName": group }));
+        let stream = json!({ "logGroupName": group, "logStreamName": "main" });
+        aws(addr, "CreateLogStream", stream);
+
+        let events = json!([
+            { "timestamp": 1551675967089i64, "message": "started\n" },
+            { "timestamp": 1551675968000i64, "message": "GET /health 200" },
+        ]);
+        let put = json!({ "logGroupName": group, "logStreamName": "main", "logEvents": events });
+        aws(addr, "PutLogEvents", put);
+    }
+
+    assert_eq!(
+        out.text(),
+        "app-web/main | 2019-03-04T05:06:07.089Z started\n\
+         app-web/main | 2019-03-04T05:06:08.000Z GET /health 200\n"
+    );
+}
+
+#[test]
+fn color_prefixes() {
+    let (server, addr) = start_server();
+    server.clock().freeze();
+    server.clock().set(1000);
+    let out = Buffer::default();
+
+    let mut mirror = Mirror::new(out.clone());
+    mirror.color = true;
+    server.set_mirror(Some(mirror));
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    aws(
+        addr,
+        "CreateLogStream",
+        json!({ "logGroupName": "app", "logStreamName": "web" }),
+    );
+    let events = json!([{ "timestamp": 0, "message": "hello" }]);
+    let put = json!({ "logGroupName": "app", "logStreamName": "web", "logEvents": events });
+    aws(addr, "PutLogEvents", put);
+
+    let text = out.text();
+    assert!(text.starts_with("\x1b["), "{:?}", text);
+    assert!(
+        text.ends_with("app/web\x1b[0m | 1970-01-01T00:00:00.000Z hello\n"),
+        "{:?}",
+        text
+    );
+}
+
+/// A `Write` the test can read back.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use mockwatchlogs::testing::Events;
+use mockwatchlogs::MockServer;
+use rusoto_logs::{
+    CloudWatchLogs, CreateLogGroupRequest, CreateLogStreamRequest, InputLogEvent,
+    PutLogEventsRequest,
+};
+use std::path::PathBuf;
+
+mod common;
+use common::{client, serve};
+
+#[test]
+fn recover_after_restart() {
+    let dir = data_dir("recover_after_restart");
+
+    let server = MockServer::new();
+    assert!(!server.persist(&dir).unwrap());
+    let addr = serve(&server);
+    let client = client(addr);
+
+    let req = CreateLogGroupRequest {
+        log_group_name: "app".into(),
+        ..Default::default()
+    };
+    client.create_log_group(req).sync().unwrap();
+
+    let req = CreateLogStreamRequest {
+        log_group_name: "app".into(),
+        log_stream_name: "web-1".into(),
+    };
+    client.create_log_stream(req).sync().unwrap();
+
+    let req = PutLogEventsRequest {
+        log_events: vec![InputLogEvent {
+            message: "hello world".into(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }],
+        log_group_name: "app".into(),
+        log_stream_name: "web-1".into(),
+        sequence_token: None,
+    };
+    client.put_log_events(req).sync().unwrap();
+
+    let restarted = MockServer::new();
+    assert!(restarted.persist(&dir).unwrap());
+    assert_eq!(
+        restarted.group("app").stream("web-1").messages(),
+        vec!["hello world"]
+    );
+
+    // Recovery compacts the log, so a second restart sees the same state.
+    let again = MockServer::new();
+    assert!(again.persist(&dir).unwrap());
+    assert_eq!(
+        again.group("app").stream("web-1").messages(),
+        vec!["hello world"]
+    );
+}
+
+#[test]
+fn ignore_torn_write() {
+    let dir = data_dir("ignore_torn_write");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("wal.jsonl"),
+        concat!(
+            r#"{"target":"Logs_20140328.CreateLogGroup","body":"{\"logGroupName\":\"app\"}"}"#,
+            "\n",
+            r#"{"target":"Logs_20140328.CreateLog"#,
+        ),
+    )
+    .unwrap();
+
+    let server = MockServer::new();
+    assert!(server.persist(&dir).unwrap());
+    assert!(server.group("app").exists());
+}
+
+fn data_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mockwatchlogs-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
@@ -0,0 +1,248 @@
+use futures::Stream;
+use hyper::{Client, Method};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+mod common;
+use common::{aws, call, now, put, raw, start_server};
+
+const EMAIL: &str = "arn:aws:dataprotection::aws:data-identifier/EmailAddress";
+const CARD: &str = "arn:aws:dataprotection::aws:data-identifier/CreditCardNumber";
+const SECRET: &str = "arn:aws:dataprotection::aws:data-identifier/AwsSecretKey";
+
+fn policy(identifiers: &[&str], custom: Value) -> String {
+    json!({
+        "Name": "data-protection-policy",
+        "Version": "2021-06-01",
+        "Configuration": { "CustomDataIdentifier": custom },
+        "Statement": [
+            {
+                "Sid": "audit",
+                "DataIdentifier": identifiers,
+                "Operation": { "Audit": { "FindingsDestination": {} } },
+            },
+            {
+                "Sid": "redact",
+                "DataIdentifier": identifiers,
+                "Operation": { "Deidentify": { "MaskConfig": {} } },
+            },
+        ],
+    })
+    .to_string()
+}
+
+#[test]
+fn put_get_and_delete_policies() {
+    let (_, addr) = start_server();
+    put(addr, &["hello"], now());
+
+    let get = json!({ "logGroupIdentifier": "app" });
+    let (status, body) = aws(addr, "GetDataProtectionPolicy", get.clone());
+    assert_eq!(status, 200);
+    assert!(body["policyDocument"].is_null());
+
+    let document = policy(&[EMAIL], json!([]));
+    let arn = "arn:aws:logs:us-east-1:123456789012:log-group:app";
+    let put = json!({ "logGroupIdentifier": arn, "policyDocument": document });
+    let (status, body) = aws(addr, "PutDataProtectionPolicy", put);
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["logGroupIdentifier"], arn);
+
+    let (_, body) = aws(addr, "GetDataProtectionPolicy", get.clone());
+    assert_eq!(body["policyDocument"], document.as_str());
+    assert!(body["lastUpdatedTime"].is_i64());
+
+    let (_, body) = aws(addr, "DescribeLogGroups", json!({}));
+    assert_eq!(body["logGroups"][0]["dataProtectionStatus"], "ACTIVATED");
+
+    let invalid = vec![
+        "[]".to_string(),
+        policy(
+            &["arn:aws:dataprotection::aws:data-identifier/Unknown"],
+            json!([]),
+        ),
+        policy(&["EmployeeId"], json!([])),
+        policy(
+            &["EmployeeId"],
+            json!([{ "Name": "EmployeeId", "Regex": "(" }]),
+        ),
+        json!({ "Statement": [{
+            "DataIdentifier": [EMAIL],
+            "Operation": { "Deidentify": { "MaskConfig": {} } },
+        }] })
+        .to_string(),
+    ];
+    for document in invalid {
+        let put = json!({ "logGroupIdentifier": "app", "policyDocument": document });
+        let (status, body) = aws(addr, "PutDataProtectionPolicy", put);
+        assert_eq!(status, 400, "{}", document);
+        assert_eq!(body["__type"], "InvalidParameterException");
+    }
+
+    let missing = json!({ "logGroupIdentifier": "missing", "policyDocument": document });
+    assert_eq!(aws(addr, "PutDataProtectionPolicy", missing).0, 404);
+
+    assert_eq!(aws(addr, "DeleteDataProtectionPolicy", get.clone()).0, 200);
+    assert_eq!(aws(addr, "DeleteDataProtectionPolicy", get.clone()).0, 404);
+
+    let (_, body) = aws(addr, "GetDataProtectionPolicy", get);
+    assert!(body["policyDocument"].is_null());
+}
+
+#[test]
+fn mask_events_unless_unmasked() {
+    let (_, addr) = start_server();
+    put(
+        addr,
+        &[
+            "user bob@example.com signed in",
+            "paid with 4111 1111 1111 1111",
+            "aws_secret_access_key=wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            "employee EMP-123456 left",
+            "order 1234 1234 1234 1234 shipped",
+        ],
+        now(),
+    );
+
+    let custom = json!([{ "Name": "EmployeeId", "Regex": "EMP-\\d{6}" }]);
+    let document = policy(&[EMAIL, CARD, SECRET, "EmployeeId"], custom);
+    let put = json!({ "logGroupIdentifier": "app", "policyDocument": document });
+    assert_eq!(aws(addr, "PutDataProtectionPolicy", put).0, 200);
+
+    let masked = vec![
+        "user *************** signed in",
+        "paid with *******************",
+        "aws_secret_access_key=****************************************",
+        "employee ********** left",
+        // Not a valid card number.
+        "order 1234 1234 1234 1234 shipped",
+    ];
+
+    let get = json!({ "logGroupName": "app", "logStreamName": "web", "startFromHead": true });
+    let (_, body) = aws(addr, "GetLogEvents", get);
+    assert_eq!(messages(&body), masked);
+
+    let get = json!({
+        "logGroupName": "app",
+        "logStreamName": "web",
+        "startFromHead": true,
+        "unmask": true,
+    });
+    let (_, body) = aws(addr, "GetLogEvents", get);
+    assert_eq!(messages(&body)[0], "user bob@example.com signed in");
+
+    let (_, body) = aws(addr, "FilterLogEvents", json!({ "logGroupName": "app" }));
+    assert_eq!(messages(&body), masked);
+
+    // Masked data cannot be searched for, unless unmasked.
+    let filter = json!({ "logGroupName": "app", "filterPattern": "\"example.com\"" });
+    let (_, body) = aws(addr, "FilterLogEvents", filter);
+    assert!(messages(&body).is_empty());
+
+    let filter = json!({
+        "logGroupName": "app",
+        "filterPattern": "\"example.com\"",
+        "unmask": true,
+    });
+    let (_, body) = aws(addr, "FilterLogEvents", filter);
+    assert_eq!(messages(&body), vec!["user bob@example.com signed in"]);
+}
+
+#[test]
+fn account_policy_masks_selected_groups() {
+    let (_, addr) = start_server();
+    let message = "user bob@example.com signed in";
+    put(addr, &[message], now());
+
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "audit" }));
+    let stream = json!({ "logGroupName": "audit", "logStreamName": "web" });
+    aws(addr, "CreateLogStream", stream);
+    let events = json!([{ "timestamp": now(), "message": message }]);
+    let put = json!({ "logGroupName": "audit", "logStreamName": "web", "logEvents": events });
+    assert_eq!(aws(addr, "PutLogEvents", put).0, 200);
+
+    let put = json!({
+        "policyName": "guardrails",
+        "policyType": "DATA_PROTECTION_POLICY",
+        "policyDocument": policy(&[EMAIL], json!([])),
+        "selectionCriteria": "LogGroupName NOT IN [\"audit\"]",
+    });
+    let (status, body) = aws(addr, "PutAccountPolicy", put);
+    assert_eq!(status, 200, "{}", body);
+
+    // The group's own policy adds to the account's.
+    let custom = json!([{ "Name": "User", "Regex": "user" }]);
+    let own = json!({ "logGroupIdentifier": "app", "policyDocument": policy(&["User"], custom) });
+    assert_eq!(aws(addr, "PutDataProtectionPolicy", own).0, 200);
+
+    let get = json!({ "logGroupName": "app", "logStreamName": "web" });
+    let (_, body) = aws(addr, "GetLogEvents", get);
+    assert_eq!(messages(&body), vec!["**** *************** signed in"]);
+
+    let get = json!({ "logGroupName": "audit", "logStreamName": "web" });
+    let (_, body) = aws(addr, "GetLogEvents", get);
+    assert_eq!(messages(&body), vec![message]);
+
+    // Policies are compiled again when state is loaded.
+    let (_, state) = call(addr, Method::GET, "/_mock/state", "");
+    let (_, restored) = start_server();
+    let (status, _) = call(restored, Method::PUT, "/_mock/state", &state.to_string());
+    assert_eq!(status, 200);
+
+    let get = json!({ "logGroupName": "app", "logStreamName": "web" });
+    let (_, body) = aws(restored, "GetLogEvents", get);
+    assert_eq!(messages(&body), vec!["**** *************** signed in"]);
+}
+
+#[test]
+fn mask_tailed_events() {
+    let (server, addr) = start_server();
+    server.set_live_tail_timeout(Duration::from_secs(1));
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    let stream = json!({ "logGroupName": "app", "logStreamName": "web" });
+    aws(addr, "CreateLogStream", stream);
+
+    let document = policy(&[EMAIL], json!([]));
+    let put = json!({ "logGroupIdentifier": "app", "policyDocument": document });
+    assert_eq!(aws(addr, "PutDataProtectionPolicy", put).0, 200);
+
+    let uri = format!("http://{}/_mock/tail?group=app", addr);
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let res = rt
+        .block_on(Client::new().get(uri.parse().unwrap()))
+        .unwrap();
+    let mut feed = res.into_body().wait();
+    feed.next().unwrap().unwrap();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+
+        let events = json!([{ "timestamp": now(), "message": "user bob@example.com signed in" }]);
+        let put = json!({ "logGroupName": "app", "logStreamName": "web", "logEvents": events });
+        aws(addr, "PutLogEvents", put);
+    });
+
+    let start = json!({ "logGroupIdentifiers": ["app"] });
+    let (status, body) = raw(addr, "StartLiveTail", start);
+    assert_eq!(status, 200);
+    let frames = String::from_utf8_lossy(&body);
+    assert!(
+        frames.contains("user *************** signed in"),
+        "{}",
+        frames
+    );
+    assert!(!frames.contains("example.com"));
+
+    let line = feed.next().unwrap().unwrap();
+    let line = String::from_utf8_lossy(&line);
+    assert!(line.contains("user *************** signed in"), "{}", line);
+}
+
+fn messages(body: &Value) -> Vec<String> {
+    body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["message"].as_str().unwrap().to_string())
+        .collect()
+}
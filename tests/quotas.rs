@@ -0,0 +1,101 @@
+use mockwatchlogs::quotas::{Limiter, Quota, Scope};
+use mockwatchlogs::MockServer;
+use rusoto_logs::{CloudWatchLogs, CreateLogGroupRequest};
+use serde_json::json;
+
+mod common;
+use common::{client, serve};
+
+const WEB_1: &[u8] = br#"{"logGroupName":"app","logStreamName":"web-1"}"#;
+const WEB_2: &[u8] = br#"{"logGroupName":"app","logStreamName":"web-2"}"#;
+const PUT: &str = "Logs_20140328.PutLogEvents";
+
+#[test]
+fn parse_quota() {
+    let quota: Quota = "PutLogEvents/stream=10".parse().unwrap();
+    assert_eq!(quota.action, "PutLogEvents");
+    assert_eq!(quota.scope, Scope::Stream);
+    assert_eq!(quota.tps, 10.0);
+
+    let quota: Quota = "DescribeLogGroups=2".parse().unwrap();
+    assert_eq!(quota.scope, Scope::Account);
+
+    assert!("PutLogEvents/group=1".parse::<Quota>().is_err());
+    assert!("PutLogEvents".parse::<Quota>().is_err());
+    assert!("PutLogEvents=0".parse::<Quota>().is_err());
+    assert!("PutLogEvents=-1".parse::<Quota>().is_err());
+    assert!("PutLogEvents=NaN".parse::<Quota>().is_err());
+    assert!("PutLogEvents=inf".parse::<Quota>().is_err());
+
+    let quota = json!({ "action": "PutLogEvents", "tps": -5.0 });
+    assert!(serde_json::from_value::<Quota>(quota).is_err());
+}
+
+#[test]
+fn per_stream() {
+    let mut limiter = Limiter::default();
+    limiter.set(vec!["PutLogEvents/stream=5".parse().unwrap()]);
+
+    for _ in 0..5 {
+        assert!(limiter.check(PUT, WEB_1, 0));
+    }
+    assert!(!limiter.check(PUT, WEB_1, 0));
+
+    // Other streams and actions have their own budget.
+    assert!(limiter.check(PUT, WEB_2, 0));
+    assert!(limiter.check("Logs_20140328.GetLogEvents", WEB_1, 0));
+
+    // A fifth of a second refills one request.
+    assert!(limiter.check(PUT, WEB_1, 200));
+    assert!(!limiter.check(PUT, WEB_1, 200));
+}
+
+#[test]
+fn idle_buckets_are_dropped() {
+    let mut limiter = Limiter::default();
+    limiter.set(vec!["PutLogEvents/stream=5".parse().unwrap()]);
+
+    for _ in 0..5 {
+        assert!(limiter.check(PUT, WEB_1, 900));
+    }
+
+    // Dropping the full buckets at 1000 keeps the one still refilling.
+    assert!(limiter.check(PUT, WEB_2, 1000));
+    assert!(!limiter.check(PUT, WEB_1, 1000));
+    assert!(limiter.check(PUT, WEB_1, 1100));
+}
+
+#[test]
+fn rejected_requests_do_not_count() {
+    let mut limiter = Limiter::default();
+    limiter.set(vec![
+        "PutLogEvents/stream=1".parse().unwrap(),
+        "PutLogEvents=2".parse().unwrap(),
+    ]);
+
+    assert!(limiter.check(PUT, WEB_1, 0));
+    assert!(!limiter.check(PUT, WEB_1, 0));
+    assert!(limiter.check(PUT, WEB_2, 0));
+}
+
+#[test]
+fn throttled_over_http() {
+    let server = MockServer::new();
+    server.clock().freeze();
+    server.set_quotas(vec!["CreateLogGroup=1".parse().unwrap()]);
+    let client = client(serve(&server));
+
+    let create = |name: &str| {
+        let req = CreateLogGroupRequest {
+            log_group_name: name.into(),
+            ..Default::default()
+        };
+        client.create_log_group(req).sync()
+    };
+
+    assert!(create("a").is_ok());
+    assert!(create("b").is_err());
+
+    server.clock().advance(1000);
+    assert!(create("b").is_ok());
+}
@@ -0,0 +1,98 @@
+use futures::{Future, Stream};
+use hyper::{Body, Client, Method, Request};
+use mockwatchlogs::recording;
+use mockwatchlogs::MockServer;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+mod common;
+use common::serve;
+
+#[test]
+fn record_and_replay() {
+    let path = recording_path("replay");
+    let mut server = MockServer::new();
+    server.record(&path).unwrap();
+    // Keep ingestion times identical between the two runs.
+    server.clock().freeze();
+    let addr = serve(&server);
+
+    let now = server.clock().now();
+    aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    aws(
+        addr,
+        "CreateLogStream",
+        json!({ "logGroupName": "app", "logStreamName": "web-1" }),
+    );
+    aws(
+        addr,
+        "PutLogEvents",
+        json!({
+            "logGroupName": "app",
+            "logStreamName": "web-1",
+            "logEvents": [{ "timestamp": now, "message": "hello" }]
+        }),
+    );
+    let (status, _) = aws(addr, "CreateLogGroup", json!({ "logGroupName": "app" }));
+    assert_eq!(status, 400);
+    aws(
+        addr,
+        "GetLogEvents",
+        json!({ "logGroupName": "app", "logStreamName": "web-1" }),
+    );
+
+    let exchanges = recording::read(&path).unwrap();
+    assert_eq!(exchanges.len(), 5);
+    assert_eq!(
+        exchanges[0].target,
+        Some("Logs_20140328.CreateLogGroup".into())
+    );
+    assert_eq!(exchanges[3].status, 400);
+    assert_eq!(exchanges[0].headers["authorization"], "<redacted>");
+
+    let replayed = MockServer::new();
+    assert_eq!(replayed.replay(&path).unwrap(), vec![]);
+    assert_eq!(replayed.group("app").stream("web-1").events().len(), 1);
+
+    // Replaying on top of the existing state gets different answers.
+    let mismatches = replayed.replay(&path).unwrap();
+    assert_eq!(mismatches[0].index, 1);
+    assert_eq!(mismatches[0].expected_status, 200);
+    assert_eq!(mismatches[0].actual_status, 400);
+}
+
+fn recording_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "mockwatchlogs-{}-{}.jsonl",
+        std::process::id(),
+        name
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Like `common::aws`, but signed the way SDKs sign requests.
+fn aws(addr: SocketAddr, action: &str, body: Value) -> (u16, Value) {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri(format!("http://{}/", addr))
+        .header("X-Amz-Target", format!("Logs_20140328.{}", action))
+        .header("Authorization", "AWS4-HMAC-SHA256 Credential=secret")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let client = Client::new();
+    let fut = client.request(req).and_then(|res| {
+        let status = res.status().as_u16();
+        res.into_body().concat2().map(move |body| {
+            let body = serde_json::from_slice(&body[..]).unwrap_or(Value::Null);
+            (status, body)
+        })
+    });
+
+    tokio::runtime::current_thread::Runtime::new()
+        .unwrap()
+        .block_on(fut)
+        .unwrap()
+}
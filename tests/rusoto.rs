@@ -1,14 +1,16 @@
-use rusoto_core::Region;
+use hyper::Method;
 use rusoto_logs::{
-    CloudWatchLogs, CloudWatchLogsClient, CreateLogGroupRequest, CreateLogStreamRequest,
-    DescribeLogGroupsRequest, DescribeLogStreamsRequest, GetLogEventsRequest, InputLogEvent,
-    LogGroup, PutLogEventsRequest,
+    CloudWatchLogs, CreateLogGroupRequest, CreateLogStreamRequest, DescribeLogGroupsRequest,
+    DescribeLogStreamsRequest, GetLogEventsRequest, InputLogEvent, LogGroup, PutLogEventsRequest,
 };
 use std::default::Default;
 
+mod common;
+use common::{client, request, start_server};
+
 #[test]
 fn describe_group() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let req = CreateLogGroupRequest {
@@ -28,6 +30,8 @@ fn describe_group() {
         groups,
         vec![LogGroup {
             log_group_name: Some("test-group".into()),
+            metric_filter_count: Some(0),
+            stored_bytes: Some(0),
             ..Default::default()
         }]
     );
@@ -35,7 +39,7 @@ fn describe_group() {
 
 #[test]
 fn group_not_found() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let mut desc_streams_req = DescribeLogStreamsRequest::default();
@@ -49,7 +53,7 @@ fn group_not_found() {
 
 #[test]
 fn group_found() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let req = CreateLogGroupRequest {
@@ -72,7 +76,7 @@ fn group_found() {
 
 #[test]
 fn stream_found() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group".to_string();
@@ -109,7 +113,7 @@ fn stream_found() {
 
 #[test]
 fn create_group() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let req = CreateLogGroupRequest {
@@ -122,7 +126,7 @@ fn create_group() {
 
 #[test]
 fn create_stream() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group-1".to_string();
@@ -143,7 +147,7 @@ fn create_stream() {
 
 #[test]
 fn put_logs_empty() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group".to_string();
@@ -189,7 +193,7 @@ fn put_logs_empty() {
 
 #[test]
 fn put_logs_non_empty() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group".to_string();
@@ -240,7 +244,7 @@ fn put_logs_non_empty() {
 
 #[test]
 fn get_logs_empty() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group".to_string();
@@ -298,7 +302,7 @@ fn get_logs_empty() {
 
 #[test]
 fn get_logs_non_empty() {
-    let addr = start_server();
+    let (_, addr) = start_server();
     let client = client(addr);
 
     let group_name = "test-group".to_string();
@@ -361,38 +365,104 @@ fn get_logs_non_empty() {
     assert_eq!(message, "hello world".to_string());
 }
 
-fn client(addr: SocketAddr) -> impl CloudWatchLogs {
-    let endpoint = format!("http://localhost:{}", addr.port());
-    let region = Region::Custom {
-        name: "mockwatchlogs".into(),
-        endpoint,
+#[test]
+fn get_logs_paginate() {
+    let (_, addr) = start_server();
+    let client = client(addr);
+
+    let group_name = "test-group".to_string();
+    let req = CreateLogGroupRequest {
+        log_group_name: group_name.clone(),
+        ..Default::default()
     };
 
-    CloudWatchLogsClient::new(region)
-}
+    client.create_log_group(req).sync().unwrap();
 
-fn start_server() -> SocketAddr {
-    use mockwatchlogs::serve;
-    let addr = next_addr();
+    let req = CreateLogStreamRequest {
+        log_group_name: group_name.clone(),
+        log_stream_name: "test-log-stream".into(),
+    };
 
-    std::thread::spawn(move || {
-        use tokio::runtime::current_thread;
-        let serve = serve(addr);
+    client.create_log_stream(req).sync().unwrap();
 
-        current_thread::run(serve);
-    });
+    // Deliberately out of order, storage keeps them sorted by timestamp.
+    let base = chrono::Utc::now().timestamp_millis();
+    let logs = vec![3, 1, 4, 0, 2]
+        .into_iter()
+        .map(|i| InputLogEvent {
+            message: format!("event {}", i),
+            timestamp: base + i,
+        })
+        .collect();
 
-    std::thread::sleep(std::time::Duration::from_millis(100));
+    let req = PutLogEventsRequest {
+        log_events: logs,
+        log_group_name: group_name.clone(),
+        log_stream_name: "test-log-stream".to_string(),
+        sequence_token: None,
+    };
+    client.put_log_events(req).sync().unwrap();
+
+    let mut messages = Vec::new();
+    let mut next_token = None;
+
+    loop {
+        let req = GetLogEventsRequest {
+            log_stream_name: "test-log-stream".to_string(),
+            log_group_name: group_name.clone(),
+            start_from_head: Some(true),
+            limit: Some(2),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let res = client.get_log_events(req).sync().unwrap();
+        let events = res.events.unwrap();
+
+        if events.is_empty() {
+            // The end of the stream hands back the token that was passed in.
+            assert_eq!(res.next_forward_token, next_token);
+            break;
+        }
+
+        messages.extend(events.into_iter().map(|e| e.message.unwrap()));
+        next_token = res.next_forward_token;
+    }
+
+    assert_eq!(
+        messages,
+        vec!["event 0", "event 1", "event 2", "event 3", "event 4"]
+    );
 
-    addr
+    let req = GetLogEventsRequest {
+        log_stream_name: "test-log-stream".to_string(),
+        log_group_name: group_name.clone(),
+        start_time: Some(base + 1),
+        end_time: Some(base + 4),
+        ..Default::default()
+    };
+
+    let res = client.get_log_events(req).sync().unwrap();
+    let messages = res
+        .events
+        .unwrap()
+        .into_iter()
+        .map(|e| e.message.unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages, vec!["event 1", "event 2", "event 3"]);
 }
 
-use std::net::SocketAddr;
-use std::sync::atomic::{AtomicUsize, Ordering};
-static NEXT_PORT: AtomicUsize = AtomicUsize::new(1234);
-pub fn next_addr() -> SocketAddr {
-    use std::net::{IpAddr, Ipv4Addr};
+#[test]
+fn malformed_request() {
+    let (_, addr) = start_server();
+
+    for body in &["", "{", "{\"logGroupName\": 7}"] {
+        let target = Some("Logs_20140328.CreateLogGroup");
+        let (status, body) = request(addr, Method::POST, "/", target, body);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
-    let port = NEXT_PORT.fetch_add(1, Ordering::AcqRel) as u16;
-    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port)
+        assert_eq!(status, 400);
+        assert_eq!(body["__type"], "SerializationException");
+    }
 }
@@ -0,0 +1,297 @@
+//! Behaviour every `Storage` backend must share.
+
+use mockwatchlogs::limits::{event_size, Usage};
+use mockwatchlogs::streams::{
+    lock, Evicted, Group, InMemory, LogEvent, Storage, StorageError, Stream, DAY,
+};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[test]
+fn in_memory() {
+    conformance(&InMemory::default());
+}
+
+#[test]
+fn concurrent_writers() {
+    let storage = Arc::new(InMemory::default());
+    storage.create_group(group("app"), None).unwrap();
+
+    let writers = (0..4)
+        .map(|i| {
+            let storage = storage.clone();
+            let name = format!("web-{}", i);
+            storage.create_stream("app", stream(&name)).unwrap();
+
+            thread::spawn(move || {
+                for n in 0..100 {
+                    let event = LogEvent {
+                        message: format!("{} {}", name, n),
+                        timestamp: n,
+                        ingestion_time: n,
+                    };
+                    storage.put_events("app", &name, vec![event]).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    for i in 0..4 {
+        let name = format!("web-{}", i);
+        let events = storage.events("app", &name, None, None).unwrap();
+        assert_eq!(events.len(), 100);
+    }
+}
+
+#[test]
+fn concurrent_group_creates() {
+    let storage = Arc::new(InMemory::default());
+
+    let creators = (0..8)
+        .map(|i| {
+            let storage = storage.clone();
+            thread::spawn(move || {
+                let name = format!("group-{}", i);
+                storage.create_group(group(&name), Some(3)).is_ok()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let created = creators
+        .into_iter()
+        .map(|creator| creator.join().unwrap())
+        .filter(|&created| created)
+        .count();
+    assert_eq!(created, 3);
+    assert_eq!(storage.groups().len(), 3);
+}
+
+fn conformance(storage: &dyn Storage) {
+    groups(storage);
+    storage.clear();
+    streams(storage);
+    storage.clear();
+    events(storage);
+    storage.clear();
+    usage(storage);
+    storage.clear();
+    eviction(storage);
+    storage.clear();
+    assert!(storage.groups().is_empty());
+}
+
+fn groups(storage: &dyn Storage) {
+    storage.create_group(group("b"), None).unwrap();
+    storage.create_group(group("a"), None).unwrap();
+    assert_eq!(
+        storage.create_group(group("a"), None),
+        Err(StorageError::GroupAlreadyExists)
+    );
+
+    match storage.create_group(group("c"), Some(2)) {
+        Err(StorageError::LimitExceeded(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let names = storage
+        .groups()
+        .iter()
+        .map(|g| lock(g).name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b"]);
+
+    lock(&storage.group("a").unwrap()).retention_in_days = Some(7);
+    assert_eq!(
+        lock(&storage.group("a").unwrap()).retention_in_days,
+        Some(7)
+    );
+
+    assert_eq!(lock(&storage.delete_group("b").unwrap()).name, "b");
+    assert!(storage.group("b").is_none());
+    assert_eq!(
+        storage.delete_group("b").unwrap_err(),
+        StorageError::GroupNotFound
+    );
+}
+
+fn streams(storage: &dyn Storage) {
+    assert_eq!(
+        storage.create_stream("app", stream("web-1")),
+        Err(StorageError::GroupNotFound)
+    );
+
+    storage.create_group(group("app"), None).unwrap();
+    storage.create_stream("app", stream("web-2")).unwrap();
+    storage.create_stream("app", stream("web-1")).unwrap();
+    assert_eq!(
+        storage.create_stream("app", stream("web-1")),
+        Err(StorageError::StreamAlreadyExists)
+    );
+
+    let names = storage
+        .streams("app")
+        .unwrap()
+        .iter()
+        .map(|s| lock(s).name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["web-1", "web-2"]);
+
+    storage.delete_stream("app", "web-2").unwrap();
+    assert_eq!(
+        storage.stream("app", "web-2").unwrap_err(),
+        StorageError::StreamNotFound
+    );
+}
+
+fn events(storage: &dyn Storage) {
+    storage.create_group(group("app"), None).unwrap();
+    storage.create_stream("app", stream("web-1")).unwrap();
+
+    let events = (0..5)
+        .map(|i| LogEvent {
+            message: format!("event {}", i),
+            timestamp: i * 10,
+            ingestion_time: 0,
+        })
+        .collect();
+    storage.put_events("app", "web-1", events).unwrap();
+
+    let all = storage.events("app", "web-1", None, None).unwrap();
+    assert_eq!(all.len(), 5);
+
+    let range = storage.events("app", "web-1", Some(10), Some(30)).unwrap();
+    let messages = range.iter().map(|e| e.message.as_str()).collect::<Vec<_>>();
+    assert_eq!(messages, vec!["event 1", "event 2"]);
+
+    assert_eq!(
+        storage.put_events("app", "missing", Vec::new()),
+        Err(StorageError::StreamNotFound)
+    );
+}
+
+fn usage(storage: &dyn Storage) {
+    assert_eq!(storage.usage(), Usage::default());
+    assert_eq!(
+        storage.stream_names("app").unwrap_err(),
+        StorageError::GroupNotFound
+    );
+
+    storage.create_group(group("app"), None).unwrap();
+    storage.create_group(group("db"), None).unwrap();
+    storage.create_stream("app", stream("web-2")).unwrap();
+    storage.create_stream("app", stream("web-1")).unwrap();
+    storage
+        .put_events("app", "web-1", vec![event("hello", 1), event("world", 2)])
+        .unwrap();
+
+    assert_eq!(storage.stream_names("app").unwrap(), vec!["web-1", "web-2"]);
+
+    let bytes = event_size("hello") + event_size("world");
+    let app = Usage {
+        stored_bytes: bytes,
+        groups: 1,
+        streams: 2,
+        events: 2,
+    };
+    assert_eq!(storage.group_usage("app").unwrap(), app);
+    assert_eq!(
+        storage.usage(),
+        Usage {
+            groups: 2,
+            ..app.clone()
+        }
+    );
+
+    storage.delete_stream("app", "web-1").unwrap();
+    assert_eq!(storage.usage().events, 0);
+    assert_eq!(storage.usage().stored_bytes, 0);
+    assert_eq!(storage.evict_oldest(), None);
+
+    // A group can arrive with its streams, as it does when restored.
+    let mut restored = group("restored");
+    let mut web = stream("web");
+    web.logs.extend(vec![event("hello", 1)]);
+    restored
+        .streams
+        .insert("web".into(), Arc::new(Mutex::new(web)));
+    storage.create_group(restored, None).unwrap();
+    assert_eq!(
+        storage.usage(),
+        Usage {
+            stored_bytes: event_size("hello"),
+            groups: 3,
+            streams: 2,
+            events: 1,
+        }
+    );
+
+    storage.delete_group("restored").unwrap();
+    storage.delete_group("app").unwrap();
+    assert_eq!(
+        storage.usage(),
+        Usage {
+            groups: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(storage.evict_oldest(), None);
+}
+
+fn eviction(storage: &dyn Storage) {
+    assert_eq!(storage.evict_oldest(), None);
+
+    storage.create_group(group("app"), None).unwrap();
+    storage.create_stream("app", stream("web-1")).unwrap();
+    storage.create_stream("app", stream("web-2")).unwrap();
+    storage
+        .put_events("app", "web-1", vec![event("b", 20), event("d", 40)])
+        .unwrap();
+    storage
+        .put_events("app", "web-2", vec![event("c", 30), event("a", 10)])
+        .unwrap();
+
+    assert_eq!(
+        storage.evict_oldest(),
+        Some(Evicted {
+            group: "app".into(),
+            stream: "web-2".into(),
+            event: event("a", 10),
+        })
+    );
+    assert_eq!(storage.evict_oldest().unwrap().event, event("b", 20));
+
+    let evicted = storage.evict_stream("app", "web-2", 0).unwrap();
+    assert_eq!(evicted, vec![event("c", 30)]);
+    assert_eq!(storage.usage().events, 1);
+
+    lock(&storage.group("app").unwrap()).retention_in_days = Some(1);
+    assert_eq!(storage.drop_expired(40 + DAY), 0);
+    assert_eq!(storage.drop_expired(41 + DAY), 1);
+    assert_eq!(storage.usage().events, 0);
+}
+
+fn event(message: &str, timestamp: i64) -> LogEvent {
+    LogEvent {
+        message: message.into(),
+        timestamp,
+        ingestion_time: 0,
+    }
+}
+
+fn group(name: &str) -> Group {
+    Group {
+        name: name.into(),
+        ..Default::default()
+    }
+}
+
+fn stream(name: &str) -> Stream {
+    Stream {
+        name: name.into(),
+        ..Default::default()
+    }
+}
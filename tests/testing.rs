@@ -0,0 +1,115 @@
+use mockwatchlogs::testing::{
+    assert_logged, assert_not_logged, assert_snapshot, wait_for_logged, Events,
+};
+use mockwatchlogs::MockServer;
+use rusoto_logs::{
+    CloudWatchLogs, CreateLogGroupRequest, CreateLogStreamRequest, InputLogEvent,
+    PutLogEventsRequest,
+};
+use std::time::Duration;
+
+mod common;
+use common::{client, start_server};
+
+#[test]
+fn captured_events() {
+    let (server, addr) = start_server();
+    let client = client(addr);
+
+    put(&client, "app", "web-1", &["starting", "started"]);
+
+    let stream = server.group("app").stream("web-1");
+    assert!(stream.exists());
+    assert_eq!(stream.messages(), vec!["starting", "started"]);
+
+    assert_logged(&server.group("app"), |msg| msg.contains("started"));
+    assert_not_logged(&server.group("app"), |msg| msg.contains("panic"));
+    assert_snapshot(
+        &stream,
+        r#"
+        starting
+        started
+        "#,
+    );
+}
+
+#[test]
+fn wait_for_events() {
+    let (server, addr) = start_server();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        put(&client(addr), "app", "worker", &["one", "two"]);
+    });
+
+    let events = server
+        .group("app")
+        .stream("worker")
+        .wait_for(2, Duration::from_secs(5))
+        .unwrap();
+
+    assert_eq!(events.len(), 2);
+}
+
+#[test]
+fn wait_for_times_out() {
+    let (server, _) = start_server();
+
+    let err = server
+        .group("missing")
+        .stream("none")
+        .wait_for(1, Duration::from_millis(50))
+        .unwrap_err();
+
+    assert_eq!(err.expected, 1);
+    assert_eq!(err.found, 0);
+}
+
+#[test]
+fn wait_for_logged_reports_what_it_saw() {
+    let (server, addr) = start_server();
+    put(&client(addr), "app", "web-1", &["starting", "started"]);
+
+    let err = wait_for_logged(
+        &server.group("app"),
+        |msg| msg.contains("panic"),
+        Duration::from_millis(50),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.found, 2);
+    assert_eq!(err.messages, vec!["starting", "started"]);
+    assert!(err.to_string().contains("none of 2 matched"));
+}
+
+fn put(client: &impl CloudWatchLogs, group: &str, stream: &str, messages: &[&str]) {
+    let req = CreateLogGroupRequest {
+        log_group_name: group.into(),
+        ..Default::default()
+    };
+    client.create_log_group(req).sync().unwrap();
+
+    let req = CreateLogStreamRequest {
+        log_group_name: group.into(),
+        log_stream_name: stream.into(),
+    };
+    client.create_log_stream(req).sync().unwrap();
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let log_events = messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| InputLogEvent {
+            message: message.to_string(),
+            timestamp: now + i as i64,
+        })
+        .collect();
+
+    let req = PutLogEventsRequest {
+        log_events,
+        log_group_name: group.into(),
+        log_stream_name: stream.into(),
+        sequence_token: None,
+    };
+    client.put_log_events(req).sync().unwrap();
+}
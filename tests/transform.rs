@@ -0,0 +1,138 @@
+use hyper::Method;
+use serde_json::{json, Value};
+
+mod common;
+use common::{aws, call, now, put, request, start_server};
+
+fn config() -> Value {
+    json!([
+        { "grok": { "match": "%{LOGLEVEL:level} %{WORD:service} took %{INT:latency:int}ms %{GREEDYDATA:body}" } },
+        { "parseJSON": { "source": "body", "destination": "request" } },
+        { "lowerCaseString": { "withKeys": ["level"] } },
+        { "renameKeys": { "entries": [{ "key": "service", "renameTo": "app.service" }] } },
+        { "copyValue": { "entries": [{ "source": "request.user", "target": "user" }] } },
+        { "addKeys": { "entries": [{ "key": "env", "value": "dev" }] } },
+        { "deleteKeys": { "withKeys": ["body", "request.token"] } },
+    ])
+}
+
+#[test]
+fn test_transformer() {
+    let (_, addr) = start_server();
+
+    let test = json!({
+        "transformerConfig": config(),
+        "logEventMessages": [
+            "ERROR api took 12ms {\"user\":\"bob\",\"token\":\"t0k3n\"}",
+            "not what the grok pattern expects",
+        ],
+    });
+    let (status, body) = aws(addr, "TestTransformer", test);
+    assert_eq!(status, 200, "{}", body);
+
+    let logs = body["transformedLogs"].as_array().unwrap();
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["eventNumber"], 1);
+    let transformed: Value =
+        serde_json::from_str(logs[0]["transformedEventMessage"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        transformed,
+        json!({
+            "level": "error",
+            "app": { "service": "api" },
+            "latency": 12,
+            "request": { "user": "bob" },
+            "user": "bob",
+            "env": "dev",
+        })
+    );
+    assert!(logs[1]["transformedEventMessage"].is_null());
+
+    let invalid = vec![
+        json!([]),
+        json!([{ "addKeys": { "entries": [{ "key": "a", "value": "b" }] } }]),
+        json!([{ "grok": { "match": "%{NOSUCHPATTERN:x}" } }]),
+        json!([{ "parseJSON": {} }, { "uppercaseEverything": {} }]),
+    ];
+    for config in invalid {
+        let test = json!({ "transformerConfig": config, "logEventMessages": ["{}"] });
+        let (status, body) = aws(addr, "TestTransformer", test);
+        assert_eq!(status, 400, "{}", config);
+        assert_eq!(body["__type"], "InvalidParameterException");
+    }
+}
+
+#[test]
+fn transform_on_ingest() {
+    let (_, addr) = start_server();
+    put(addr, &["INFO web took 3ms {\"user\":\"amy\"}"], now());
+
+    let put_transformer = json!({ "logGroupIdentifier": "app", "transformerConfig": config() });
+    let (status, body) = aws(addr, "PutTransformer", put_transformer);
+    assert_eq!(status, 200, "{}", body);
+
+    let get = json!({ "logGroupIdentifier": "app" });
+    let (_, body) = aws(addr, "GetTransformer", get.clone());
+    assert_eq!(body["transformerConfig"], config());
+    assert!(body["creationTime"].is_i64());
+
+    put(
+        addr,
+        &["WARN api took 7ms {\"user\":\"bob\"}", "unparseable"],
+        now(),
+    );
+
+    let events = json!({ "logGroupName": "app", "logStreamName": "web", "startFromHead": true });
+    let (_, body) = aws(addr, "GetLogEvents", events);
+    let messages: Vec<&str> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["message"].as_str().unwrap())
+        .collect();
+
+    // Events from before the transformer, and those it cannot parse, are
+    // kept as sent.
+    assert_eq!(messages[0], "INFO web took 3ms {\"user\":\"amy\"}");
+    assert_eq!(messages[2], "unparseable");
+    let transformed: Value = serde_json::from_str(messages[1]).unwrap();
+    assert_eq!(transformed["level"], "warn");
+    assert_eq!(transformed["latency"], 7);
+    assert_eq!(transformed["user"], "bob");
+
+    // Ingested bytes are counted as stored, after transforming.
+    let (_, metrics) = request(addr, Method::GET, "/metrics", None, "");
+    let metrics = String::from_utf8(metrics).unwrap();
+    let metric = |name: &str| {
+        let prefix = format!("mockwatchlogs_{}{{group=\"app\"}} ", name);
+        metrics
+            .lines()
+            .find(|line| line.starts_with(&prefix))
+            .map(|line| line[prefix.len()..].to_string())
+    };
+    assert!(metric("stored_bytes").is_some(), "{}", metrics);
+    assert_eq!(metric("ingested_bytes_total"), metric("stored_bytes"));
+
+    // The transformer is compiled again when state is loaded.
+    let (_, state) = call(addr, Method::GET, "/_mock/state", "");
+    let (_, restored) = start_server();
+    let (status, _) = call(restored, Method::PUT, "/_mock/state", &state.to_string());
+    assert_eq!(status, 200);
+    put(restored, &["ERROR db took 9ms {\"user\":\"cy\"}"], now());
+
+    let events = json!({ "logGroupName": "app", "logStreamName": "web" });
+    let (_, body) = aws(restored, "GetLogEvents", events);
+    let last = body["events"].as_array().unwrap().last().unwrap();
+    let transformed: Value = serde_json::from_str(last["message"].as_str().unwrap()).unwrap();
+    assert_eq!(transformed["user"], "cy");
+
+    assert_eq!(aws(addr, "DeleteTransformer", get.clone()).0, 200);
+    assert_eq!(aws(addr, "DeleteTransformer", get.clone()).0, 404);
+    let (_, body) = aws(addr, "GetTransformer", get);
+    assert!(body["transformerConfig"].is_null());
+
+    let ia = json!({ "logGroupName": "noisy", "logGroupClass": "INFREQUENT_ACCESS" });
+    aws(addr, "CreateLogGroup", ia);
+    let put_transformer = json!({ "logGroupIdentifier": "noisy", "transformerConfig": config() });
+    assert_eq!(aws(addr, "PutTransformer", put_transformer).0, 400);
+}